@@ -155,7 +155,7 @@ _CC_STATIC_ASSERT(CC128M_HIGHEST_PERM < CC128M_FIELD_ALL_PERMS_MAX_VALUE, "permi
 _CC_STATIC_ASSERT((CC128M_HIGHEST_PERM << 1) > CC128M_FIELD_ALL_PERMS_MAX_VALUE, "all permission bits should be used");
 
 #define CC128M_PERMS_MASK UINT64_C(0x3FFFF) /* Includes SW perms */
-#define CC128M_PERMS_ALL (CC128M_PERMS_MASK & ~CC128M_UPERMS_ALL)
+#define CC128M_PERMS_ALL (CC128M_PERMS_MASK & ~CC128M_PERM_SW_ALL)
 enum { _CC_N(PERMS_RESERVED_ONES) = 0 };
 #define CC128M_ENCODED_INFINITE_PERMS(lvbits) _CC_ENCODE_FIELD(CC128M_PERMS_MASK, ALL_PERMS)
 _CC_STATIC_ASSERT_SAME(CC128M_PERMS_MASK, CC128M_FIELD_ALL_PERMS_MAX_VALUE);
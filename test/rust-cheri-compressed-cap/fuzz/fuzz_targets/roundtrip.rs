@@ -0,0 +1,26 @@
+//! Applies a random sequence of operations to a random capability,
+//! checking that every intermediate capability survives a compress/decompress round trip unchanged.
+//!
+//! Run with `cargo fuzz run roundtrip` from the crate directory.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_cheri_compressed_cap::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+use rust_cheri_compressed_cap::fuzzing::CapOp;
+use rust_cheri_compressed_cap::{CcxCap, CompressedCapability};
+
+fn check_ops<T: CompressedCapability>(mut cap: CcxCap<T>, ops: &[CapOp<T::Addr>]) {
+    for op in ops {
+        cap = op.apply(&cap);
+        let (tag, [cursor, pesbt]) = cap.reg_representation();
+        assert_eq!(T::decompress_raw(pesbt, cursor, tag), cap, "after {:?}", op);
+        let (tag, [cursor, pesbt]) = cap.mem_representation();
+        assert_eq!(T::decompress_mem(pesbt, cursor, tag), cap, "after {:?}", op);
+    }
+}
+
+fuzz_target!(|input: (CcxCap<Cc64>, Vec<CapOp<u32>>, CcxCap<Cc128>, Vec<CapOp<u64>>)| {
+    let (cap64, ops64, cap128, ops128) = input;
+    check_ops(cap64, &ops64);
+    check_ops(cap128, &ops128);
+});
@@ -0,0 +1,385 @@
+//! Rust-side description of the floating-point bounds encoding (the "EBT" field of [CcxCap::cr_pesbt](crate::CcxCap)).
+//!
+//! All of the supported formats share the same overall shape:
+//! `B` occupies the bottom `MW` bits of the pesbt, `T` (minus its top two bits, which are inferred) sits directly above it,
+//! followed by an optional length MSB and the internal exponent flag.
+//! When the internal exponent is in use the exponent is split across the low bits of `B` (low part) and `T` (high part).
+//! The differences between formats are captured in [EbtLayout].
+//!
+//! With the `verify-ffi` feature enabled, every call to the FFI
+//! [CompressedCapability::extract_bounds_bits](crate::CompressedCapability::extract_bounds_bits)
+//! is checked against [EbtLayout::decode], as the getters are checked in [crate::fields].
+
+use crate::CcxBoundsBits;
+
+/// How the exponent is stored in the pesbt when the internal exponent flag is set.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ExponentEncoding {
+    /// Stored as-is (CHERIv9)
+    Raw,
+    /// Stored bitwise-inverted (Morello)
+    Inverted,
+    /// Stored as `max_exponent - E` (RISC-V Y)
+    SubtractedFrom(u8),
+}
+
+/// Bounds encodings which `compute_base_top` handles before the general algorithm,
+/// equivalent to the C `compute_base_top_special_cases`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum BaseTopSpecialCases {
+    /// No special cases (CHERIv9)
+    None,
+    /// Exponents above the maximum cover the whole address space (Morello)
+    ExponentAboveMax,
+    /// Malformed bounds decode to an empty region at zero (RISC-V Y)
+    Malformed,
+}
+
+/// Describes where the bounds fields live inside the pesbt for a given format, and how they decode to a base and top.
+///
+/// Mirrors the `_CC_FIELD` definitions of `EBT`, `EXP_ZERO_*`, `EXP_NONZERO_*` and `EXPONENT_*_PART` in the C headers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct EbtLayout {
+    /// Width of the `B` field (`_CC_MANTISSA_WIDTH`)
+    pub mantissa_width: u32,
+    /// Width of each half of the split exponent (`EXPONENT_LOW_PART` and `EXPONENT_HIGH_PART`)
+    pub exp_part_width: u32,
+    /// If true the flag bit stores `!IE`, i.e. it is an "exponent zero" flag
+    pub ie_inverted: bool,
+    pub exponent: ExponentEncoding,
+    /// If true there is a `LEN_MSB` bit between `T` and the IE flag (`_CC_N(USES_LEN_MSB)`).
+    /// It holds the MSB of the length without an internal exponent, and the MSB of the exponent with one.
+    pub len_msb: bool,
+    /// `_CC_ADDR_WIDTH`
+    pub addr_width: u32,
+    /// The number of low bits of the cursor used to compute the bounds, which are sign-extended (see `_CC_CURSOR_MASK`)
+    pub bounds_addr_width: u32,
+    /// `_CC_MAX_EXPONENT`
+    pub max_exponent: u8,
+    /// [CompressedCapability::USES_V9_CORRECTION_FACTORS](crate::CompressedCapability::USES_V9_CORRECTION_FACTORS)
+    pub v9_correction: bool,
+    pub special_cases: BaseTopSpecialCases,
+}
+
+impl EbtLayout {
+    fn top_width(&self) -> u32 {
+        self.mantissa_width - 2
+    }
+    fn len_msb_shift(&self) -> u32 {
+        self.mantissa_width + self.top_width()
+    }
+    fn ie_shift(&self) -> u32 {
+        self.len_msb_shift() + if self.len_msb { 1 } else { 0 }
+    }
+    fn exponent_width(&self) -> u32 {
+        2 * self.exp_part_width + if self.len_msb { 1 } else { 0 }
+    }
+
+    /// Extract the bounds bits from a pesbt and infer the top two bits of `T`, equivalent to the C `extract_bounds_bits`.
+    ///
+    /// Bits outside the EBT portion are ignored.
+    pub fn decode(&self, pesbt: u64) -> CcxBoundsBits {
+        let mw = self.mantissa_width;
+        let tw = self.top_width();
+        let ew = self.exp_part_width;
+        let mask = |width: u32| (1u64 << width) - 1;
+
+        let ie = ((pesbt >> self.ie_shift()) & 1 != 0) != self.ie_inverted;
+        let len_msb = if self.len_msb { (pesbt >> self.len_msb_shift()) & 1 } else { 0 };
+        let (e, b, mut t, l_msb) = if ie {
+            let e_raw = (pesbt & mask(ew)) | (((pesbt >> mw) & mask(ew)) << ew) | (len_msb << (2 * ew));
+            let e = match self.exponent {
+                ExponentEncoding::Raw => e_raw as u8,
+                ExponentEncoding::Inverted => !e_raw as u8 & mask(self.exponent_width()) as u8,
+                ExponentEncoding::SubtractedFrom(max) => max.wrapping_sub(e_raw as u8),
+            };
+            let b = pesbt & mask(mw) & !mask(ew);
+            let t = (pesbt >> mw) & mask(tw) & !mask(ew);
+            (e, b, t, 1)
+        } else {
+            (0, pesbt & mask(mw), (pesbt >> mw) & mask(tw), len_msb)
+        };
+
+        // Reconstruct the top two bits of T = B + length from the top two bits of B,
+        // the length MSB, and the carry out of the lower bits (implied if T < B there)
+        let l_carry = if t < (b & mask(tw)) { 1 } else { 0 };
+        t |= (((b >> tw) + l_carry + l_msb) & 0b11) << tw;
+        CcxBoundsBits {
+            b: b as u16,
+            t: t as u16,
+            e,
+            ie,
+        }
+    }
+
+    /// Equivalent to the C `bounds_malformed` for RISC-V Y
+    fn bounds_malformed(&self, bits: CcxBoundsBits) -> bool {
+        let mut malformed_lsb = bits.e > self.max_exponent;
+        if self.len_msb {
+            // For MXLEN==32, IE && E == 0 is also malformed
+            malformed_lsb |= bits.e == 0;
+        }
+        let malformed_msb = (bits.e == self.max_exponent && bits.b != 0)
+            || (bits.e == self.max_exponent - 1 && (bits.b >> (self.mantissa_width - 1)) & 1 != 0);
+        bits.ie && (malformed_lsb || malformed_msb)
+    }
+
+    /// Reconstruct the base and top from the bounds bits and the cursor, equivalent to the C `compute_base_top`.
+    ///
+    /// Returns `(base, top)` exactly as `decompress_raw` would store them in `cr_base` and `_cr_top`.
+    /// Base and top are not ordered for some invalid inputs.
+    pub fn compute_base_top(&self, bits: CcxBoundsBits, cursor: u64) -> (u64, u128) {
+        let mw = self.mantissa_width;
+        let addr_width = self.addr_width;
+        let truncate = |value: u64, width: u32| if width >= 64 { value } else { value & ((1 << width) - 1) };
+
+        match self.special_cases {
+            BaseTopSpecialCases::ExponentAboveMax if bits.e > self.max_exponent => return (0, 1 << addr_width),
+            BaseTopSpecialCases::Malformed if self.bounds_malformed(bits) => return (0, 0),
+            _ => {}
+        }
+
+        // Sign-extend the bits of the cursor used for bounds, equivalent to the C `cap_bounds_address`
+        let cursor = if self.bounds_addr_width < addr_width {
+            let unused = 64 - self.bounds_addr_width;
+            truncate((((cursor << unused) as i64) >> unused) as u64, addr_width)
+        } else {
+            cursor
+        };
+
+        // Find which representable region (relative to the one containing R) the cursor, base and top lie in
+        let (e, a_mid, correction_b, correction_t, r) = if self.v9_correction {
+            let e = bits.e.min(self.max_exponent) as u32;
+            let correction_b = bits.b as u64 >> (mw - 3);
+            let r = truncate(correction_b.wrapping_sub(1), 3);
+            (e, truncate(cursor >> (e + mw - 3), 3), correction_b, bits.t as u64 >> (mw - 3), r)
+        } else {
+            let e = bits.e as u32;
+            let r = truncate((bits.b as u64).wrapping_sub(1 << (mw - 2)), mw);
+            (e, truncate(cursor >> e, mw), bits.b as u64, bits.t as u64, r)
+        };
+        let a_hi = (a_mid < r) as i64;
+        let correction_base = (correction_b < r) as i64 - a_hi;
+        let correction_top = (correction_t < r) as i64 - a_hi;
+
+        let a_top_shift = e + mw;
+        let a_top = if a_top_shift >= addr_width { 0 } else { cursor >> a_top_shift };
+        let len_mask = (1u128 << (addr_width + 1)) - 1;
+        // Bits shifted out of the u128 are discarded, as they are in C
+        let region = |correction: i64, mantissa: u16| {
+            let upper = truncate((a_top as i64).wrapping_add(correction) as u64, addr_width) as u128;
+            (((upper << mw) | mantissa as u128) << e) & len_mask
+        };
+        let base = region(correction_base, bits.b);
+        let mut top = region(correction_top, bits.t);
+
+        // If the base and top are more than an address space away from each other, invert the MSB of top.
+        // This corrects for errors that happen when the representable space wraps the address space.
+        // The top bit of base is ignored, as otherwise setbounds/incoffset/etc. can break monotonicity.
+        let base2 = ((base >> (addr_width - 1)) & 1) as u32;
+        let top2 = ((top >> (addr_width - 1)) & 0b11) as u32;
+        if (e as u8) < self.max_exponent - 1 && top2.wrapping_sub(base2) > 1 {
+            top ^= 1 << addr_width;
+        }
+
+        (truncate(base as u64, addr_width), top)
+    }
+
+    /// Encode `bits` into the EBT portion of a pesbt, leaving all other bits zero.
+    ///
+    /// This is the inverse of `extract_bounds_bits`: the low bits of `B` and `T` which are replaced by the exponent
+    /// (if `bits.ie`) and the top two bits of `T` are dropped, as the hardware infers them when decoding.
+    /// Values for which that inference would give a different result do not round-trip.
+    pub fn encode(&self, bits: CcxBoundsBits) -> u64 {
+        let mw = self.mantissa_width;
+        let tw = self.top_width();
+        let ew = self.exp_part_width;
+        let mask = |width: u32| (1u64 << width) - 1;
+
+        let b = bits.b as u64 & mask(mw);
+        let t = bits.t as u64 & mask(mw);
+
+        let mut pesbt = if bits.ie {
+            let e_raw = match self.exponent {
+                ExponentEncoding::Raw => bits.e,
+                ExponentEncoding::Inverted => !bits.e,
+                ExponentEncoding::SubtractedFrom(max) => max.wrapping_sub(bits.e),
+            } as u64
+                & mask(self.exponent_width());
+
+            let mut pesbt = (b & !mask(ew)) | (e_raw & mask(ew));
+            pesbt |= ((t & mask(tw) & !mask(ew)) | ((e_raw >> ew) & mask(ew))) << mw;
+            if self.len_msb {
+                pesbt |= (e_raw >> (2 * ew)) << self.len_msb_shift();
+            }
+            pesbt
+        } else {
+            let mut pesbt = b | ((t & mask(tw)) << mw);
+            if self.len_msb {
+                // Invert the inference in extract_bounds_bits: T[top2] = B[top2] + L_carry + L_msb
+                let l_carry = if (t & mask(tw)) < (b & mask(tw)) { 1 } else { 0 };
+                let l_msb = (t >> tw).wrapping_sub(b >> tw).wrapping_sub(l_carry) & 1;
+                pesbt |= l_msb << self.len_msb_shift();
+            }
+            pesbt
+        };
+
+        if bits.ie != self.ie_inverted {
+            pesbt |= 1 << self.ie_shift();
+        }
+        pesbt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CompressedCapability;
+
+    /// Every pesbt decodes to some bounds bits, and re-encoding them must reproduce the same bounds bits.
+    fn check_roundtrip<T: CompressedCapability>()
+    where
+        T::Addr: num_traits::AsPrimitive<u64>,
+        u64: num_traits::AsPrimitive<T::Addr>,
+    {
+        use num_traits::AsPrimitive;
+
+        let mut rng = crate::testing::Lcg::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..10_000 {
+            let pesbt: T::Addr = rng.next_u64().as_();
+
+            let bits = T::extract_bounds_bits(pesbt);
+            let reencoded = T::extract_bounds_bits(T::encode_bounds_bits(bits));
+            assert_eq!(bits, reencoded, "pesbt = {:#x}", pesbt.as_());
+        }
+    }
+
+    #[test]
+    fn test_encode_bounds_bits_roundtrip() {
+        crate::for_each_cap_format!(|C| {
+            check_roundtrip::<C>();
+        });
+    }
+
+    /// Decoding in Rust must match the C extract_bounds_bits for every pesbt.
+    fn check_decode<T: CompressedCapability>(layout: &super::EbtLayout)
+    where
+        T::Addr: num_traits::AsPrimitive<u64>,
+        u64: num_traits::AsPrimitive<T::Addr>,
+    {
+        use num_traits::AsPrimitive;
+
+        let mut rng = crate::testing::Lcg::new(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..10_000 {
+            let pesbt: T::Addr = rng.next_u64().as_();
+            assert_eq!(
+                layout.decode(pesbt.as_()),
+                T::extract_bounds_bits(pesbt),
+                "pesbt = {:#x}",
+                pesbt.as_()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_matches_c() {
+        use crate::caps::*;
+
+        check_decode::<cheriv9::cc64::Cc64>(&cheriv9::cc64::EBT_LAYOUT);
+        check_decode::<cheriv9::cc128::Cc128>(&cheriv9::cc128::EBT_LAYOUT);
+        check_decode::<morello::Cc128>(&morello::EBT_LAYOUT);
+        check_decode::<rvy::cc64::Cc64>(&rvy::cc64::EBT_LAYOUT);
+        check_decode::<rvy::cc128::Cc128>(&rvy::cc128::EBT_LAYOUT);
+    }
+
+    /// The Rust compute_base_top must give the same base and top as the C decompress_raw,
+    /// both for random encodings and for cursors spanning the representable region of a valid capability.
+    fn check_compute_base_top<T: CompressedCapability>()
+    where
+        T::Addr: num_traits::AsPrimitive<u64>,
+        u64: num_traits::AsPrimitive<T::Addr>,
+    {
+        use num_traits::AsPrimitive;
+
+        let mut rng = crate::testing::Lcg::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..10_000 {
+            let pesbt: T::Addr = rng.next_u64().as_();
+            let cursor: T::Addr = rng.next_u64().as_();
+
+            let cap = T::decompress_raw(pesbt, cursor, false);
+            assert_eq!(
+                T::extract_bounds_bits(pesbt).decode::<T>(cursor),
+                cap.bounds(),
+                "pesbt = {:#x} cursor = {:#x}",
+                pesbt.as_(),
+                cursor.as_()
+            );
+        }
+
+        let cap = T::make_max_perms_cap(0x1_0000.as_(), 0x1_0000.as_(), 0x1_8000u64.as_().into());
+        let bits = cap.extract_bounds_bits();
+        let region = 1u64 << (bits.e as u32 + T::MANTISSA_WIDTH);
+        let step = region / 32;
+        let start = 0x1_0000u64.wrapping_sub(region);
+        for i in 0..96 {
+            let cursor: T::Addr = start.wrapping_add(i * step).as_();
+            let decompressed = T::decompress_raw(cap.cr_pesbt, cursor, false);
+            assert_eq!(bits.decode::<T>(cursor), decompressed.bounds(), "cursor = {:#x}", cursor.as_());
+        }
+    }
+
+    #[test]
+    fn test_compute_base_top_matches_c() {
+        crate::for_each_cap_format!(|C| {
+            check_compute_base_top::<C>();
+        });
+    }
+
+    /// Compares each field of the Rust decoding against cc128_extract_bounds_bits for some hand-picked pesbts
+    #[test]
+    fn test_decode_cc128_fields() {
+        use crate::c_funcs::cc128_extract_bounds_bits;
+        use crate::caps::cheriv9::cc128::{Cc128, EBT_LAYOUT};
+
+        let ie = 1u64 << 26;
+        let pesbts = [
+            0,
+            u64::MAX,
+            ie,
+            (1 << 26) - 1,
+            // The pesbt of the NULL capability
+            0x0000_1fff_fc01_8004,
+            // T = 0x123 (stored), B = 0x456, so T < B and the top bits of T are 0b01
+            (0x123 << 14) | 0x456,
+            // B = 0x3fff, T = 0 (stored): the top bits of T wrap around
+            0x3fff,
+            // E = 0b101_010 split across T and B
+            ie | (0b101 << 14) | 0b010,
+            Cc128::make_max_perms_cap(0, 0, 1 << 64).cr_pesbt,
+            Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000).cr_pesbt,
+            Cc128::make_max_perms_cap(0x1_0000, 0x1_0000, 0x100_0000).cr_pesbt,
+        ];
+        for &pesbt in pesbts.iter() {
+            let c = unsafe { cc128_extract_bounds_bits(pesbt) };
+            let rust = EBT_LAYOUT.decode(pesbt);
+            assert_eq!(rust.b, c.b, "B, pesbt = {:#x}", pesbt);
+            assert_eq!(rust.t, c.t, "T, pesbt = {:#x}", pesbt);
+            assert_eq!(rust.e, c.e, "E, pesbt = {:#x}", pesbt);
+            assert_eq!(rust.ie, c.ie, "IE, pesbt = {:#x}", pesbt);
+        }
+
+        let bits = EBT_LAYOUT.decode((0x123 << 14) | 0x456);
+        assert_eq!((bits.b, bits.t, bits.e, bits.ie), (0x456, 0x1123, 0, false));
+        let bits = EBT_LAYOUT.decode(ie | (0b101 << 14) | 0b010);
+        assert_eq!((bits.e, bits.ie), (0b101_010, true));
+    }
+
+    #[test]
+    fn test_encode_bounds_bits_max_perms() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let bits = cap.extract_bounds_bits();
+        let ebt_mask = (1u64 << 27) - 1;
+        assert_eq!(Cc128::encode_bounds_bits(bits), cap.cr_pesbt & ebt_mask);
+    }
+}
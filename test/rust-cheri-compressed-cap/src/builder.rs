@@ -0,0 +1,193 @@
+//! A builder for capabilities with explicit bounds, cursor, permissions and otype.
+
+use crate::{CcxCap, CompressedCapability, SealError};
+use num_traits::Zero;
+
+/// Reasons [CapBuilder::build] can fail
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The requested top was below the requested base, or above the top of the address space
+    InvalidBounds,
+    /// The requested bounds can't be represented exactly
+    BoundsNotExact,
+    /// The requested cursor isn't representable with the requested bounds
+    CursorNotRepresentable,
+    /// The requested permissions include bits which aren't architectural permissions in this format
+    InvalidPerms,
+    /// The requested otype can't be used for sealing, see [SealError::InvalidOType]
+    InvalidOType,
+}
+
+/// Builds a capability from explicit fields, e.g.
+/// `CapBuilder::<Cc128>::new().base(0x1000).top(0x2000).perms(Permissions::EMPTY.load()).build()`.
+///
+/// Any field which isn't set takes its value from [CcxCap::root]:
+/// the whole address space, the cursor at the base, all permissions, unsealed and tagged.
+///
+/// [Self::build] applies the fields in an order where they can't clobber each other
+/// (bounds, then cursor, then permissions, then otype, then tag).
+/// Unlike [CcxCap::set_bounds_unchecked], bounds which can't be represented exactly are an error instead of being rounded.
+#[derive(Debug, Copy, Clone)]
+pub struct CapBuilder<T: CompressedCapability> {
+    base: T::Addr,
+    top: T::Length,
+    cursor: Option<T::Addr>,
+    perms: Option<u32>,
+    otype: u32,
+    tag: bool,
+}
+impl<T: CompressedCapability> CapBuilder<T> {
+    pub fn new() -> Self {
+        CapBuilder {
+            base: T::Addr::zero(),
+            top: crate::max_top::<T>(),
+            cursor: None,
+            perms: None,
+            otype: T::OTYPE_UNSEALED,
+            tag: true,
+        }
+    }
+
+    pub fn base(self, base: T::Addr) -> Self {
+        CapBuilder { base, ..self }
+    }
+    pub fn top(self, top: T::Length) -> Self {
+        CapBuilder { top, ..self }
+    }
+    /// Sets the cursor, which defaults to the base
+    pub fn cursor(self, cursor: T::Addr) -> Self {
+        CapBuilder {
+            cursor: Some(cursor),
+            ..self
+        }
+    }
+    /// Sets the architectural permissions, from a raw `u32` or a [Permissions](crate::permissions::Permissions)
+    pub fn perms(self, perms: impl Into<u32>) -> Self {
+        CapBuilder {
+            perms: Some(perms.into()),
+            ..self
+        }
+    }
+    /// Sets the otype. Anything other than [CompressedCapability::OTYPE_UNSEALED] seals the capability.
+    pub fn otype(self, otype: u32) -> Self {
+        CapBuilder { otype, ..self }
+    }
+    pub fn tag(self, tag: bool) -> Self {
+        CapBuilder { tag, ..self }
+    }
+
+    pub fn build(self) -> Result<CcxCap<T>, BuildError> {
+        let base: T::Length = self.base.into();
+        if self.top < base || self.top > crate::max_top::<T>() {
+            return Err(BuildError::InvalidBounds);
+        }
+
+        let mut cap = CcxCap::<T>::root();
+        if cap.set_bounds_exact(self.base, self.top).is_err() {
+            return Err(BuildError::BoundsNotExact);
+        }
+        if let Some(cursor) = self.cursor {
+            if !cap.is_representable_with_new_addr(cursor) {
+                return Err(BuildError::CursorNotRepresentable);
+            }
+            cap.set_address_unchecked(cursor);
+        }
+        if let Some(perms) = self.perms {
+            // Checked here, as the C library asserts on permission bits outside the format's mask
+            if perms & !cap.permissions() != 0 {
+                return Err(BuildError::InvalidPerms);
+            }
+            cap.set_permissions(perms);
+        }
+        if self.otype != T::OTYPE_UNSEALED {
+            cap = cap.seal_checked(self.otype).map_err(|err| match err {
+                SealError::InvalidOType => BuildError::InvalidOType,
+                // The otype is never part of the bounds in the implemented formats
+                SealError::BoundsChanged => unreachable!("sealing changed the bounds"),
+            })?;
+        }
+        cap.set_tag(self.tag);
+        Ok(cap)
+    }
+}
+impl<T: CompressedCapability> Default for CapBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caps::cheriv9::cc128::Cc128;
+    use crate::permissions::Permissions;
+
+    #[test]
+    fn test_build_code_cap() {
+        let perms = Permissions::<Cc128>::EMPTY.global().load().execute().load_cap();
+        let code = CapBuilder::<Cc128>::new()
+            .base(0x1_0000)
+            .top(0x1_4000)
+            .cursor(0x1_0100)
+            .perms(perms)
+            .otype(Cc128::OTYPE_SENTRY)
+            .build()
+            .unwrap();
+
+        let mut manual = Cc128::make_max_perms_cap(0x1_0000, 0x1_0100, 0x1_4000);
+        manual.set_perms(perms);
+        manual.set_otype(Cc128::OTYPE_SENTRY);
+        assert_eq!(code, manual);
+        assert!(code.is_sentry());
+    }
+
+    #[test]
+    fn test_build_data_cap() {
+        let perms = Permissions::<Cc128>::EMPTY.global().load().store().load_cap().store_cap();
+        let data = CapBuilder::<Cc128>::new().base(0x8000).top(0x9000).perms(perms).build().unwrap();
+
+        let mut manual = Cc128::make_max_perms_cap(0x8000, 0x8000, 0x9000);
+        manual.set_perms(perms);
+        assert_eq!(data, manual);
+        assert!(data.tag() && !data.is_sealed());
+
+        // Defaults are the root capability
+        assert_eq!(CapBuilder::<Cc128>::new().build(), Ok(CcxCap::root()));
+    }
+
+    #[test]
+    fn test_build_morello_cap() {
+        use crate::caps::morello;
+
+        // GLOBAL is an ordinary permission on Morello, in the bit just below the user permissions
+        let perms = morello::Cc128::PERM_GLOBAL | morello::Cc128::PERM_LOAD | morello::Cc128::PERM_STORE;
+        let data = CapBuilder::<morello::Cc128>::new().base(0x8000).top(0x9000).perms(perms).build().unwrap();
+        assert_eq!(data.permissions(), perms);
+        assert_eq!(data.bounds(), (0x8000, 0x9000));
+
+        // The user permissions aren't architectural permissions
+        assert_eq!(
+            CapBuilder::<morello::Cc128>::new().perms(1u32 << 2).build(),
+            Err(BuildError::InvalidPerms)
+        );
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let builder = CapBuilder::<Cc128>::new();
+        assert_eq!(builder.base(0x2000).top(0x1000).build(), Err(BuildError::InvalidBounds));
+        assert_eq!(builder.top((1 << 64) + 1).build(), Err(BuildError::InvalidBounds));
+        assert_eq!(builder.base(0x1001).top(0x1_0000).build(), Err(BuildError::BoundsNotExact));
+        assert_eq!(
+            builder.base(0x1000).top(0x1010).cursor(0x10_0000).build(),
+            Err(BuildError::CursorNotRepresentable)
+        );
+        assert_eq!(
+            builder.otype(Cc128::MAX_REPRESENTABLE_OTYPE + 1).build(),
+            Err(BuildError::InvalidOType)
+        );
+        assert_eq!(builder.perms(1u32 << 31).build(), Err(BuildError::InvalidPerms));
+        // Untagged caps can still be built
+        assert!(!builder.tag(false).build().unwrap().tag());
+    }
+}
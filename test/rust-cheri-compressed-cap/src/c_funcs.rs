@@ -46,21 +46,26 @@ macro_rules! cap_c_funcs {
         pub(crate) fn [<$ver _extract_bounds_bits>](pesbt: $mod::Addr) -> CcxBoundsBits;
         pub(crate) fn [<$ver _setbounds>](cap: *mut $mod::Cap, req_len: $mod::FfiLength) -> bool;
         pub(crate) fn [<$ver _is_representable_cap_exact>](cap: *const $mod::Cap) -> bool;
-        pub(crate) fn [<$ver _is_representable_new_addr>](sealed: bool, base: $mod::Addr, length: $mod::FfiLength, cursor: $mod::Addr, new_cursor: $mod::Addr) -> bool;
+        pub(crate) fn [<$ver _is_representable_with_addr>](cap: *const $mod::Cap, new_addr: $mod::Addr, precise_representable_check: bool) -> bool;
         pub(crate) fn [<$ver _make_max_perms_cap>](base: $mod::Addr, cursor: $mod::Addr, top: $mod::FfiLength) -> $mod::Cap;
-        pub(crate) fn [<$ver _get_representable_length>](length: $mod::FfiLength) -> $mod::FfiLength;
-        pub(crate) fn [<$ver _get_required_alignment>](length: $mod::FfiLength) -> $mod::FfiLength;
-        pub(crate) fn [<$ver _get_alignment_mask>](length: $mod::FfiLength) -> $mod::FfiLength;
+        // These take and return _cc_addr_t, not _cc_length_t
+        pub(crate) fn [<$ver _get_representable_length>](length: $mod::Addr) -> $mod::Addr;
+        pub(crate) fn [<$ver _get_required_alignment>](length: $mod::Addr) -> $mod::Addr;
+        pub(crate) fn [<$ver _get_alignment_mask>](length: $mod::Addr) -> $mod::Addr;
     }};
 }
 
 #[link(name = "cheri_compressed_cap_lib")]
 #[allow(improper_ctypes)]
+// The cc128 bounds encoding functions are replaced by crate::concentrate with the rust-bounds feature,
+// but are still used by its differential tests
+#[cfg_attr(feature = "rust-bounds", allow(dead_code))]
 extern "C" {
     cap_c_funcs!{cc64, crate::caps::cheriv9::cc64}
-    cap_c_funcs!{cc64r, crate::caps::rvy::cc64}
     cap_c_funcs!{cc128, crate::caps::cheriv9::cc128}
+    cap_c_funcs!{cc64r, crate::caps::rvy::cc64}
     cap_c_funcs!{cc128r, crate::caps::rvy::cc128}
     cap_c_funcs!{cc128m, crate::caps::morello}
+    pub(crate) fn cc128m_cap_bounds_uses_value(cap: *const crate::caps::morello::Cap) -> bool;
     // TODO 256
-}
\ No newline at end of file
+}
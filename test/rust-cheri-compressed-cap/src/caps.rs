@@ -1,4 +1,5 @@
 macro_rules! ccap_impl_funcs {
+    // Bounds encoding/decoding implemented by the C library
     ($ver:ident) => { paste! {
         fn compress_raw(cap: &Cap) -> Addr {
             unsafe { [<$ver _compress_raw>](cap) }
@@ -16,22 +17,70 @@ macro_rules! ccap_impl_funcs {
             unsafe { [<$ver _decompress_mem>](pesbt, cursor, tag, &mut cap) };
             cap
         }
+        // Checked against the Rust decoding in EBT_LAYOUT if the verify-ffi feature is enabled
+        fn extract_bounds_bits(pesbt: Self::Addr) -> CcxBoundsBits {
+            let bits = unsafe { [<$ver _extract_bounds_bits>](pesbt) };
+            crate::fields::cross_check("extract_bounds_bits", pesbt as u64, bits, || Some(EBT_LAYOUT.decode(pesbt as u64)))
+        }
+        fn set_bounds(cap: &mut Cap, req_len: Self::Length) -> bool {
+            unsafe { [<$ver _setbounds>](cap, req_len) }
+        }
+
+        ccap_impl_funcs!(@common $ver);
+    } };
+    // Bounds encoding/decoding implemented in Rust by crate::$rust
+    ($ver:ident, $rust:ident) => {
+        fn compress_raw(cap: &Cap) -> Addr {
+            crate::$rust::compress_raw(cap)
+        }
+        fn decompress_raw(pesbt: Addr, cursor: Addr, tag: bool) -> Cap {
+            crate::$rust::decompress_raw(pesbt, cursor, tag)
+        }
+        fn compress_mem(cap: &Cap) -> Addr {
+            crate::$rust::compress_mem(cap)
+        }
+        fn decompress_mem(pesbt: Addr, cursor: Addr, tag: bool) -> Cap {
+            crate::$rust::decompress_mem(pesbt, cursor, tag)
+        }
+        fn extract_bounds_bits(pesbt: Self::Addr) -> CcxBoundsBits {
+            crate::$rust::extract_bounds_bits(pesbt)
+        }
+        fn set_bounds(cap: &mut Cap, req_len: Self::Length) -> bool {
+            crate::$rust::set_bounds(cap, req_len)
+        }
 
+        ccap_impl_funcs!(@common $ver);
+    };
+    // Everything else, which always goes through the C library
+    (@common $ver:ident) => { paste! {
         /* Getters */
+        // Checked against the Rust extraction in PESBT_FIELDS if the verify-ffi feature is enabled
         fn get_uperms(cap: &Cap) -> u32 {
-            unsafe { [<$ver _get_uperms>](cap) }
+            let pesbt = cap.cr_pesbt as u64;
+            let uperms = unsafe { [<$ver _get_uperms>](cap) };
+            crate::fields::cross_check("get_uperms", pesbt, uperms, || Some(PESBT_FIELDS.uperms(pesbt)))
         }
         fn get_perms(cap: &Cap) -> u32 {
-            unsafe { [<$ver _get_perms>](cap) }
+            let pesbt = cap.cr_pesbt as u64;
+            let perms = unsafe { [<$ver _get_perms>](cap) };
+            crate::fields::cross_check("get_perms", pesbt, perms, || PESBT_FIELDS.perms(pesbt))
         }
         fn get_otype(cap: &Cap) -> u32 {
-            unsafe { [<$ver _get_otype>](cap) }
+            let pesbt = cap.cr_pesbt as u64;
+            let otype = unsafe { [<$ver _get_otype>](cap) };
+            crate::fields::cross_check("get_otype", pesbt, otype, || Some(PESBT_FIELDS.otype(pesbt)))
         }
         fn get_reserved(cap: &Cap) -> u8 {
-            unsafe { [<$ver _get_reserved>](cap) }
+            let pesbt = cap.cr_pesbt as u64;
+            let reserved = unsafe { [<$ver _get_reserved>](cap) };
+            crate::fields::cross_check("get_reserved", pesbt, reserved, || {
+                Some(crate::fields::reserved(Self::RESERVED_FIELDS, pesbt))
+            })
         }
         fn get_flags(cap: &Cap) -> u8 {
-            unsafe { [<$ver _get_flags>](cap) }
+            let pesbt = cap.cr_pesbt as u64;
+            let flags = unsafe { [<$ver _get_flags>](cap) };
+            crate::fields::cross_check("get_flags", pesbt, flags, || Some(PESBT_FIELDS.flags(pesbt)))
         }
 
         /* Updaters */
@@ -52,36 +101,63 @@ macro_rules! ccap_impl_funcs {
         }
 
         /* Misc */
-        fn extract_bounds_bits(pesbt: Self::Addr) -> CcxBoundsBits {
-            unsafe { [<$ver _extract_bounds_bits>](pesbt) }
+        fn encode_bounds_bits(bits: CcxBoundsBits) -> Self::Addr {
+            // Implemented in Rust, using the EBT_LAYOUT defined next to each impl
+            EBT_LAYOUT.encode(bits) as Self::Addr
         }
-        fn set_bounds(cap: &mut Cap, req_len: Self::Length) -> bool {
-            unsafe { [<$ver _setbounds>](cap, req_len) }
+        fn compute_base_top(bits: CcxBoundsBits, cursor: Self::Addr) -> (Self::Addr, Self::Length) {
+            // Implemented in Rust, the C compute_base_top isn't exported
+            let (base, top) = EBT_LAYOUT.compute_base_top(bits, cursor as u64);
+            (base as Addr, top as Length)
         }
         fn is_representable_cap_exact(cap: &Cap) -> bool {
             unsafe { [<$ver _is_representable_cap_exact>](cap) }
         }
-        fn is_representable_new_addr(sealed: bool, base: Self::Addr, length: Self::Length, cursor: Self::Addr, new_cursor: Self::Addr) -> bool {
-            unsafe { [<$ver _is_representable_new_addr>](sealed, base, length, cursor, new_cursor) }
+        fn is_representable_with_addr(cap: &Cap, new_addr: Self::Addr, precise: bool) -> bool {
+            unsafe { [<$ver _is_representable_with_addr>](cap, new_addr, precise) }
         }
         fn make_max_perms_cap(base: Self::Addr, cursor: Self::Addr, top: Self::Length) -> Cap {
             unsafe { [<$ver _make_max_perms_cap>](base, cursor, top) }
         }
+        // The C functions only take/return addresses, so lengths are clamped with clamp_length
+        // and results which wrap to zero in C are really the full address space.
         fn get_representable_length(length: Self::Length) -> Self::Length {
-            unsafe { [<$ver _get_representable_length>](length) }
+            match unsafe { [<$ver _get_representable_length>](crate::caps::clamp_length(length)) } {
+                0 if length != 0 => Addr::MAX as Length + 1,
+                representable_length => representable_length as Length,
+            }
         }
         fn get_required_alignment(length: Self::Length) -> Self::Length {
-            unsafe { [<$ver _get_required_alignment>](length) }
+            match unsafe { [<$ver _get_required_alignment>](crate::caps::clamp_length(length)) } {
+                0 => Addr::MAX as Length + 1,
+                alignment => alignment as Length,
+            }
         }
         fn get_alignment_mask(length: Self::Length) -> Self::Length {
-            unsafe { [<$ver _get_alignment_mask>](length) }
+            unsafe { [<$ver _get_alignment_mask>](crate::caps::clamp_length(length)) as Length }
         }
     } }
 }
 
+/// Converts a length to an address for the C functions which take `_cc_addr_t` lengths,
+/// saturating at the maximum address (which needs the same exponent as any longer length).
+pub(crate) fn clamp_length<L, A>(length: L) -> A
+where
+    L: num_traits::PrimInt + num_traits::AsPrimitive<A>,
+    A: num_traits::PrimInt + num_traits::AsPrimitive<L>,
+{
+    if length > A::max_value().as_() {
+        A::max_value()
+    } else {
+        length.as_()
+    }
+}
+
 
 pub mod morello {
     use crate::c_funcs::*;
+    use crate::bounds::{BaseTopSpecialCases, EbtLayout, ExponentEncoding};
+    use crate::fields::{Field, PermsEncoding, PesbtFields};
     use crate::{CcxBoundsBits, CcxCap, CompressedCapability};
     use paste::paste;
 
@@ -94,12 +170,43 @@ pub mod morello {
     pub type Cap = CcxCap<Cc128>;
     pub type Cc128Cap = Cap;
 
+    /// Alternative name for [Cc128], for code that mixes Morello and CHERIv9/RISC-V Y formats
+    pub type CcMorello = Cc128;
+    /// Alternative name for [Cc128Cap], see [CcMorello]
+    pub type CcMorelloCap = Cap;
+
+    /// Permission, otype and flags field layout, see _128m.h
+    pub(crate) const PESBT_FIELDS: PesbtFields = PesbtFields {
+        perms: PermsEncoding::Combined {
+            all_perms: Field { start: 46, width: 18 },
+            uperms_shift: 2,
+            uperms_width: 4,
+        },
+        otype: Field { start: 31, width: 15 },
+        // FLAGS live in the top byte of the cursor, so C always returns zero
+        flags: Field::NONE,
+    };
+
+    /// Bounds field layout, see _128m.h
+    pub(crate) const EBT_LAYOUT: EbtLayout = EbtLayout {
+        mantissa_width: Cc128::MANTISSA_WIDTH,
+        exp_part_width: 3,
+        ie_inverted: true,
+        exponent: ExponentEncoding::Inverted,
+        len_msb: false,
+        addr_width: 64,
+        bounds_addr_width: 56,
+        max_exponent: 50,
+        v9_correction: Cc128::USES_V9_CORRECTION_FACTORS,
+        special_cases: BaseTopSpecialCases::ExponentAboveMax,
+    };
+
     /// Implements [CompressedCapability] for Morello 128-bit capabilities.
     ///
     /// Empty enum, so it cannot be itself constructed. If you want a CC128 capability, instantiate Cc128Cap.
     ///
-    /// Derives Debug, Copy, Clone so that CcxCap<Cc128> can derive them too.
-    #[derive(Debug, Copy, Clone)]
+    /// Derives the supertraits of [CompressedCapability] so that CcxCap<Cc128> can derive them too.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     pub enum Cc128 {}
     impl CompressedCapability for Cc128 {
         type Length = Length;
@@ -108,6 +215,12 @@ pub mod morello {
 
         type FfiLength = FfiLength;
         type FfiOffset = FfiOffset;
+        /// _CC_N(CAP_SIZE)
+        type MemBytes = [u8; 16];
+
+        const CAP_SIZE_BYTES: usize = 16;
+        const ADDR_WIDTH_BITS: u32 = 64;
+        const CAP_MAX_LENGTH: Length = 1 << 64;
 
         const PERM_GLOBAL: u32 = (1 << 0);
         const PERM_EXECUTE: u32 = (1 << 15);
@@ -124,22 +237,107 @@ pub mod morello {
 
         /// _128m.h:171
         /// encoded directly _128m.h:78
-        const MAX_REPRESENTABLE_OTYPE: u32 = ((1u32 << 15)) - 1;
+        const MAX_REPRESENTABLE_OTYPE: u32 = (1u32 << 15) - 1;
         const OTYPE_UNSEALED: u32 = 0;
         const OTYPE_SENTRY: u32 = 1;
         const OTYPE_RESERVED2: u32 = 2;
         const OTYPE_RESERVED3: u32 = 3;
         const MAX_UNRESERVED_OTYPE: u32 = Self::MAX_REPRESENTABLE_OTYPE;
+        /// The C64 mode is in PSTATE, not the capability
+        const FLAGS_CAP_MODE: Option<u8> = None;
+        const RESERVED_OTYPES: &'static [u32] = &[
+            Self::OTYPE_UNSEALED,
+            Self::OTYPE_SENTRY,
+            Self::OTYPE_RESERVED2,
+            Self::OTYPE_RESERVED3,
+        ];
+
+        const IS_CHERI_CONCENTRATE: bool = true;
+        /// _128m.h:67
+        const MANTISSA_WIDTH: u32 = 16;
+        const USES_V9_CORRECTION_FACTORS: bool = true;
+        /// no reserved bits, _128m.h:195
+        const RESERVED_FIELDS: &'static [(u32, u32)] = &[];
+
+        fn encoding_family() -> &'static str {
+            "Morello"
+        }
+
+        /// PERMS_ALL, i.e. every bit of ALL_PERMS except the user permissions: _128m.h:158
+        fn arch_perm_count() -> u32 {
+            14
+        }
+        /// UPERMS_ALL: _128m.h:134
+        fn software_perm_count() -> u32 {
+            4
+        }
 
         ccap_impl_funcs!(cc128m);
+
+        fn bounds_uses_value(cap: &Cap) -> bool {
+            unsafe { cc128m_cap_bounds_uses_value(cap) }
+        }
     }
 }
 
 pub mod cheriv9 {
     //! CHERIv9 Implementations
 
+    use crate::{CcxCap, CompressedCapability, ConversionError, OType};
+
+    /// Maps a format-`F` otype onto the otype with the same meaning in format `T`.
+    fn convert_otype<F: CompressedCapability, T: CompressedCapability>(otype: u32) -> Option<u32> {
+        match OType::from_raw::<F>(otype)? {
+            OType::Unsealed => Some(T::OTYPE_UNSEALED),
+            OType::Sentry => Some(T::OTYPE_SENTRY),
+            OType::Reserved(otype) if otype == F::OTYPE_RESERVED2 => Some(T::OTYPE_RESERVED2),
+            OType::Reserved(otype) if otype == F::OTYPE_RESERVED3 => Some(T::OTYPE_RESERVED3),
+            kind => match OType::from_raw::<T>(otype) {
+                Some(converted) if converted == kind => Some(otype),
+                _ => None,
+            },
+        }
+    }
+
+    /// Re-encodes a capability in another CHERIv9 format, keeping the bounds, permissions, flags and otype.
+    ///
+    /// Returns the cursor and the new pesbt, and whether the cursor is within the representable region of the new encoding.
+    /// Both formats use the same permission layout, so permissions are copied as-is.
+    /// Software permissions the target format doesn't have are dropped.
+    /// Reversed bounds (only possible for untagged capabilities) are converted as empty bounds at the base.
+    fn convert<F: CompressedCapability, T: CompressedCapability>(
+        c: &CcxCap<F>,
+    ) -> Result<(T::Addr, T::Addr, bool), ConversionError> {
+        use num_traits::{CheckedAdd, Zero};
+
+        let cursor: T::Addr = num_traits::cast(c.address()).ok_or(ConversionError::AddressOutOfRange)?;
+        let base: T::Addr = num_traits::cast(c.base()).ok_or(ConversionError::AddressOutOfRange)?;
+        let length: T::Length = num_traits::cast(c.length_saturating()).ok_or(ConversionError::AddressOutOfRange)?;
+        let base_len: T::Length = base.into();
+        match base_len.checked_add(&length) {
+            Some(top) if top <= crate::max_top::<T>() => {}
+            _ => return Err(ConversionError::AddressOutOfRange),
+        }
+        let otype = convert_otype::<F, T>(c.otype()).ok_or(ConversionError::OTypeNotRepresentable)?;
+
+        let mut cap = T::make_max_perms_cap(T::Addr::zero(), base, crate::max_top::<T>());
+        if !cap.set_bounds_unchecked(length) {
+            return Err(ConversionError::BoundsNotRepresentable);
+        }
+        cap.set_permissions(c.permissions());
+        cap.set_software_permissions(c.software_permissions());
+        cap.set_flags(c.flags());
+        cap.set_otype(otype);
+        let (_, [_, pesbt]) = cap.reg_representation();
+        // Check the bounds decode the same with the real cursor
+        let cursor_representable = T::decompress_raw(pesbt, cursor, false).bounds() == cap.bounds();
+        Ok((cursor, pesbt, cursor_representable))
+    }
+
     pub mod cc64 {
         use crate::c_funcs::*;
+        use crate::bounds::{BaseTopSpecialCases, EbtLayout, ExponentEncoding};
+        use crate::fields::{Field, PermsEncoding, PesbtFields};
         use crate::{CcxBoundsBits, CcxCap, CompressedCapability};
         use paste::paste;
 
@@ -152,12 +350,36 @@ pub mod cheriv9 {
         pub type Cap = CcxCap<Cc64>;
         pub type Cc64Cap = Cap;
 
+        /// Permission, otype and flags field layout, see _64.h
+        pub(crate) const PESBT_FIELDS: PesbtFields = PesbtFields {
+            perms: PermsEncoding::Split {
+                hwperms: Field { start: 20, width: 12 },
+                uperms: Field::NONE,
+            },
+            otype: Field { start: 15, width: 4 },
+            flags: Field { start: 19, width: 1 },
+        };
+
+        /// Bounds field layout, see _64.h
+        pub(crate) const EBT_LAYOUT: EbtLayout = EbtLayout {
+            mantissa_width: Cc64::MANTISSA_WIDTH,
+            exp_part_width: 3,
+            ie_inverted: false,
+            exponent: ExponentEncoding::Raw,
+            len_msb: false,
+            addr_width: 32,
+            bounds_addr_width: 32,
+            max_exponent: 26,
+            v9_correction: Cc64::USES_V9_CORRECTION_FACTORS,
+            special_cases: BaseTopSpecialCases::None,
+        };
+
         /// Implements [CompressedCapability] for CHERIv9 64-bit capabilities.
         ///
         /// Empty enum, so it cannot be itself constructed. If you want a CC64 capability, instantiate CC64::Cap.
         ///
-        /// Derives Debug, Copy, Clone so that CcxCap<Cc64> can derive them too.
-        #[derive(Debug, Copy, Clone)]
+        /// Derives the supertraits of [CompressedCapability] so that CcxCap<Cc64> can derive them too.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
         pub enum Cc64 {}
         impl CompressedCapability for Cc64 {
             type Length = Length;
@@ -166,21 +388,74 @@ pub mod cheriv9 {
 
             type FfiLength = FfiLength;
             type FfiOffset = FfiOffset;
+            /// _CC_N(CAP_SIZE)
+            type MemBytes = [u8; 8];
+
+            const CAP_SIZE_BYTES: usize = 8;
+            const ADDR_WIDTH_BITS: u32 = 32;
+            const CAP_MAX_LENGTH: Length = 1 << 32;
 
             /// _64.h:126
             const MAX_REPRESENTABLE_OTYPE: u32 = 0b1111;
-            const OTYPE_UNSEALED: u32 = Self::MAX_REPRESENTABLE_OTYPE - 0;
+            const OTYPE_UNSEALED: u32 = Self::MAX_REPRESENTABLE_OTYPE;
             const OTYPE_SENTRY: u32 = Self::MAX_REPRESENTABLE_OTYPE - 1;
             const OTYPE_RESERVED2: u32 = Self::MAX_REPRESENTABLE_OTYPE - 2;
             const OTYPE_RESERVED3: u32 = Self::MAX_REPRESENTABLE_OTYPE - 3;
             const MAX_UNRESERVED_OTYPE: u32 = Self::MAX_REPRESENTABLE_OTYPE;
+            /// MODE_CAP: _64.h:67
+            const FLAGS_CAP_MODE: Option<u8> = Some(1);
+            const RESERVED_OTYPES: &'static [u32] = &[
+                Self::OTYPE_UNSEALED,
+                Self::OTYPE_SENTRY,
+                Self::OTYPE_RESERVED2,
+                Self::OTYPE_RESERVED3,
+            ];
+
+            const IS_CHERI_CONCENTRATE: bool = true;
+            /// _64.h:44
+            const MANTISSA_WIDTH: u32 = 8;
+            const USES_V9_CORRECTION_FACTORS: bool = true;
+            /// no reserved bits, _64.h:91
+            const RESERVED_FIELDS: &'static [(u32, u32)] = &[];
+
+            fn encoding_family() -> &'static str {
+                "CHERIv9"
+            }
+
+            /// PERMS_ALL: _64.h:118
+            fn arch_perm_count() -> u32 {
+                12
+            }
+            /// UPERMS_ALL: _64.h:119
+            fn software_perm_count() -> u32 {
+                0
+            }
 
             ccap_impl_funcs! {cc64}
         }
+
+        impl Cap {
+            /// Narrows a 128-bit capability to 64 bits, keeping the tag, cursor, bounds, permissions, flags and otype.
+            /// Software permissions are dropped, as this format has none.
+            ///
+            /// Fails if the cursor or bounds don't fit in the 32-bit address space,
+            /// the bounds can't be represented exactly with the 64-bit encoding (or the cursor is then outside the representable region),
+            /// or the otype doesn't fit in the 64-bit otype field.
+            ///
+            /// Counterpart: [super::cc128::Cap::from_cc64]
+            pub fn try_from_cc128(c: &super::cc128::Cc128Cap) -> Result<Cc64Cap, crate::ConversionError> {
+                match super::convert::<_, Cc64>(c)? {
+                    (cursor, pesbt, true) => Ok(Cc64::decompress_raw(pesbt, cursor, c.tag())),
+                    (_, _, false) => Err(crate::ConversionError::BoundsNotRepresentable),
+                }
+            }
+        }
     }
 
     pub mod cc128 {
         use crate::c_funcs::*;
+        use crate::bounds::{BaseTopSpecialCases, EbtLayout, ExponentEncoding};
+        use crate::fields::{Field, PermsEncoding, PesbtFields};
         use crate::{CcxBoundsBits, CcxCap, CompressedCapability};
         use paste::paste;
 
@@ -193,12 +468,36 @@ pub mod cheriv9 {
         pub type Cap = CcxCap<Cc128>;
         pub type Cc128Cap = Cap;
 
+        /// Permission, otype and flags field layout, see _128.h
+        pub(crate) const PESBT_FIELDS: PesbtFields = PesbtFields {
+            perms: PermsEncoding::Split {
+                hwperms: Field { start: 48, width: 12 },
+                uperms: Field { start: 60, width: 4 },
+            },
+            otype: Field { start: 27, width: 18 },
+            flags: Field { start: 45, width: 1 },
+        };
+
+        /// Bounds field layout, see _128.h
+        pub(crate) const EBT_LAYOUT: EbtLayout = EbtLayout {
+            mantissa_width: Cc128::MANTISSA_WIDTH,
+            exp_part_width: 3,
+            ie_inverted: false,
+            exponent: ExponentEncoding::Raw,
+            len_msb: false,
+            addr_width: 64,
+            bounds_addr_width: 64,
+            max_exponent: 52,
+            v9_correction: Cc128::USES_V9_CORRECTION_FACTORS,
+            special_cases: BaseTopSpecialCases::None,
+        };
+
         /// Implements [CompressedCapability] for CHERIv9 128-bit capabilities.
         ///
         /// Empty enum, so it cannot be itself constructed. If you want a CC128 capability, instantiate Cc128Cap.
         ///
-        /// Derives Debug, Copy, Clone so that CcxCap<Cc128> can derive them too.
-        #[derive(Debug, Copy, Clone)]
+        /// Derives the supertraits of [CompressedCapability] so that CcxCap<Cc128> can derive them too.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
         pub enum Cc128 {}
         impl CompressedCapability for Cc128 {
             type Length = Length;
@@ -207,17 +506,71 @@ pub mod cheriv9 {
 
             type FfiLength = FfiLength;
             type FfiOffset = FfiOffset;
+            /// _CC_N(CAP_SIZE)
+            type MemBytes = [u8; 16];
+
+            const CAP_SIZE_BYTES: usize = 16;
+            const ADDR_WIDTH_BITS: u32 = 64;
+            const CAP_MAX_LENGTH: Length = 1 << 64;
 
             /// The OTYPE field is 18 bits (108:91) in CC128
             /// _128.h:126
             const MAX_REPRESENTABLE_OTYPE: u32 = 0x3_FFFF;
-            const OTYPE_UNSEALED: u32 = Self::MAX_REPRESENTABLE_OTYPE - 0;
+            const OTYPE_UNSEALED: u32 = Self::MAX_REPRESENTABLE_OTYPE;
             const OTYPE_SENTRY: u32 = Self::MAX_REPRESENTABLE_OTYPE - 1;
             const OTYPE_RESERVED2: u32 = Self::MAX_REPRESENTABLE_OTYPE - 2;
             const OTYPE_RESERVED3: u32 = Self::MAX_REPRESENTABLE_OTYPE - 3;
             const MAX_UNRESERVED_OTYPE: u32 = Self::MAX_REPRESENTABLE_OTYPE - 4;
+            /// MODE_CAP: _128.h:67
+            const FLAGS_CAP_MODE: Option<u8> = Some(1);
+            const RESERVED_OTYPES: &'static [u32] = &[
+                Self::OTYPE_UNSEALED,
+                Self::OTYPE_SENTRY,
+                Self::OTYPE_RESERVED2,
+                Self::OTYPE_RESERVED3,
+            ];
+
+            const IS_CHERI_CONCENTRATE: bool = true;
+            /// _128.h:45
+            const MANTISSA_WIDTH: u32 = 14;
+            const USES_V9_CORRECTION_FACTORS: bool = true;
+            /// RESERVED: _128.h:75
+            const RESERVED_FIELDS: &'static [(u32, u32)] = &[(46, 2)];
+
+            fn encoding_family() -> &'static str {
+                "CHERIv9"
+            }
+
+            /// PERMS_ALL: _128.h:119
+            fn arch_perm_count() -> u32 {
+                12
+            }
+            /// UPERMS_ALL: _128.h:120
+            fn software_perm_count() -> u32 {
+                4
+            }
 
+            #[cfg(not(feature = "rust-bounds"))]
             ccap_impl_funcs!(cc128);
+            #[cfg(feature = "rust-bounds")]
+            ccap_impl_funcs!(cc128, concentrate);
+        }
+
+        impl Cap {
+            /// Widens a 64-bit capability to 128 bits, keeping the tag, cursor, bounds, permissions, flags and otype.
+            ///
+            /// The 128-bit encoding has more mantissa bits, so it can represent the 64-bit bounds exactly.
+            /// However the 64-bit representable region wraps around the 32-bit address space,
+            /// so the cursor of a 64-bit capability may be far outside the 128-bit representable region.
+            /// In that case the result is untagged, and its bounds are decoded from the new encoding with the original cursor,
+            /// as if the cursor had been moved to an unrepresentable address.
+            ///
+            /// Counterpart: [super::cc64::Cap::try_from_cc128]
+            pub fn from_cc64(c: &super::cc64::Cc64Cap) -> Cc128Cap {
+                let (cursor, pesbt, cursor_representable) =
+                    super::convert::<_, Cc128>(c).expect("64-bit bounds and otypes should be representable in 128 bits");
+                Cc128::decompress_raw(pesbt, cursor, c.tag() && cursor_representable)
+            }
         }
     }
 }
@@ -227,6 +580,8 @@ pub mod rvy {
 
     pub mod cc64 {
         use crate::c_funcs::*;
+        use crate::bounds::{BaseTopSpecialCases, EbtLayout, ExponentEncoding};
+        use crate::fields::{Field, PermsEncoding, PesbtFields};
         use crate::{CcxBoundsBits, CcxCap, CompressedCapability};
         use paste::paste;
 
@@ -239,12 +594,35 @@ pub mod rvy {
         pub type Cap = CcxCap<Cc64>;
         pub type Cc64Cap = Cap;
 
+        /// Permission, otype and flags field layout, see _64r.h
+        pub(crate) const PESBT_FIELDS: PesbtFields = PesbtFields {
+            perms: PermsEncoding::Compressed {
+                sdp: Field { start: 30, width: 2 },
+            },
+            otype: Field { start: 20, width: 1 },
+            flags: Field { start: 25, width: 1 },
+        };
+
+        /// Bounds field layout, see _64r.h
+        pub(crate) const EBT_LAYOUT: EbtLayout = EbtLayout {
+            mantissa_width: Cc64::MANTISSA_WIDTH,
+            exp_part_width: 2,
+            ie_inverted: true,
+            exponent: ExponentEncoding::SubtractedFrom(24),
+            len_msb: true,
+            addr_width: 32,
+            bounds_addr_width: 32,
+            max_exponent: 24,
+            v9_correction: Cc64::USES_V9_CORRECTION_FACTORS,
+            special_cases: BaseTopSpecialCases::Malformed,
+        };
+
         /// Implements [CompressedCapability] for RISC-V Y 64-bit capabilities.
         ///
         /// Empty enum, so it cannot be itself constructed. If you want a CC64 capability, instantiate CC64::Cap.
         ///
-        /// Derives Debug, Copy, Clone so that CcxCap<Cc64> can derive them too.
-        #[derive(Debug, Copy, Clone)]
+        /// Derives the supertraits of [CompressedCapability] so that CcxCap<Cc64> can derive them too.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
         pub enum Cc64 {}
         impl CompressedCapability for Cc64 {
             type Length = Length;
@@ -253,6 +631,12 @@ pub mod rvy {
 
             type FfiLength = FfiLength;
             type FfiOffset = FfiOffset;
+            /// _CC_N(CAP_SIZE)
+            type MemBytes = [u8; 8];
+
+            const CAP_SIZE_BYTES: usize = 8;
+            const ADDR_WIDTH_BITS: u32 = 32;
+            const CAP_MAX_LENGTH: Length = 1 << 32;
 
             // Lots of permissions are missing
             const PERM_GLOBAL: u32 = 0;
@@ -275,6 +659,30 @@ pub mod rvy {
             const OTYPE_RESERVED2: u32 = u32::MAX;
             const OTYPE_RESERVED3: u32 = u32::MAX;
             const MAX_UNRESERVED_OTYPE: u32 = u32::MAX;
+            /// MODE_CAP: _64r.h:73 (the M bit is set for integer mode)
+            const FLAGS_CAP_MODE: Option<u8> = Some(0);
+            /// RISC-V Y only has sentries, RESERVED2/RESERVED3 don't exist
+            const RESERVED_OTYPES: &'static [u32] = &[Self::OTYPE_UNSEALED, Self::OTYPE_SENTRY];
+
+            const IS_CHERI_CONCENTRATE: bool = true;
+            /// _64r.h:48
+            const MANTISSA_WIDTH: u32 = 10;
+            const USES_V9_CORRECTION_FACTORS: bool = false;
+            /// RESERVED0, RESERVED1: _64r.h
+            const RESERVED_FIELDS: &'static [(u32, u32)] = &[(21, 2), (23, 1)];
+
+            fn encoding_family() -> &'static str {
+                "RISC-V Y"
+            }
+
+            /// PERMS_ALL (0x7003f), mostly encoded in AP_M: _64r.h:127
+            fn arch_perm_count() -> u32 {
+                9
+            }
+            /// UPERMS_ALL (SDP): _64r.h:120
+            fn software_perm_count() -> u32 {
+                2
+            }
 
             ccap_impl_funcs! {cc64r}
         }
@@ -282,6 +690,8 @@ pub mod rvy {
 
     pub mod cc128 {
         use crate::c_funcs::*;
+        use crate::bounds::{BaseTopSpecialCases, EbtLayout, ExponentEncoding};
+        use crate::fields::{Field, PermsEncoding, PesbtFields};
         use crate::{CcxBoundsBits, CcxCap, CompressedCapability};
         use paste::paste;
 
@@ -294,12 +704,35 @@ pub mod rvy {
         pub type Cap = CcxCap<Cc128>;
         pub type Cc128Cap = Cap;
 
+        /// Permission, otype and flags field layout, see _128r.h
+        pub(crate) const PESBT_FIELDS: PesbtFields = PesbtFields {
+            perms: PermsEncoding::Compressed {
+                sdp: Field { start: 53, width: 4 },
+            },
+            otype: Field { start: 27, width: 1 },
+            flags: Field { start: 52, width: 1 },
+        };
+
+        /// Bounds field layout, see _128r.h
+        pub(crate) const EBT_LAYOUT: EbtLayout = EbtLayout {
+            mantissa_width: Cc128::MANTISSA_WIDTH,
+            exp_part_width: 3,
+            ie_inverted: true,
+            exponent: ExponentEncoding::SubtractedFrom(52),
+            len_msb: false,
+            addr_width: 64,
+            bounds_addr_width: 64,
+            max_exponent: 52,
+            v9_correction: Cc128::USES_V9_CORRECTION_FACTORS,
+            special_cases: BaseTopSpecialCases::Malformed,
+        };
+
         /// Implements [CompressedCapability] for RISC-V Y 128-bit capabilities.
         ///
         /// Empty enum, so it cannot be itself constructed. If you want a CC128 capability, instantiate Cc128Cap.
         ///
-        /// Derives Debug, Copy, Clone so that CcxCap<Cc128> can derive them too.
-        #[derive(Debug, Copy, Clone)]
+        /// Derives the supertraits of [CompressedCapability] so that CcxCap<Cc128> can derive them too.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
         pub enum Cc128 {}
         impl CompressedCapability for Cc128 {
             type Length = Length;
@@ -308,6 +741,12 @@ pub mod rvy {
 
             type FfiLength = FfiLength;
             type FfiOffset = FfiOffset;
+            /// _CC_N(CAP_SIZE)
+            type MemBytes = [u8; 16];
+
+            const CAP_SIZE_BYTES: usize = 16;
+            const ADDR_WIDTH_BITS: u32 = 64;
+            const CAP_MAX_LENGTH: Length = 1 << 64;
 
             // Lots of permissions are missing
             const PERM_GLOBAL: u32 = 0;
@@ -330,6 +769,30 @@ pub mod rvy {
             const OTYPE_RESERVED2: u32 = u32::MAX;
             const OTYPE_RESERVED3: u32 = u32::MAX;
             const MAX_UNRESERVED_OTYPE: u32 = u32::MAX;
+            /// MODE_CAP: _128r.h:72 (the M bit is set for integer mode)
+            const FLAGS_CAP_MODE: Option<u8> = Some(0);
+            /// RISC-V Y only has sentries, RESERVED2/RESERVED3 don't exist
+            const RESERVED_OTYPES: &'static [u32] = &[Self::OTYPE_UNSEALED, Self::OTYPE_SENTRY];
+
+            const IS_CHERI_CONCENTRATE: bool = true;
+            /// _128r.h:48
+            const MANTISSA_WIDTH: u32 = 14;
+            const USES_V9_CORRECTION_FACTORS: bool = false;
+            /// RESERVED0, RESERVED1: _128r.h
+            const RESERVED_FIELDS: &'static [(u32, u32)] = &[(28, 15), (57, 7)];
+
+            fn encoding_family() -> &'static str {
+                "RISC-V Y"
+            }
+
+            /// PERMS_ALL (0x7003f), mostly encoded in AP: _128r.h:125
+            fn arch_perm_count() -> u32 {
+                9
+            }
+            /// UPERMS_ALL (SDP): _128r.h:119
+            fn software_perm_count() -> u32 {
+                4
+            }
 
             ccap_impl_funcs!(cc128r);
         }
@@ -360,7 +823,7 @@ pub mod cheri256 {
         // TODO in-memory and in-register representations with {de,}compress_256cap() C function
         
         pub const MAX_REPRESENTABLE_OTYPE: u32 = (1u32 << 24) - 1;
-        pub const OTYPE_UNSEALED: u32 = Self::MAX_REPRESENTABLE_OTYPE - 0;
+        pub const OTYPE_UNSEALED: u32 = Self::MAX_REPRESENTABLE_OTYPE;
         pub const OTYPE_SENTRY: u32 = Self::MAX_REPRESENTABLE_OTYPE - 1;
 
         pub fn tag(&self) -> bool {
@@ -399,9 +862,7 @@ pub mod cheri256 {
         }
 
         pub fn offset(&self) -> Offset {
-            let cursor: Offset = self.cr_cursor.into();
-            let base: Offset = self.cr_base.into();
-            (cursor - base).into()
+            self.cr_cursor - self.cr_base
         }
         // TODO top64
 
@@ -451,7 +912,7 @@ pub mod cheri256 {
         pub fn is_exact(&self) -> bool {
             true
         }
-        pub fn is_representable_with_new_addr(&self, new_addr: Addr) -> bool {
+        pub fn is_representable_with_new_addr(&self, _new_addr: Addr) -> bool {
             true
         }
 
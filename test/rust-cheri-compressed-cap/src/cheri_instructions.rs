@@ -0,0 +1,123 @@
+//! Models of individual CHERI instructions operating on [CcxCap]s.
+//!
+//! These are free functions rather than [CcxCap] methods because they mirror
+//! instructions taking multiple capability operands.
+
+use crate::{CcxCap, CompressedCapability};
+use num_traits::{Bounded, WrappingSub};
+use std::cmp::Ordering;
+
+/// Models the `CPtrCmp` family of instructions (`CEQ`, `CLT`, `CLTU`, ...).
+///
+/// Compares the addresses of `a` and `b` as unsigned integers.
+/// As specified by the architecture, tags, bounds, permissions and otypes are ignored entirely.
+pub fn cptr_cmp<T: CompressedCapability>(a: &CcxCap<T>, b: &CcxCap<T>) -> Ordering {
+    a.address().cmp(&b.address())
+}
+
+/// Models the `CSub` instruction.
+///
+/// Returns `a.address() - b.address()`, wrapping around on underflow.
+/// As specified by the architecture, tags, bounds, permissions and otypes are ignored entirely.
+pub fn csub<T: CompressedCapability>(a: &CcxCap<T>, b: &CcxCap<T>) -> T::Addr {
+    a.address().wrapping_sub(&b.address())
+}
+
+/// Models the `CCopyType` instruction: returns `dest` with its cursor set to the otype of `src`.
+///
+/// If `src` is unsealed the cursor is set to `-1` (all ones), the sentinel for "no otype",
+/// and other reserved otypes (e.g. [CompressedCapability::OTYPE_SENTRY] in CHERIv9) are sign-extended in the same way.
+/// The tag is cleared if `dest` is sealed, if `src` has a reserved otype (the result is just an integer),
+/// or if the new cursor is outside `dest`'s bounds.
+/// Every in-bounds cursor is representable, so the last case covers the unrepresentable results.
+pub fn ccopy_type<T: CompressedCapability>(dest: &CcxCap<T>, src: &CcxCap<T>) -> CcxCap<T>
+where
+    T::Addr: From<u32>,
+{
+    let otype = src.otype();
+    let reserved = !src.is_sealed() || otype > T::MAX_UNRESERVED_OTYPE;
+    let new_addr: T::Addr = if !src.is_sealed() {
+        T::Addr::max_value()
+    } else if reserved {
+        T::Addr::max_value() - (T::MAX_REPRESENTABLE_OTYPE - otype).into()
+    } else {
+        otype.into()
+    };
+
+    let new_addr_len: T::Length = new_addr.into();
+    let in_bounds = new_addr >= dest.base() && new_addr_len < dest.top();
+
+    let mut result = *dest;
+    result.set_address_unchecked(new_addr);
+    if dest.is_sealed() || reserved || !in_bounds {
+        result.set_tag(false);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caps::cheriv9::cc128::Cc128;
+
+    #[test]
+    fn test_cptr_cmp() {
+        let a = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        // Different bounds and no tag, but a higher address
+        let b = Cc128::make_max_perms_cap(0, 0x1800, 0x1900).with_tag(false);
+
+        assert_eq!(cptr_cmp(&a, &b), Ordering::Less);
+        assert_eq!(cptr_cmp(&b, &a), Ordering::Greater);
+        assert_eq!(cptr_cmp(&a, &a.with_tag(false)), Ordering::Equal);
+
+        // Comparison is unsigned
+        let mut high = a;
+        high.set_address_unchecked(u64::MAX);
+        assert_eq!(cptr_cmp(&a, &high), Ordering::Less);
+    }
+
+    #[test]
+    fn test_csub() {
+        let a = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let b = Cc128::make_max_perms_cap(0, 0x1800, 0x1900).with_tag(false);
+
+        assert_eq!(csub(&b, &a), 0x800);
+        // a - b wraps
+        assert_eq!(csub(&a, &b), 0u64.wrapping_sub(0x800));
+        assert_eq!(csub(&a, &a), 0);
+    }
+
+    #[test]
+    fn test_ccopy_type_sealed() {
+        let dest = Cc128::make_max_perms_cap(0, 0, 0x1000);
+        let mut src = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        src.set_otype(0x123);
+
+        let result = ccopy_type(&dest, &src);
+        assert_eq!(result.address(), 0x123);
+        assert!(result.tag());
+        assert_eq!(result.bounds(), dest.bounds());
+
+        // otype outside of dest's bounds
+        let small_dest = Cc128::make_max_perms_cap(0, 0, 0x100);
+        let result = ccopy_type(&small_dest, &src);
+        assert_eq!(result.address(), 0x123);
+        assert!(!result.tag());
+    }
+
+    #[test]
+    fn test_ccopy_type_unsealed() {
+        let dest = Cc128::make_max_perms_cap(0, 0, 0x1000);
+        let src = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(!src.is_sealed());
+
+        let result = ccopy_type(&dest, &src);
+        assert_eq!(result.address(), u64::MAX);
+        assert!(!result.tag());
+
+        // Sentries have a reserved otype in CHERIv9, which is sign-extended
+        let mut sentry = src;
+        sentry.set_otype(Cc128::OTYPE_SENTRY);
+        assert_eq!(ccopy_type(&dest, &sentry).address(), -2i64 as u64);
+    }
+}
@@ -0,0 +1,246 @@
+//! Rust implementation of the CHERI Concentrate bounds encoding for CHERIv9 128-bit capabilities,
+//! following `cheri_compressed_cap_common.h`.
+//!
+//! With the `rust-bounds` feature enabled, [Cc128](crate::caps::cheriv9::cc128::Cc128) uses these in place of the
+//! C functions for decompressing, compressing, extracting the bounds bits, and setting bounds.
+//! The remaining operations (e.g. [CompressedCapability::make_max_perms_cap](crate::CompressedCapability::make_max_perms_cap))
+//! still call into the C library, so it is still built and linked.
+//!
+//! The C debug assertions are replicated with `debug_assert!`, so misuse panics instead of aborting the process.
+
+use crate::caps::cheriv9::cc128::{Cap, Cc128, EBT_LAYOUT, PESBT_FIELDS};
+use crate::{CcxBoundsBits, CompressedCapability};
+
+/// _CC_ADDR_WIDTH
+const ADDR_WIDTH: u32 = 64;
+/// _CC_LEN_WIDTH
+const LEN_WIDTH: u32 = 65;
+/// _CC_EXP_LOW_WIDTH
+const EXP_LOW_WIDTH: u32 = 3;
+/// _CC_BOT_INTERNAL_EXP_WIDTH
+const BOT_INTERNAL_EXP_WIDTH: u32 = Cc128::MANTISSA_WIDTH - EXP_LOW_WIDTH;
+/// _CC_N(FIELD_EBT_MASK64), relative to the start of the pesbt
+const EBT_MASK: u64 = (1 << 27) - 1;
+/// _CC_N(MEM_XOR_MASK), i.e. the pesbt of the NULL capability
+pub(crate) const MEM_XOR_MASK: u64 = 0x0000_1fff_fc01_8004;
+
+fn truncate(value: u64, width: u32) -> u64 {
+    value & ((1 << width) - 1)
+}
+
+/// Equivalent to the C `extract_bounds_bits`
+pub(crate) fn extract_bounds_bits(pesbt: u64) -> CcxBoundsBits {
+    EBT_LAYOUT.decode(pesbt)
+}
+
+/// Equivalent to the C `decompress_raw`
+pub(crate) fn decompress_raw(pesbt: u64, cursor: u64, tag: bool) -> Cap {
+    let bounds = extract_bounds_bits(pesbt);
+    let (base, top) = EBT_LAYOUT.compute_base_top(bounds, cursor);
+    let cap = Cap {
+        _cr_cursor: cursor,
+        cr_pesbt: pesbt,
+        _cr_top: top,
+        cr_base: base,
+        cr_tag: tag as u8,
+        cr_bounds_valid: 1,
+        cr_exp: bounds.e,
+        cr_extra: 0,
+    };
+    if tag {
+        debug_assert!(cap._cr_top <= 1 << ADDR_WIDTH, "tagged capability with top above 2^64");
+        debug_assert!(cap.cr_base as u128 <= cap._cr_top, "tagged capability with base above top");
+        debug_assert!(
+            crate::fields::reserved(Cc128::RESERVED_FIELDS, pesbt) == 0,
+            "unknown reserved bits set in tagged capability"
+        );
+    }
+    cap
+}
+
+/// Equivalent to the C `compress_raw`
+pub(crate) fn compress_raw(cap: &Cap) -> u64 {
+    debug_assert!(
+        {
+            let decoded = decompress_raw(cap.cr_pesbt, cap._cr_cursor, false);
+            (decoded._cr_top, decoded.cr_base, decoded.cr_exp) == (cap._cr_top, cap.cr_base, cap.cr_exp)
+        },
+        "capability bounds were modified without updating pesbt"
+    );
+    cap.cr_pesbt
+}
+
+/// Equivalent to the C `decompress_mem`
+pub(crate) fn decompress_mem(pesbt: u64, cursor: u64, tag: bool) -> Cap {
+    decompress_raw(pesbt ^ MEM_XOR_MASK, cursor, tag)
+}
+
+/// Equivalent to the C `compress_mem`
+pub(crate) fn compress_mem(cap: &Cap) -> u64 {
+    compress_raw(cap) ^ MEM_XOR_MASK
+}
+
+/// Equivalent to the C `get_exponent`
+fn get_exponent(length: u128) -> u32 {
+    let mw = Cc128::MANTISSA_WIDTH;
+    if length > u64::MAX as u128 {
+        LEN_WIDTH - (mw - 1)
+    } else if length < 1 << (mw - 1) {
+        0
+    } else {
+        // Index of the most significant set bit, minus (mw - 2)
+        (127 - length.leading_zeros()) - (mw - 2)
+    }
+}
+
+/// Equivalent to the C `compute_ebt`: returns the EBT bits for the smallest representable bounds
+/// containing `[req_base, req_top)`, and whether they are exactly the requested bounds.
+fn compute_ebt(req_base: u64, req_top: u128) -> (u64, bool) {
+    debug_assert!(req_base as u128 <= req_top, "Cannot invert base and top");
+    let req_length = req_top - req_base as u128;
+    let e = get_exponent(req_length);
+
+    // Use the internal exponent if e is non-zero, or if the implied bit of the length is set (normal vs. denormal)
+    let internal_exponent = e != 0 || (req_length >> (BOT_INTERNAL_EXP_WIDTH + 1)) & 1 != 0;
+    if !internal_exponent {
+        // Exactly representable: just store the relevant bits
+        let bits = CcxBoundsBits {
+            b: req_base as u16,
+            t: req_top as u16,
+            e: 0,
+            ie: false,
+        };
+        return (EBT_LAYOUT.encode(bits), true);
+    }
+
+    // Drop the low bits of base and top (including EXP_LOW_WIDTH for the exponent)
+    let shift = e + EXP_LOW_WIDTH;
+    let mut bot_ie = truncate(req_base >> shift, BOT_INTERNAL_EXP_WIDTH);
+    let mut top_ie = truncate((req_top >> shift) as u64, BOT_INTERNAL_EXP_WIDTH);
+    let mask_lo = (1u128 << shift) - 1;
+    let mut lost_significant_base = (req_base as u128 & mask_lo) != 0;
+    let mut lost_significant_top = (req_top & mask_lo) != 0;
+    // Increment T to make sure it's still above top even with lost bits.
+    // It might wrap around, but if that makes B < T then decoding will compensate.
+    if lost_significant_top {
+        top_ie = truncate(top_ie + 1, BOT_INTERNAL_EXP_WIDTH);
+    }
+
+    // The exponent was chosen so the top two bits of the length would be 0b01,
+    // but incrementing T or losing bits of the base may have made it overflow: increment E and recalculate
+    let len_ie = truncate(top_ie.wrapping_sub(bot_ie), BOT_INTERNAL_EXP_WIDTH);
+    let mut new_e = e;
+    if (len_ie >> (BOT_INTERNAL_EXP_WIDTH - 1)) & 1 != 0 {
+        new_e += 1;
+        lost_significant_base |= bot_ie & 1 != 0;
+        lost_significant_top |= top_ie & 1 != 0;
+        bot_ie = truncate(req_base >> (shift + 1), BOT_INTERNAL_EXP_WIDTH);
+        top_ie = truncate((req_top >> (shift + 1)) as u64, BOT_INTERNAL_EXP_WIDTH);
+        if lost_significant_top {
+            top_ie = truncate(top_ie + 1, BOT_INTERNAL_EXP_WIDTH);
+        }
+    }
+
+    let bits = CcxBoundsBits {
+        b: (bot_ie << EXP_LOW_WIDTH) as u16,
+        t: (top_ie << EXP_LOW_WIDTH) as u16,
+        e: new_e as u8,
+        ie: true,
+    };
+    (EBT_LAYOUT.encode(bits), !lost_significant_base && !lost_significant_top)
+}
+
+/// Equivalent to the C `setbounds`: sets the bounds to the smallest representable bounds containing
+/// `[cursor, cursor + req_len)`, clearing the tag if the capability is sealed or the request isn't within the current bounds.
+///
+/// Returns true if the bounds are exactly the requested bounds.
+pub(crate) fn set_bounds(cap: &mut Cap, req_len: u128) -> bool {
+    let req_base = cap._cr_cursor;
+    if PESBT_FIELDS.otype(cap.cr_pesbt) != Cc128::OTYPE_UNSEALED {
+        // Detag sealed inputs to maintain invariants
+        cap.cr_tag = 0;
+    }
+    let req_top = req_base as u128 + req_len;
+    if req_base < cap.cr_base || req_top > cap._cr_top {
+        cap.cr_tag = 0;
+    }
+
+    let (new_ebt, exact) = compute_ebt(req_base, req_top);
+    let new_bounds = extract_bounds_bits(new_ebt);
+    let (new_base, new_top) = EBT_LAYOUT.compute_base_top(new_bounds, req_base);
+    debug_assert!(
+        exact == (new_base == req_base && new_top == req_top),
+        "exactness doesn't match the new bounds"
+    );
+    if cap.cr_tag != 0 {
+        debug_assert!(new_base >= cap.cr_base, "Cannot reduce base on tagged capabilities");
+        debug_assert!(new_top <= cap._cr_top, "Cannot increase top on tagged capabilities");
+    }
+
+    cap.cr_base = new_base;
+    cap._cr_top = new_top;
+    cap.cr_pesbt = (cap.cr_pesbt & !EBT_MASK) | new_ebt;
+    cap.cr_exp = new_bounds.e;
+    cap.cr_bounds_valid = 1;
+    exact
+}
+
+#[cfg(test)]
+mod tests {
+    //! Differential tests against the C implementation
+    use super::*;
+    use crate::c_funcs::*;
+    use crate::testing::Lcg;
+
+    fn c_decompress_raw(pesbt: u64, cursor: u64, tag: bool) -> Cap {
+        let mut cap = Cap::default();
+        unsafe { cc128_decompress_raw(pesbt, cursor, tag, &mut cap) };
+        cap
+    }
+
+    #[test]
+    fn test_decompress_matches_c() {
+        let mut rng = Lcg::new(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..100_000 {
+            let pesbt = rng.next_u64();
+            let cursor = rng.next_u64();
+
+            let c_bits = unsafe { cc128_extract_bounds_bits(pesbt) };
+            assert_eq!(extract_bounds_bits(pesbt), c_bits, "pesbt = {:#x}", pesbt);
+
+            // Untagged, so neither implementation checks the bounds are valid
+            let rust = decompress_raw(pesbt, cursor, false);
+            let c = c_decompress_raw(pesbt, cursor, false);
+            assert_eq!(rust.raw_cache(), c.raw_cache(), "pesbt = {:#x} cursor = {:#x}", pesbt, cursor);
+            assert_eq!(rust.reg_representation(), c.reg_representation());
+            assert_eq!(rust.cr_bounds_valid, c.cr_bounds_valid);
+
+            let mut c_mem = Cap::default();
+            unsafe { cc128_decompress_mem(pesbt, cursor, false, &mut c_mem) };
+            assert_eq!(decompress_mem(pesbt, cursor, false).raw_cache(), c_mem.raw_cache());
+        }
+    }
+
+    #[test]
+    fn test_set_bounds_matches_c() {
+        let mut rng = Lcg::new(0x2545_f491_4f6c_dd1d);
+        let root = c_decompress_raw(MEM_XOR_MASK | (Cc128::OTYPE_UNSEALED as u64) << 27, 0, true);
+        for i in 0..100_000 {
+            let base = rng.next_u64();
+            // Cover the whole range of exponents
+            let length = (rng.next_u64() as u128) >> (i % 64);
+            let length = length.min((1u128 << 64) - base as u128);
+
+            let mut rust = root;
+            rust._cr_cursor = base;
+            let mut c = rust;
+            let rust_exact = set_bounds(&mut rust, length);
+            let c_exact = unsafe { cc128_setbounds(&mut c, length) };
+            assert_eq!(rust_exact, c_exact, "base = {:#x} length = {:#x}", base, length);
+            assert_eq!(rust.raw_cache(), c.raw_cache(), "base = {:#x} length = {:#x}", base, length);
+            assert_eq!(rust.reg_representation(), c.reg_representation());
+            assert_eq!(compress_raw(&rust), unsafe { cc128_compress_raw(&c) });
+            assert_eq!(compress_mem(&rust), unsafe { cc128_compress_mem(&c) });
+        }
+    }
+}
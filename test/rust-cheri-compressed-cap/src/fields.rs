@@ -0,0 +1,182 @@
+//! Rust-side extraction of the non-bounds fields of [CcxCap::cr_pesbt](crate::CcxCap) (permissions, otype, flags, reserved).
+//!
+//! These mirror the `_CC_FIELD` definitions in the C headers and the C getters built on them.
+//! With the `verify-ffi` feature enabled, every call to one of the FFI getters
+//! (e.g. [CompressedCapability::get_otype](crate::CompressedCapability::get_otype)) also runs the Rust extraction
+//! and panics if the two disagree, catching drift between the C layout and the Rust bindings.
+
+use std::fmt::Debug;
+
+/// A contiguous bit field inside the pesbt.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Field {
+    /// Position of the lowest bit, relative to the start of the pesbt (i.e. `_CC_N(FIELD_*_START)`)
+    pub start: u32,
+    /// Zero for fields the format doesn't have
+    pub width: u32,
+}
+
+impl Field {
+    /// A zero-width field, which always reads as zero
+    pub const NONE: Field = Field { start: 0, width: 0 };
+
+    fn extract(&self, pesbt: u64) -> u64 {
+        if self.width == 0 {
+            0
+        } else {
+            (pesbt >> self.start) & ((1u64 << self.width) - 1)
+        }
+    }
+}
+
+/// How the permissions are laid out in the pesbt.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum PermsEncoding {
+    /// Separate hardware and user permission fields (CHERIv9)
+    Split { hwperms: Field, uperms: Field },
+    /// A single field with the user permissions `uperms_shift` bits up (Morello)
+    Combined {
+        all_perms: Field,
+        uperms_shift: u32,
+        uperms_width: u32,
+    },
+    /// Architectural permissions compressed into the `AP_M` field, user permissions in `SDP` (RISC-V Y).
+    ///
+    /// Decoding `AP_M` isn't a bit extraction, so only the user permissions are extracted in Rust.
+    Compressed { sdp: Field },
+}
+
+/// Describes where the permissions, otype and flags live inside the pesbt for a given format.
+///
+/// The reserved fields are described by [CompressedCapability::RESERVED_FIELDS](crate::CompressedCapability::RESERVED_FIELDS).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct PesbtFields {
+    pub perms: PermsEncoding,
+    pub otype: Field,
+    pub flags: Field,
+}
+
+impl PesbtFields {
+    /// Equivalent to the C `get_perms`, or None if the permissions can't be extracted in Rust
+    pub fn perms(&self, pesbt: u64) -> Option<u32> {
+        match self.perms {
+            PermsEncoding::Split { hwperms, .. } => Some(hwperms.extract(pesbt) as u32),
+            PermsEncoding::Combined {
+                all_perms,
+                uperms_shift,
+                uperms_width,
+            } => {
+                let uperms_mask = ((1u64 << uperms_width) - 1) << uperms_shift;
+                Some((all_perms.extract(pesbt) & !uperms_mask) as u32)
+            }
+            PermsEncoding::Compressed { .. } => None,
+        }
+    }
+
+    /// Equivalent to the C `get_uperms`
+    pub fn uperms(&self, pesbt: u64) -> u32 {
+        let uperms = match self.perms {
+            PermsEncoding::Split { uperms, .. } => uperms.extract(pesbt),
+            PermsEncoding::Combined {
+                all_perms,
+                uperms_shift,
+                uperms_width,
+            } => (all_perms.extract(pesbt) >> uperms_shift) & ((1u64 << uperms_width) - 1),
+            PermsEncoding::Compressed { sdp } => sdp.extract(pesbt),
+        };
+        uperms as u32
+    }
+
+    /// Equivalent to the C `get_otype`
+    pub fn otype(&self, pesbt: u64) -> u32 {
+        self.otype.extract(pesbt) as u32
+    }
+
+    /// Equivalent to the C `get_flags`
+    pub fn flags(&self, pesbt: u64) -> u8 {
+        self.flags.extract(pesbt) as u8
+    }
+}
+
+/// Equivalent to the C `get_reserved`, given a format's `RESERVED_FIELDS`.
+///
+/// The C function returns the reserved bits masked but *not* shifted down,
+/// which is then truncated to the `u8` the FFI declares - this is replicated exactly.
+pub(crate) fn reserved(reserved_fields: &[(u32, u32)], pesbt: u64) -> u8 {
+    let mask = reserved_fields
+        .iter()
+        .fold(0u64, |mask, &(start, width)| mask | (((1u64 << width) - 1) << start));
+    (pesbt & mask) as u8
+}
+
+/// Returns `ffi`, first checking it against `rust()` if the `verify-ffi` feature is enabled.
+///
+/// `rust` returns None if there is no Rust equivalent to check against.
+#[inline]
+pub(crate) fn cross_check<V: PartialEq + Debug>(getter: &str, pesbt: u64, ffi: V, rust: impl FnOnce() -> Option<V>) -> V {
+    if cfg!(feature = "verify-ffi") {
+        if let Some(rust) = rust() {
+            assert_eq!(ffi, rust, "FFI {} disagrees with Rust extraction for pesbt = {:#x}", getter, pesbt);
+        }
+    }
+    ffi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CompressedCapability;
+
+    /// Decompresses random pesbt values (untagged, so the C doesn't check them)
+    /// and checks the Rust extraction matches the FFI getters.
+    fn check_against_ffi<T: CompressedCapability>(fields: &PesbtFields)
+    where
+        T::Addr: num_traits::AsPrimitive<u64>,
+        u64: num_traits::AsPrimitive<T::Addr>,
+    {
+        use num_traits::AsPrimitive;
+
+        let mut rng = crate::testing::Lcg::new(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..10_000 {
+            let cap = T::decompress_raw(rng.next_u64().as_(), 0.as_(), false);
+            let pesbt: u64 = cap.cr_pesbt.as_();
+
+            // These go through cross_check, so also test it if verify-ffi is enabled
+            if let Some(perms) = fields.perms(pesbt) {
+                assert_eq!(T::get_perms(&cap), perms, "perms, pesbt = {:#x}", pesbt);
+            }
+            assert_eq!(T::get_uperms(&cap), fields.uperms(pesbt), "uperms, pesbt = {:#x}", pesbt);
+            assert_eq!(T::get_otype(&cap), fields.otype(pesbt), "otype, pesbt = {:#x}", pesbt);
+            assert_eq!(T::get_flags(&cap), fields.flags(pesbt), "flags, pesbt = {:#x}", pesbt);
+            assert_eq!(
+                T::get_reserved(&cap),
+                reserved(T::RESERVED_FIELDS, pesbt),
+                "reserved, pesbt = {:#x}",
+                pesbt
+            );
+        }
+    }
+
+    #[test]
+    fn test_pesbt_fields_match_ffi() {
+        use crate::caps::*;
+
+        check_against_ffi::<cheriv9::cc64::Cc64>(&cheriv9::cc64::PESBT_FIELDS);
+        check_against_ffi::<cheriv9::cc128::Cc128>(&cheriv9::cc128::PESBT_FIELDS);
+        check_against_ffi::<morello::Cc128>(&morello::PESBT_FIELDS);
+        check_against_ffi::<rvy::cc64::Cc64>(&rvy::cc64::PESBT_FIELDS);
+        check_against_ffi::<rvy::cc128::Cc128>(&rvy::cc128::PESBT_FIELDS);
+    }
+
+    #[test]
+    fn test_pesbt_fields_known_values() {
+        use crate::caps::cheriv9::cc128::{Cc128, PESBT_FIELDS};
+
+        let cap = Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        let pesbt = cap.cr_pesbt;
+        assert_eq!(PESBT_FIELDS.perms(pesbt), Some(0xfff));
+        assert_eq!(PESBT_FIELDS.uperms(pesbt), 0xf);
+        assert_eq!(PESBT_FIELDS.otype(pesbt), Cc128::OTYPE_UNSEALED);
+        assert_eq!(PESBT_FIELDS.flags(pesbt), 0);
+    }
+}
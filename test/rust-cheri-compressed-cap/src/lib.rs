@@ -1,9 +1,13 @@
-use num_traits::{Num, WrappingAdd};
-use std::fmt::Debug;
+use num_traits::{AsPrimitive, Bounded, CheckedAdd, CheckedSub, Num, One, PrimInt, WrappingAdd, WrappingSub, Zero};
+use std::fmt::{Debug, LowerHex, UpperHex};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 
 /// Trait that the field types defined in CompressedCapability (Length, Offset, Addr) have to implement.
-/// This asserts that a) they're numeric, b) they support Default/Copy/Clone/Debug so that CcxCap can derive these.
-pub trait NumType: Default + Num + WrappingAdd + Copy + Clone + Debug + PartialOrd + Ord {}
+/// This asserts that a) they're numeric (primitive integers, so bitwise operations are available),
+/// b) they support Default/Copy/Clone/Debug so that CcxCap can derive these,
+/// c) they support Hash so that CcxCap can implement it.
+pub trait NumType: 'static + Default + Num + PrimInt + Bounded + WrappingAdd + WrappingSub + Copy + Clone + Debug + LowerHex + UpperHex + PartialOrd + Ord + Hash {}
 impl NumType for u32 {}
 impl NumType for u64 {}
 impl NumType for u128 {}
@@ -29,22 +33,38 @@ impl FfiNumType<i128> for i128 {}
 ///
 /// It is not recommended to call the trait functions directly.
 /// Instead, use one of the [crate::wrappers].
-pub trait CompressedCapability: Sized + Copy + Clone {
+///
+/// The supertraits let types generic over a format (e.g. [Flags]) `#[derive]` them,
+/// as the formats are uninhabited marker types which derive them all.
+pub trait CompressedCapability: Sized + Copy + Clone + Debug + PartialEq + Eq + Hash {
     /// ccx_length_t Rust-land equivalent - should be a superset of Addr
-    type Length: NumType + From<Self::Addr>;
+    type Length: NumType + From<Self::Addr> + AsPrimitive<Self::Addr>;
     /// ccx_offset_t Rust-land equivalent - should be a superset of Addr
-    type Offset: NumType + From<Self::Addr>;
+    type Offset: NumType + From<Self::Addr> + AsPrimitive<Self::Addr>;
     /// ccx_addr_t equivalent
     type Addr: NumType + Into<Self::Offset> + Into<Self::Length>;
 
     /// ccx_length_t C-land equivalent - should have a memory layout identical to the C ccx_length_t.
     /// This is separate from Length because for 128-bit types the Rust and C versions may not look the same.
-    /// In practice, we just assume they are the same (see [crate::c_funcs] documentation).
+    /// In practice, we just assume they are the same (see [crate::c_funcs] documentation),
+    /// so every format uses the native `u128`/`u64` for both and the conversions are no-ops.
+    /// There is no separate 128-bit wrapper type to do arithmetic on.
     type FfiLength: FfiNumType<Self::Length>;
     /// ccx_offset_t C-land equivalent - should have a memory layout identical to the C ccx_offset_t.
     /// See [Self::FfiLength] for an explanation.
     type FfiOffset: FfiNumType<Self::Offset>;
 
+    /// A capability as stored in memory (without the tag), i.e. `[u8; 2 * size_of::<Self::Addr>()]`.
+    /// See [CcxCap::to_mem_bytes].
+    type MemBytes: Copy + Default + Debug + PartialEq + Eq + AsRef<[u8]> + AsMut<[u8]>;
+
+    /// _CC_N(CAP_SIZE) equivalent: the size of a capability in memory (without the tag), i.e. the size of [Self::MemBytes]
+    const CAP_SIZE_BYTES: usize;
+    /// _CC_N(ADDR_WIDTH) equivalent: the width of [Self::Addr]
+    const ADDR_WIDTH_BITS: u32;
+    /// The top of a capability covering the whole address space, i.e. `2^ADDR_WIDTH_BITS`
+    const CAP_MAX_LENGTH: Self::Length;
+
     /// CCX_PERM_GLOBAL equivalent
     /// These are the same for 64 and 128bit, but should be overridden for Morello-128
     const PERM_GLOBAL: u32 = (1 << 0);
@@ -67,6 +87,34 @@ pub trait CompressedCapability: Sized + Copy + Clone {
     const OTYPE_RESERVED2: u32;
     const OTYPE_RESERVED3: u32;
     const MAX_UNRESERVED_OTYPE: u32;
+    /// `_CC_N(MODE_CAP)` equivalent: the value of the flags field in capability mode, or None if the format
+    /// doesn't store a mode in the capability (Morello, where it's part of PSTATE).
+    /// The other value of the one-bit field is integer mode.
+    const FLAGS_CAP_MODE: Option<u8>;
+    /// The otypes with a special meaning, which can't be used to seal with an arbitrary object type:
+    /// [Self::OTYPE_UNSEALED], [Self::OTYPE_SENTRY], and any others the format reserves.
+    const RESERVED_OTYPES: &'static [u32];
+
+    /// True if bounds are encoded with CHERI Concentrate, i.e. a floating-point base/top pair sharing one exponent,
+    /// with the exponent stored inside the bounds fields when it's nonzero (the "internal exponent" optimization).
+    ///
+    /// All formats currently implemented use CHERI Concentrate, but future ones may not.
+    const IS_CHERI_CONCENTRATE: bool;
+
+    /// _CC_MANTISSA_WIDTH equivalent, i.e. the width of [CcxBoundsBits::b]
+    const MANTISSA_WIDTH: u32;
+    /// _CC_N(USES_V9_CORRECTION_FACTORS) equivalent.
+    /// If true (CHERIv9 and Morello), the representable region starts at an eighth-of-a-region boundary below the base.
+    /// Otherwise (RISC-V Y), it starts exactly a quarter of a region below the base.
+    const USES_V9_CORRECTION_FACTORS: bool;
+    /// The `(start, width)` of each reserved field in [CcxCap::cr_pesbt], lowest first.
+    /// Empty if the format has no reserved bits. See [reserved::ReservedBits].
+    const RESERVED_FIELDS: &'static [(u32, u32)];
+
+    /// Returns true if `otype` is one of [Self::RESERVED_OTYPES]
+    fn is_reserved_otype(otype: u32) -> bool {
+        Self::RESERVED_OTYPES.contains(&otype)
+    }
 
     // Adapted, Rust-safe version of the C API
     // Should be defined by building a wrapper around a linked C function
@@ -158,9 +206,42 @@ pub trait CompressedCapability: Sized + Copy + Clone {
 
     /* Misc */
 
+    /// Returns the name of the encoding family this format belongs to, e.g. `"CHERIv9"` or `"Morello"`.
+    ///
+    /// Formats of different sizes from the same family (e.g. CHERIv9 CC64 and CC128) return the same name.
+    fn encoding_family() -> &'static str;
+
+    /// The number of architectural permission bits defined by this format (e.g. 12 for CHERIv9 CC128).
+    ///
+    /// This counts every architectural permission, including those without a `PERM_*` constant in this trait
+    /// (e.g. Morello's `EXECUTIVE`), so it can be larger than the number of nonzero `PERM_*` constants.
+    fn arch_perm_count() -> u32;
+
+    /// The number of software (user) permission bits defined by this format (e.g. 4 for CHERIv9 CC128),
+    /// i.e. the width of [Self::get_uperms].
+    fn software_perm_count() -> u32;
+
     /// Extracts the floating-point encoded bounds from [CcxCap::cr_pesbt]
     fn extract_bounds_bits(pesbt: Self::Addr) -> CcxBoundsBits;
 
+    /// Encodes explicit bounds field values into the bounds portion of a [CcxCap::cr_pesbt], with all other fields zeroed.
+    ///
+    /// This is the inverse of [Self::extract_bounds_bits], and is useful for constructing arbitrary (including invalid)
+    /// encodings e.g. to fuzz the decoder.
+    /// The top two bits of `t` are not stored, they are inferred from `b` on decode,
+    /// and if `ie` is set the low bits of `b` and `t` are replaced by the exponent.
+    /// `extract_bounds_bits(encode_bounds_bits(x)) == x` only holds for `x` where these bits are consistent,
+    /// e.g. any value returned by [Self::extract_bounds_bits].
+    fn encode_bounds_bits(bits: CcxBoundsBits) -> Self::Addr;
+
+    /// Reconstructs the base and top encoded by `bits` for a capability with the given cursor,
+    /// i.e. the values [Self::decompress_raw] stores in [CcxCap::cr_base] and [CcxCap::_cr_top].
+    ///
+    /// The higher bits of base and top are taken from the cursor, corrected depending on which side of the
+    /// representable region boundary the cursor, `B`, and `T` fall.
+    /// Base and top are not ordered for some invalid encodings.
+    fn compute_base_top(bits: CcxBoundsBits, cursor: Self::Addr) -> (Self::Addr, Self::Length);
+
     /// Sets the capability bounds to bounds that encompass ((cursor), (cursor+req_len)).
     /// Because a floating-point representation is used for bounds, it may not be able to set (req_base, req_top) exactly.
     /// In this case it will return False.
@@ -171,14 +252,23 @@ pub trait CompressedCapability: Sized + Copy + Clone {
     /// Check if the range ([CcxCap::cr_base], [CcxCap::_cr_top]) can be encoded exactly with the floating-point encoding
     fn is_representable_cap_exact(cap: &CcxCap<Self>) -> bool;
 
-    /// Check if a capability with the parameters `sealed, base, length, cursor` would be representable if the cursor were updated to `new_cursor`.
-    fn is_representable_new_addr(
-        sealed: bool,
-        base: Self::Addr,
-        length: Self::Length,
-        cursor: Self::Addr,
-        new_cursor: Self::Addr,
-    ) -> bool;
+    /// Check if `cap` would still be representable, i.e. decode to the same bounds, if its cursor were updated to `new_addr`.
+    ///
+    /// If `precise` is false this uses the fast approximate check from the architecture,
+    /// which may report some representable addresses outside the bounds as unrepresentable.
+    /// Sealing is not taken into account.
+    fn is_representable_with_addr(cap: &CcxCap<Self>, new_addr: Self::Addr, precise: bool) -> bool;
+
+    /// Returns true if changing the address of `cap` can change its decoded bounds even when the new address is representable,
+    /// so the bounds must be recomputed on every address change.
+    ///
+    /// This only happens on Morello, which sign-extends the address used for bounds calculations:
+    /// see [CcxCap::bounds_uses_value].
+    /// The other formats always return false, as their bounds are fixed within the representable region.
+    fn bounds_uses_value(cap: &CcxCap<Self>) -> bool {
+        let _ = cap;
+        false
+    }
 
     /// Generate a capability for `base, top, cursor` with the maximum available permissions.
     /// Panics if the bounds are not exactly representable
@@ -200,6 +290,244 @@ pub trait CompressedCapability: Sized + Copy + Clone {
     ///
     /// See also [Self::get_representable_length], [Self::get_required_alignment].
     fn get_alignment_mask(length: Self::Length) -> Self::Length;
+
+    // Provided helpers, implemented in Rust on top of the above
+
+    /// Decompresses many `(pesbt, cursor)` pairs in memory format, with the tag for each in `tags`,
+    /// as if by calling [Self::decompress_mem] on each.
+    ///
+    /// This is for scanning a region of memory for capabilities. The output is allocated once up front,
+    /// so the only per-element cost is the decode itself: each element is still one call into C
+    /// (or into Rust, for formats implemented in Rust), which dominates for small regions.
+    /// Expect a modest speedup over collecting single [Self::decompress_mem] calls, not an order of magnitude.
+    ///
+    /// Panics if `words` and `tags` have different lengths.
+    fn decompress_mem_slice(words: &[(Self::Addr, Self::Addr)], tags: &[bool]) -> Vec<CcxCap<Self>> {
+        assert_eq!(words.len(), tags.len(), "each (pesbt, cursor) pair needs a tag");
+        // The zip has an exact size hint, so collect() allocates once
+        words
+            .iter()
+            .zip(tags)
+            .map(|(&(pesbt, cursor), &tag)| Self::decompress_mem(pesbt, cursor, tag))
+            .collect()
+    }
+
+    /// Returns an iterator over the tagged capabilities in `mem`, a snapshot of memory starting at `base_addr`,
+    /// as `(address, capability)` pairs. Granule `i` of `mem` is decoded with [Self::decompress_mem] if bit `i` of `tags` is set.
+    ///
+    /// See [CapScan](memory::CapScan) for how partial granules and malformed capabilities are handled,
+    /// and [CcxCap::to_mem_bytes] for the byte layout.
+    ///
+    /// Panics if `base_addr` isn't aligned to [Self::CAP_SIZE_BYTES].
+    fn scan_memory<'a>(
+        mem: &'a [u8],
+        tags: &'a memory::TagBitmap,
+        base_addr: Self::Addr,
+        endian: Endian,
+    ) -> memory::CapScan<'a, Self> {
+        memory::CapScan::new(mem, tags, base_addr, endian)
+    }
+
+    /// Returns a random tagged capability with exactly-representable bounds (see [align_region]),
+    /// a cursor usually within the bounds and always representable, a random subset of the permissions,
+    /// and either unsealed or sealed with a random otype.
+    /// Morello capabilities keep all architectural permissions, as the C library can't update them.
+    ///
+    /// Enabled by the `rand` feature, see [random](crate::random).
+    #[cfg(feature = "rand")]
+    fn random_cap<R: rand::Rng + ?Sized>(rng: &mut R) -> CcxCap<Self>
+    where
+        rand::distributions::Standard: rand::distributions::Distribution<Self::Addr>,
+    {
+        random::random_cap(rng)
+    }
+
+    /// Compute `top = base + length`, widening `base` to [Self::Length] before adding.
+    ///
+    /// The result may exceed the address space (e.g. `2^64` for 128-bit capabilities),
+    /// which `Self::Addr` arithmetic would overflow on.
+    /// Lengths are expected to be at most the size of the address space,
+    /// larger values wrap around in [Self::Length] instead of panicking.
+    fn compute_top(base: Self::Addr, length: Self::Length) -> Self::Length {
+        let base: Self::Length = base.into();
+        base.wrapping_add(&length)
+    }
+
+    /// Compute how far the representable bounds for the request `(req_base, req_top)` extend past it,
+    /// returning `(req_base - representable_base, representable_top - req_top)`.
+    ///
+    /// Both are zero if the request is exactly representable.
+    ///
+    /// Panics if `req_top < req_base` or `req_top` is outside the address space.
+    fn alignment_overhang(req_base: Self::Addr, req_top: Self::Length) -> (Self::Length, Self::Length) {
+        let req_base_len: Self::Length = req_base.into();
+        assert!(req_base_len <= req_top, "requested base must not be above requested top");
+        assert!(req_top <= max_top::<Self>(), "requested top must be within the address space");
+
+        let mut cap = Self::make_max_perms_cap(Self::Addr::zero(), req_base, max_top::<Self>());
+        Self::set_bounds(&mut cap, req_top - req_base_len);
+        let (base, top) = cap.bounds();
+        ((req_base - base).into(), top - req_top)
+    }
+
+    /// Compute the total number of bytes the representable bounds for the request `(req_base, req_top)`
+    /// grant beyond the request itself, i.e. the sum of both values from [Self::alignment_overhang].
+    ///
+    /// Returns zero if the request is exactly representable.
+    fn precision_loss(req_base: Self::Addr, req_top: Self::Length) -> Self::Length {
+        let (low, high) = Self::alignment_overhang(req_base, req_top);
+        low + high
+    }
+
+    /// Returns the lowest base `>= candidate` at which bounds of `length` can be set exactly,
+    /// i.e. `candidate` rounded up to [Self::get_required_alignment].
+    /// Returns `candidate` unchanged if it is already aligned, which is always the case for `length == 0`.
+    ///
+    /// Only the base is adjusted: if `length` is not itself representable
+    /// (see [Self::get_representable_length]) no base gives exact bounds.
+    /// If there is no aligned base at or above `candidate`, the result wraps around to below it.
+    fn next_representable_base(candidate: Self::Addr, length: Self::Length) -> Self::Addr {
+        // The mask only has meaningful address bits, the C function returns an address-sized value
+        let mask: Self::Addr = Self::get_alignment_mask(length).as_();
+        candidate.wrapping_add(&!mask) & mask
+    }
+
+    /// Self-test for mismatches between the Rust and C definitions of the capability structure and FFI types,
+    /// e.g. [Self::FfiLength] having a different size to the C `_cc_length_t`.
+    ///
+    /// Constructs capabilities with known bounds (including one covering the whole address space,
+    /// so the top doesn't fit in [Self::Addr]), then checks the decoded base, top and cursor match,
+    /// both directly and after a round-trip through [Self::compress_raw] and [Self::decompress_raw].
+    /// Intended to be called once at startup, e.g. `assert_eq!(Cc128::validate_field_widths(), Ok(()))`.
+    fn validate_field_widths() -> Result<(), LayoutError> {
+        let cursor: Self::Addr = (Self::Addr::one() << 12) | (Self::Addr::one() << 11);
+        let regions = [
+            (Self::Addr::one() << 12, (Self::Addr::one() << 13).into()),
+            (Self::Addr::zero(), max_top::<Self>()),
+        ];
+        for &(base, top) in regions.iter() {
+            let cap = Self::make_max_perms_cap(base, cursor, top);
+            let roundtrip = Self::decompress_raw(Self::compress_raw(&cap), cap.address(), cap.tag());
+            for cap in [cap, roundtrip].iter() {
+                if cap.base() != base {
+                    return Err(LayoutError::Base);
+                }
+                if cap.top() != top {
+                    return Err(LayoutError::Top);
+                }
+                if cap.address() != cursor {
+                    return Err(LayoutError::Cursor);
+                }
+            }
+            if roundtrip != cap {
+                return Err(LayoutError::RoundTrip);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns a value with the bottom `width` bits set.
+fn low_bits_mask<N: NumType>(width: u32) -> N {
+    if width == 0 {
+        N::zero()
+    } else {
+        N::max_value() >> (N::zero().count_zeros() - width) as usize
+    }
+}
+
+/// The top of the whole address space for capability format `T`, i.e. `2^(address width)`.
+fn max_top<T: CompressedCapability>() -> T::Length {
+    T::CAP_MAX_LENGTH
+}
+
+/// Rounds an allocation request `[base, base + len)` out to a region which format `T` can represent exactly,
+/// returning its `(base, length)`.
+///
+/// The base is rounded down to [CompressedCapability::get_alignment_mask] for the length,
+/// and the length is rounded up so the region still covers the whole request, as for
+/// [CompressedCapability::get_representable_length].
+/// Rounding the base down grows the length by up to one alignment unit, which can push it over the next
+/// exponent boundary and require a coarser alignment: the rounding is then repeated with that alignment,
+/// so the returned base may be further below `base` than the alignment for `len` alone suggests.
+/// Allocators that can choose the base should align it first (see [CompressedCapability::next_representable_base])
+/// to avoid this, in which case the result is `(base, get_representable_length(len))`.
+///
+/// [CompressedCapability::set_bounds] on the returned region is always exact.
+///
+/// Panics if `base + len` is outside the address space.
+pub fn align_region<T: CompressedCapability>(base: T::Addr, len: T::Length) -> (T::Addr, T::Length) {
+    let req_top = T::compute_top(base, len);
+    assert!(req_top <= max_top::<T>(), "requested top must be within the address space");
+
+    let req_base: T::Length = base.into();
+    let mut alignment = T::get_required_alignment(len);
+    loop {
+        let new_base = req_base - req_base % alignment;
+        let new_top = (req_top + alignment - T::Length::one()) / alignment * alignment;
+        let new_len = new_top - new_base;
+        let new_alignment = T::get_required_alignment(new_len);
+        if new_alignment <= alignment {
+            return (new_base.as_(), new_len);
+        }
+        alignment = new_alignment;
+    }
+}
+
+/// Returns the fields which differ between `a` and `b`, in the order
+/// tag, cursor, pesbt, base, top, perms, uperms, otype, flags, reserved.
+///
+/// Each field is compared in its decoded form, so a change to e.g. the otype is reported once as `otype`,
+/// and not again as `pesbt`. The pesbt is only reported if none of the other fields explain the difference,
+/// e.g. if the same bounds are encoded with different exponents.
+/// The C-only fields of [CcxCap] aren't compared, see [CcxCap::eq_exact].
+///
+/// Intended for assertion messages, which are easier to read than the [Debug] output of both capabilities:
+/// ```
+/// use rust_cheri_compressed_cap::{caps::cheriv9::cc128::Cc128, diff, CompressedCapability};
+///
+/// let a = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+/// let mut b = a;
+/// b.set_address_unchecked(0x1800);
+/// let diffs: Vec<String> = diff(&a, &b).iter().map(|d| d.to_string()).collect();
+/// assert_eq!(diffs, ["cursor: 0x1000 != 0x1800"]);
+/// ```
+pub fn diff<T: CompressedCapability>(a: &CcxCap<T>, b: &CcxCap<T>) -> Vec<FieldDiff> {
+    fn wide<N: NumType>(value: N) -> u128 {
+        num_traits::cast(value).expect("capability fields must fit in 128 bits")
+    }
+    let (_, [a_cursor, a_pesbt]) = a.reg_representation();
+    let (_, [b_cursor, b_pesbt]) = b.reg_representation();
+    let fields = [
+        ("tag", a.tag() as u128, b.tag() as u128),
+        ("cursor", wide(a_cursor), wide(b_cursor)),
+        ("base", wide(a.base()), wide(b.base())),
+        ("top", wide(a.top()), wide(b.top())),
+        ("perms", a.permissions().into(), b.permissions().into()),
+        ("uperms", a.software_permissions().into(), b.software_permissions().into()),
+        ("otype", a.otype().into(), b.otype().into()),
+        ("flags", a.flags().into(), b.flags().into()),
+        ("reserved", a.reserved_bits().into(), b.reserved_bits().into()),
+    ];
+    let mut diffs: Vec<FieldDiff> = fields
+        .iter()
+        .filter(|(_, a, b)| a != b)
+        .map(|&(field, a, b)| FieldDiff { field, a, b })
+        .collect();
+
+    let pesbt_explained = diffs.iter().any(|d| d.field != "tag" && d.field != "cursor");
+    if a_pesbt != b_pesbt && !pesbt_explained {
+        let index = diffs.iter().filter(|d| d.field == "tag" || d.field == "cursor").count();
+        diffs.insert(
+            index,
+            FieldDiff {
+                field: "pesbt",
+                a: wide(a_pesbt),
+                b: wide(b_pesbt),
+            },
+        );
+    }
+    diffs
 }
 
 #[repr(C, align(16))]
@@ -239,7 +567,8 @@ pub struct CcxCap<T: CompressedCapability> {
     /// Should be 1 (true) for all non-Morello capabilities.
     cr_bounds_valid: u8,
     /// The exponent used for storing the bounds.
-    /// Stored from various places, only used in Morello-exclusive function cap_bounds_uses_value().
+    /// Stored from various places, only used in Morello-exclusive function cap_bounds_uses_value(),
+    /// see [CcxCap::bounds_uses_value].
     cr_exp: u8,
     /// "Additional data stored by the caller."
     /// Seemingly completely unused, essentially padding.
@@ -248,6 +577,25 @@ pub struct CcxCap<T: CompressedCapability> {
 
 /// Implements getters and setters similar to the C++-only member functions in the header.
 impl<T: CompressedCapability> CcxCap<T> {
+    /// Returns the NULL capability with the integer value `addr` as its cursor, i.e. what an integer
+    /// stored in memory decodes to (untagged, with an in-memory pesbt of 0).
+    ///
+    /// This is the usual "integer as capability" value. It's distinct from a tagged capability with zero-length bounds:
+    /// it has no permissions, and its bounds are the ones decoded from the null pesbt.
+    pub fn null(addr: T::Addr) -> CcxCap<T> {
+        T::decompress_mem(T::Addr::zero(), addr, false)
+    }
+    /// Returns true if the tag is clear, i.e. this is integer data (such as [Self::null]) rather than a valid capability.
+    pub fn is_null(&self) -> bool {
+        !self.tag()
+    }
+
+    /// Returns the root ("almighty") capability: tagged, unsealed, with all permissions and software permissions,
+    /// bounds covering the whole address space (top `2^64` for 128-bit formats, `2^32` for 64-bit formats), and cursor 0.
+    pub fn root() -> CcxCap<T> {
+        T::make_max_perms_cap(T::Addr::zero(), T::Addr::zero(), max_top::<T>())
+    }
+
     /// Returns a `(tag, [cursor, pesbt])` tuple that represents all data required to
     /// store a capability in a register.
     ///
@@ -269,6 +617,91 @@ impl<T: CompressedCapability> CcxCap<T> {
         (self.tag(), [self._cr_cursor, compressed_pesbt])
     }
 
+    /// Decompresses the `(tag, [cursor, pesbt])` returned by [Self::reg_representation],
+    /// using [CompressedCapability::decompress_raw].
+    pub fn from_reg_representation(tag: bool, data: [T::Addr; 2]) -> CcxCap<T> {
+        let [cursor, pesbt] = data;
+        T::decompress_raw(pesbt, cursor, tag)
+    }
+
+    /// Decompresses the `(tag, [cursor, pesbt])` returned by [Self::mem_representation],
+    /// using [CompressedCapability::decompress_mem].
+    pub fn from_mem_representation(tag: bool, data: [T::Addr; 2]) -> CcxCap<T> {
+        let [cursor, pesbt] = data;
+        T::decompress_mem(pesbt, cursor, tag)
+    }
+
+    /// Decodes a capability loaded from memory as two address-sized words, e.g. two `u64` loads for 128-bit capabilities,
+    /// and its tag (e.g. from a separate tag cache), as a tagged-memory controller would.
+    ///
+    /// `word0` is the word at the lower address, which is the cursor, and `word1` is the in-memory pesbt,
+    /// i.e. the same order as [Self::mem_representation].
+    /// Unlike [Self::from_mem_bytes] this doesn't depend on the byte order, as the words have already been loaded.
+    ///
+    /// Counterpart: [Self::to_mem_words]
+    pub fn from_mem_words(word0: T::Addr, word1: T::Addr, tag: bool) -> CcxCap<T> {
+        Self::from_mem_representation(tag, [word0, word1])
+    }
+    /// Returns the `(word0, word1, tag)` to store in memory, see [Self::from_mem_words] for the word order.
+    pub fn to_mem_words(&self) -> (T::Addr, T::Addr, bool) {
+        let (tag, [cursor, pesbt]) = self.mem_representation();
+        (cursor, pesbt, tag)
+    }
+
+    /// Returns the bytes to store in memory with the given byte order, and the tag.
+    ///
+    /// The capability is stored as a single `2 * XLEN`-bit integer `(pesbt << XLEN) | cursor`,
+    /// so for [Endian::Little] the cursor comes first and for [Endian::Big] the pesbt comes first.
+    /// The pesbt is in the memory format (see [Self::mem_representation]).
+    /// This doesn't depend on `_CC_REVERSE_PESBT_CURSOR_ORDER`, which only changes the field order of the C structure.
+    ///
+    /// Counterpart: [Self::from_mem_bytes]
+    pub fn to_mem_bytes(&self, endian: Endian) -> (T::MemBytes, bool) {
+        let (tag, [cursor, pesbt]) = self.mem_representation();
+        let addr_bytes = std::mem::size_of::<T::Addr>();
+        let mut bytes = T::MemBytes::default();
+        let (low, high) = bytes.as_mut().split_at_mut(addr_bytes);
+        match endian {
+            Endian::Little => {
+                write_addr_bytes(cursor, low, endian);
+                write_addr_bytes(pesbt, high, endian);
+            }
+            Endian::Big => {
+                write_addr_bytes(pesbt, low, endian);
+                write_addr_bytes(cursor, high, endian);
+            }
+        }
+        (bytes, tag)
+    }
+
+    /// Decodes a capability from the bytes stored in memory with the given byte order, and its tag.
+    /// See [Self::to_mem_bytes] for the layout.
+    pub fn from_mem_bytes(bytes: &T::MemBytes, tag: bool, endian: Endian) -> CcxCap<T> {
+        let addr_bytes = std::mem::size_of::<T::Addr>();
+        let (low, high) = bytes.as_ref().split_at(addr_bytes);
+        let (cursor, pesbt) = match endian {
+            Endian::Little => (read_addr_bytes::<T::Addr>(low, endian), read_addr_bytes::<T::Addr>(high, endian)),
+            Endian::Big => (read_addr_bytes::<T::Addr>(high, endian), read_addr_bytes::<T::Addr>(low, endian)),
+        };
+        T::decompress_mem(pesbt, cursor, tag)
+    }
+
+    /// Returns the in-memory representation, typed so it can only be decompressed with [CompressedCapability::decompress_mem].
+    ///
+    /// Typed equivalent of [Self::mem_representation].
+    pub fn to_mem(&self) -> wrappers::MemRepr<T> {
+        let (tag, words) = self.mem_representation();
+        wrappers::MemRepr::new(tag, words)
+    }
+
+    /// Returns the in-register representation, typed so it can only be decompressed with [CompressedCapability::decompress_raw].
+    ///
+    /// Typed equivalent of [Self::reg_representation].
+    pub fn to_reg(&self) -> wrappers::RegRepr<T> {
+        let (tag, words) = self.reg_representation();
+        wrappers::RegRepr::new(tag, words)
+    }
+
     pub fn tag(&self) -> bool {
         // cr_tag is interpreted as a boolean with C rules
         self.cr_tag != 0
@@ -276,6 +709,16 @@ impl<T: CompressedCapability> CcxCap<T> {
     pub fn set_tag(&mut self, tag: bool) {
         self.cr_tag = if tag { 1 } else { 0 };
     }
+    /// Returns a copy of this capability with the tag set to `tag`.
+    /// All other bits are left untouched, so this can be used to build tagged and untagged variants
+    /// of the same capability bits e.g. for test vectors.
+    ///
+    /// Non-mutating counterpart of [Self::set_tag].
+    pub fn with_tag(&self, tag: bool) -> CcxCap<T> {
+        let mut cap = *self;
+        cap.set_tag(tag);
+        cap
+    }
 
     pub fn base(&self) -> T::Addr {
         self.cr_base
@@ -286,6 +729,26 @@ impl<T: CompressedCapability> CcxCap<T> {
     pub fn bounds(&self) -> (T::Addr, T::Length) {
         (self.base(), self.top())
     }
+    /// Returns true if the `size` bytes starting at `addr` are all within the bounds, i.e.
+    /// `addr >= base()` and `addr + size <= top()`.
+    ///
+    /// `addr + size` is computed in [CompressedCapability::Length], and an access whose end overflows even that
+    /// is out of bounds. This doesn't check the tag, seal or permissions.
+    pub fn is_in_bounds(&self, addr: T::Addr, size: T::Length) -> bool {
+        let addr_len: T::Length = addr.into();
+        match addr_len.checked_add(&size) {
+            Some(end) => addr >= self.base() && end <= self.top(),
+            None => false,
+        }
+    }
+    /// Returns true if the cursor is within the bounds, i.e. `base() <= address() < top()`.
+    ///
+    /// This is [Self::is_in_bounds] for a one-byte access at the cursor, so a cursor pointing exactly at `top()`
+    /// is out of bounds even though a zero-sized access there would be allowed.
+    /// Like [Self::is_in_bounds], this doesn't check the tag, seal or permissions.
+    pub fn cursor_in_bounds(&self) -> bool {
+        self.is_in_bounds(self.address(), T::Length::one())
+    }
     /// Sets the base and top of this capability using C FFI function [CompressedCapability::set_bounds].
     /// Updates the PESBT field correspondingly.
     /// Zeroes tag if out of bounds.
@@ -293,10 +756,77 @@ impl<T: CompressedCapability> CcxCap<T> {
     pub fn set_bounds_unchecked(&mut self, req_len: T::Length) -> bool {
         T::set_bounds(self, req_len)
     }
+    /// Moves the cursor to `req_base` and sets the bounds to `[req_base, req_top)`,
+    /// returning an error if they couldn't be represented exactly.
+    ///
+    /// If the bounds were rounded, the representable bounds are still stored and also returned in
+    /// [SetBoundsError::Rounded]. As with [Self::set_bounds_unchecked], the tag is cleared if the request
+    /// isn't within the current bounds or the capability is sealed.
+    ///
+    /// The capability is left untouched if it's untagged or the request is invalid.
+    pub fn set_bounds_exact(&mut self, req_base: T::Addr, req_top: T::Length) -> Result<(), SetBoundsError<T::Addr, T::Length>> {
+        if !self.tag() {
+            return Err(SetBoundsError::RequiresTag);
+        }
+        let req_base_len: T::Length = req_base.into();
+        if req_top < req_base_len || req_top > max_top::<T>() {
+            return Err(SetBoundsError::InvalidRequest);
+        }
+
+        self.set_address_unchecked(req_base);
+        if T::set_bounds(self, req_top - req_base_len) {
+            Ok(())
+        } else {
+            Err(SetBoundsError::Rounded {
+                actual_base: self.base(),
+                actual_top: self.top(),
+            })
+        }
+    }
+
+    /// Derives a capability with bounds `[new_base, new_top)` and cursor `new_cursor` from this one,
+    /// e.g. for handing out an allocation.
+    ///
+    /// Fails if this capability is untagged or sealed, the new bounds aren't within this capability's bounds,
+    /// the new bounds can't be represented exactly, or the cursor isn't representable with the new bounds.
+    /// The cursor may be outside the new bounds as long as it's representable.
+    pub fn derive_subcap(&self, new_base: T::Addr, new_top: T::Length, new_cursor: T::Addr) -> Result<CcxCap<T>, DeriveError> {
+        if !self.tag() || self.is_sealed() {
+            return Err(DeriveError::InvalidParent);
+        }
+        let new_base_len: T::Length = new_base.into();
+        if new_base < self.base() || new_top > self.top() || new_top < new_base_len {
+            return Err(DeriveError::NotMonotone);
+        }
+
+        let mut derived = *self;
+        if derived.set_bounds_exact(new_base, new_top).is_err() {
+            return Err(DeriveError::BoundsRounded);
+        }
+        if !derived.is_representable_with_new_addr(new_cursor) {
+            return Err(DeriveError::NotRepresentable);
+        }
+        derived.set_address_unchecked(new_cursor);
+        Ok(derived)
+    }
 
     pub fn extract_bounds_bits(&self) -> CcxBoundsBits {
         T::extract_bounds_bits(self.cr_pesbt)
     }
+    /// Returns the exponent of the bounds encoding, as cached in the structure.
+    ///
+    /// This is only filled in when the capability is decoded (or its bounds are set) by the C library,
+    /// so it's zero for e.g. [Self::default].
+    pub fn exponent(&self) -> u8 {
+        self.cr_exp
+    }
+    /// Returns false if the bounds decode step found the encoding invalid, as cached in the structure.
+    ///
+    /// Only Morello has invalid bounds encodings (exponents above the maximum),
+    /// so this is always true for decoded capabilities of other formats.
+    pub fn bounds_valid(&self) -> bool {
+        self.cr_bounds_valid != 0
+    }
 
     pub fn address(&self) -> T::Addr {
         self._cr_cursor
@@ -305,19 +835,139 @@ impl<T: CompressedCapability> CcxCap<T> {
         self._cr_cursor = addr;
     }
 
+    /// Returns `address() - base()`, which is negative if the cursor is below the base.
+    ///
+    /// Uses plain subtraction, kept for backwards compatibility.
+    /// [CompressedCapability::Offset] is a signed type wider than the address so this can't overflow
+    /// for the formats in this crate, but see [Self::checked_offset] for generic code.
     pub fn offset(&self) -> T::Offset {
         let cursor: T::Offset = self._cr_cursor.into();
         let base: T::Offset = self.cr_base.into();
-        (cursor - base).into()
+        cursor - base
+    }
+    /// Returns [Self::offset], or None if the subtraction overflows [CompressedCapability::Offset].
+    pub fn checked_offset(&self) -> Option<T::Offset> {
+        let cursor: T::Offset = self._cr_cursor.into();
+        let base: T::Offset = self.cr_base.into();
+        cursor.checked_sub(&base)
+    }
+    /// Returns the address an access through this capability would target.
+    ///
+    /// If `relative` is true, this is computed as `base() + offset()` (i.e. treating the pointer as base-relative),
+    /// otherwise it's just [Self::address].
+    /// The relative computation is performed in the address width, wrapping around on overflow,
+    /// so for a capability with a consistent cursor/base both modes return the same value.
+    pub fn effective_address(&self, relative: bool) -> T::Addr {
+        if relative {
+            let offset: T::Addr = self.offset().as_();
+            self.base().wrapping_add(&offset)
+        } else {
+            self.address()
+        }
+    }
+    /// Returns [Self::top] as a `u64`, saturating to `u64::MAX` if it doesn't fit
+    /// (i.e. for the `2^64` top of a 128-bit capability extending to the end of memory).
+    ///
+    /// For 128-bit formats this is equivalent to the C++ `top64()`. The 33-bit top of 64-bit formats always fits,
+    /// so unlike the C++ (which saturates to the address width) this is exact.
+    pub fn top64(&self) -> u64 {
+        num_traits::cast(self.top()).unwrap_or(u64::MAX)
     }
-    // TODO top64
 
+    /// Returns `top() - base()`.
+    ///
+    /// Uses plain subtraction, kept for backwards compatibility: reversed bounds (base > top)
+    /// panic in debug builds and wrap in release builds. See [Self::checked_length] and [Self::length_saturating].
     pub fn length(&self) -> T::Length {
         let top: T::Length = self._cr_top.into();
         let base: T::Length = self.cr_base.into();
-        (top - base).into()
+        top - base
+    }
+    /// Returns [Self::length], or zero if the bounds are reversed (base > top).
+    ///
+    /// Reversed bounds can be decoded from arbitrary bit patterns, e.g. untagged data read from untrusted memory,
+    /// where [Self::length] would underflow (and panic in debug builds).
+    /// Prefer this over [Self::length] unless the capability is known to be well-formed.
+    pub fn length_saturating(&self) -> T::Length {
+        let top: T::Length = self._cr_top.into();
+        let base: T::Length = self.cr_base.into();
+        top.checked_sub(&base).unwrap_or_else(T::Length::zero)
+    }
+    /// Returns [Self::length], or None if the bounds are reversed (base > top).
+    pub fn checked_length(&self) -> Option<T::Length> {
+        let top: T::Length = self._cr_top.into();
+        let base: T::Length = self.cr_base.into();
+        top.checked_sub(&base)
+    }
+    /// Returns [Self::length] as a `u64`, saturating to `u64::MAX` if it doesn't fit
+    /// (i.e. for the `2^64` length of a full-address-space 128-bit capability).
+    ///
+    /// For 128-bit formats this is equivalent to the C++ `length64()`. The 33-bit length of 64-bit formats always fits,
+    /// so unlike the C++ (which saturates to the address width) this is exact - use [Self::length_saturating_addr] for that.
+    pub fn length64(&self) -> u64 {
+        num_traits::cast(self.length()).unwrap_or(u64::MAX)
+    }
+
+    /// Returns the fraction of the whole address space (`2^64` for 128-bit formats, `2^32` for 64-bit formats)
+    /// covered by this capability's bounds, e.g. 1.0 for a capability covering all of memory.
+    ///
+    /// Intended for presentation, as the result is subject to floating-point rounding.
+    pub fn address_space_fraction(&self) -> f64 {
+        let length: f64 = num_traits::cast(self.length()).unwrap();
+        let total: f64 = num_traits::cast(max_top::<T>()).unwrap();
+        length / total
+    }
+
+    /*
+    Narrowing conversions for offset/length.
+
+    Both are wider than T::Addr, so these give explicit control over what happens
+    when the value doesn't fit, instead of ad-hoc `as` casts.
+    */
+
+    /// Returns [Self::offset] as an address-width value, or None if it's negative or too large to fit.
+    pub fn offset_checked_addr(&self) -> Option<T::Addr> {
+        let offset = self.offset();
+        if offset < T::Offset::zero() || offset > T::Addr::max_value().into() {
+            None
+        } else {
+            Some(offset.as_())
+        }
+    }
+    /// Returns [Self::offset] as an address-width value, clamped to the range `0..=T::Addr::MAX`.
+    pub fn offset_saturating_addr(&self) -> T::Addr {
+        let offset = self.offset();
+        if offset < T::Offset::zero() {
+            T::Addr::zero()
+        } else if offset > T::Addr::max_value().into() {
+            T::Addr::max_value()
+        } else {
+            offset.as_()
+        }
+    }
+    /// Returns [Self::offset] truncated to the address width, i.e. modulo `2^(address width)`.
+    pub fn offset_wrapping_addr(&self) -> T::Addr {
+        self.offset().as_()
+    }
+
+    /// Returns [Self::length] as an address-width value, or None if it's too large to fit
+    /// (e.g. the length `2^64` of a full-address-space 128-bit capability).
+    pub fn length_checked_addr(&self) -> Option<T::Addr> {
+        let length = self.length();
+        if length > T::Addr::max_value().into() {
+            None
+        } else {
+            Some(length.as_())
+        }
+    }
+    /// Returns [Self::length] as an address-width value, clamped to `T::Addr::MAX`.
+    pub fn length_saturating_addr(&self) -> T::Addr {
+        self.length_checked_addr().unwrap_or_else(T::Addr::max_value)
+    }
+    /// Returns [Self::length] truncated to the address width, i.e. modulo `2^(address width)`.
+    pub fn length_wrapping_addr(&self) -> T::Addr {
+        self.length().as_()
     }
-    // TODO length64
 
     pub fn software_permissions(&self) -> u32 {
         T::get_uperms(self)
@@ -332,6 +982,47 @@ impl<T: CompressedCapability> CcxCap<T> {
     pub fn set_permissions(&mut self, perms: u32) {
         T::update_perms(self, perms)
     }
+    /// Returns true if every bit of `perm` (e.g. [CompressedCapability::PERM_LOAD]) is set in [Self::permissions].
+    ///
+    /// Permissions the format doesn't model (`PERM_*` equal to zero) are treated as granted.
+    pub fn has_permission(&self, perm: u32) -> bool {
+        self.has_perms(perm)
+    }
+    /// Removes every permission not in `mask`, i.e. `permissions &= mask`.
+    ///
+    /// This is the monotonic narrowing operation (`CAndPerm`), which is always safe on a tagged capability.
+    /// Granting new permissions with [Self::set_permissions] isn't something the architecture allows,
+    /// so the result of doing that to a tagged capability doesn't correspond to anything hardware could produce.
+    pub fn retain_permissions(&mut self, mask: u32) {
+        self.set_permissions(self.permissions() & mask)
+    }
+    /// Removes every permission in `mask`, i.e. `permissions &= !mask`. See [Self::retain_permissions].
+    pub fn clear_permissions(&mut self, mask: u32) {
+        self.retain_permissions(!mask)
+    }
+    /// Returns the architectural permissions as a typed [permissions::Permissions], see [Self::permissions]
+    pub fn perms(&self) -> permissions::Permissions<T> {
+        self.permissions().into()
+    }
+    /// Sets the architectural permissions from a typed [permissions::Permissions], see [Self::set_permissions].
+    ///
+    /// Combinations can be built from named flags, e.g. `cap.set_perms(Permissions::EMPTY.load().store())`.
+    pub fn set_perms(&mut self, perms: permissions::Permissions<T>) {
+        self.set_permissions(perms.into())
+    }
+    /// Returns the named architectural permissions granted by this capability, using the format's `PERM_*` constants.
+    ///
+    /// Permission bits without a name (e.g. unmodelled bits) are skipped.
+    pub fn iter_permissions(&self) -> impl Iterator<Item = permissions::PermissionName> {
+        permissions::iter_names::<T>(self.permissions())
+    }
+    /// Returns the index of each software permission bit set in [Self::software_permissions], from lowest to highest.
+    ///
+    /// Software permissions have no architectural meaning, so they're identified by index instead of by name.
+    pub fn iter_software_permissions(&self) -> impl Iterator<Item = u32> {
+        let uperms = self.software_permissions();
+        (0..u32::BITS).filter(move |index| (uperms >> index) & 1 != 0)
+    }
 
     pub fn otype(&self) -> u32 {
         T::get_otype(self)
@@ -339,9 +1030,121 @@ impl<T: CompressedCapability> CcxCap<T> {
     pub fn is_sealed(&self) -> bool {
         self.otype() != T::OTYPE_UNSEALED
     }
+    /// Returns true if this capability is sealed as a sentry (sealed entry), i.e. has otype [CompressedCapability::OTYPE_SENTRY].
+    pub fn is_sentry(&self) -> bool {
+        self.otype() == T::OTYPE_SENTRY
+    }
     pub fn set_otype(&mut self, otype: u32) {
         T::update_otype(self, otype)
     }
+    /// Returns [Self::otype] classified using the format's otype constants, see [OType]
+    pub fn otype_kind(&self) -> OType {
+        OType::from_raw::<T>(self.otype()).expect("otype field holds values above MAX_REPRESENTABLE_OTYPE")
+    }
+
+    /// Returns [Self::otype] sign-extended from the width of the otype field, matching the C convention
+    /// for formats which reserve the otypes at the top of the range
+    /// (e.g. `OTYPE_UNSEALED_SIGNED == -1`, with the sentry otype reading as `-2`).
+    ///
+    /// For formats whose reserved otypes are at the bottom of the range (Morello, RISC-V Y)
+    /// there is no signed convention, and the otype is returned unchanged.
+    pub fn otype_signed(&self) -> i32 {
+        let otype = self.otype();
+        if T::OTYPE_UNSEALED != T::MAX_REPRESENTABLE_OTYPE {
+            return otype as i32;
+        }
+        let unused = T::MAX_REPRESENTABLE_OTYPE.leading_zeros();
+        ((otype << unused) as i32) >> unused
+    }
+
+    /// Seals this capability with `otype`, then re-derives the bounds from the sealed encoding
+    /// to check that sealing didn't change them.
+    ///
+    /// Returns an error (leaving `self` untouched) if `otype` can't be used for sealing,
+    /// or if the bounds of the sealed capability differ from the original.
+    /// In all formats currently implemented (CHERIv9 CC64/CC128, Morello, RISC-V Y) the otype field is separate from the
+    /// bounds encoding, so the latter can't happen for well-formed capabilities.
+    /// The check exists for formats which share otype bits with the bounds precision.
+    pub fn seal_checked(&self, otype: u32) -> Result<CcxCap<T>, SealError> {
+        if otype > T::MAX_REPRESENTABLE_OTYPE || otype == T::OTYPE_UNSEALED {
+            return Err(SealError::InvalidOType);
+        }
+
+        let mut sealed = *self;
+        sealed.set_otype(otype);
+        let rederived = T::decompress_raw(sealed.cr_pesbt, sealed.address(), false);
+        if rederived.bounds() != self.bounds() {
+            return Err(SealError::BoundsChanged);
+        }
+        Ok(sealed)
+    }
+
+    /// Models the `CSeal` instruction: seals this capability with the otype given by the cursor of the sealing
+    /// authority `auth`.
+    ///
+    /// Checks, in order (matching the Sail specification):
+    /// this capability is tagged, `auth` is tagged, this capability is unsealed, `auth` is unsealed,
+    /// `auth` has [CompressedCapability::PERM_SEAL], `auth`'s cursor is within its bounds,
+    /// and `auth`'s cursor is a valid, unreserved otype.
+    /// Finally the sealed capability must have the same bounds, see [Self::seal_checked].
+    pub fn cseal(&self, auth: &CcxCap<T>) -> Result<CcxCap<T>, CSealError> {
+        if !self.tag() {
+            return Err(CSealError::TagViolation);
+        }
+        if !auth.tag() {
+            return Err(CSealError::AuthorityTagViolation);
+        }
+        if self.is_sealed() {
+            return Err(CSealError::SealViolation);
+        }
+        if auth.is_sealed() {
+            return Err(CSealError::AuthoritySealViolation);
+        }
+        if !auth.has_perms(T::PERM_SEAL) {
+            return Err(CSealError::PermitSealViolation);
+        }
+
+        let cursor = auth.address();
+        let cursor_len: T::Length = cursor.into();
+        if cursor < auth.base() || cursor_len >= auth.top() {
+            return Err(CSealError::LengthViolation);
+        }
+        let otype: u32 = match num_traits::cast(cursor) {
+            Some(otype) => otype,
+            None => return Err(CSealError::InvalidOType),
+        };
+        if otype > T::MAX_UNRESERVED_OTYPE
+            || otype == T::OTYPE_UNSEALED
+            || otype == T::OTYPE_SENTRY
+            || otype == T::OTYPE_RESERVED2
+            || otype == T::OTYPE_RESERVED3
+        {
+            return Err(CSealError::InvalidOType);
+        }
+
+        self.seal_checked(otype).map_err(|_| CSealError::Unrepresentable)
+    }
+
+    /// Returns true if this capability can be jumped to as a sentry (e.g. by `CJALR`/`CJR`),
+    /// i.e. it is tagged, sealed as a sentry, and has [CompressedCapability::PERM_EXECUTE].
+    ///
+    /// See [Self::unseal_sentry_for_jump].
+    pub fn is_invokable_sentry(&self) -> bool {
+        self.tag() && self.is_sentry() && self.has_perms(T::PERM_EXECUTE)
+    }
+
+    /// Models jumping to a sentry: if [Self::is_invokable_sentry], returns the unsealed capability,
+    /// ready to become the new PCC. Otherwise returns None.
+    ///
+    /// All other fields (including the cursor, which becomes the new PC) are unchanged.
+    pub fn unseal_sentry_for_jump(&self) -> Option<CcxCap<T>> {
+        if !self.is_invokable_sentry() {
+            return None;
+        }
+        let mut unsealed = *self;
+        unsealed.set_otype(T::OTYPE_UNSEALED);
+        Some(unsealed)
+    }
 
     pub fn reserved_bits(&self) -> u8 {
         T::get_reserved(self)
@@ -349,6 +1152,32 @@ impl<T: CompressedCapability> CcxCap<T> {
     pub fn set_reserved_bits(&mut self, bits: u8) {
         T::update_reserved(self, bits)
     }
+    /// Returns all of the reserved fields, extracted from [Self::cr_pesbt] in Rust.
+    ///
+    /// Counterpart: [Self::set_reserved_typed]
+    pub fn reserved_typed(&self) -> reserved::ReservedBits<T> {
+        let mut bits = 0u32;
+        let mut shift = 0;
+        for &(start, width) in T::RESERVED_FIELDS {
+            let field = (self.cr_pesbt >> start as usize) & low_bits_mask::<T::Addr>(width);
+            bits |= num_traits::cast::<T::Addr, u32>(field).unwrap() << shift;
+            shift += width;
+        }
+        reserved::ReservedBits::new(bits).unwrap()
+    }
+    /// Deposits all of the reserved fields into [Self::cr_pesbt] in Rust.
+    ///
+    /// Note that tagged capabilities with nonzero reserved bits are considered malformed,
+    /// and the C compress/decompress functions assert that they are not used.
+    ///
+    /// Counterpart: [Self::reserved_typed]
+    pub fn set_reserved_typed(&mut self, reserved: reserved::ReservedBits<T>) {
+        for (index, &(start, width)) in T::RESERVED_FIELDS.iter().enumerate() {
+            let mask = low_bits_mask::<T::Addr>(width) << start as usize;
+            let field: T::Addr = num_traits::cast(reserved.field(index)).unwrap();
+            self.cr_pesbt = (self.cr_pesbt & !mask) | (field << start as usize);
+        }
+    }
 
     pub fn flags(&self) -> u8 {
         T::get_flags(self)
@@ -356,122 +1185,3223 @@ impl<T: CompressedCapability> CcxCap<T> {
     pub fn set_flags(&mut self, flags: u8) {
         T::update_flags(self, flags)
     }
+    /// Returns the flags as a typed [Flags], see [Self::flags]
+    pub fn flags_typed(&self) -> Flags<T> {
+        Flags::from_bits(self.flags())
+    }
+    /// Returns true if the flags select capability mode, see [Flags::cap_mode]
+    pub fn cap_mode(&self) -> bool {
+        self.flags_typed().cap_mode()
+    }
+    /// Sets the flags to select capability mode or integer mode, leaving any other flag bits untouched.
+    ///
+    /// Does nothing for formats without a mode bit ([CompressedCapability::FLAGS_CAP_MODE] is None).
+    pub fn set_cap_mode(&mut self, cap_mode: bool) {
+        if T::FLAGS_CAP_MODE.is_some() {
+            let mode_bit = Flags::<T>::from_cap_mode(cap_mode).bits();
+            self.set_flags((self.flags() & !1) | mode_bit)
+        }
+    }
 
-    /// Helper function for easily calling FFI function [CompressedCapability::is_representable_cap_exact]
-    /// on this capability.
-    /// Assertions are present in the C code, but should never be triggered.
+    /// Returns true if the bounds can be encoded exactly, see [Self::representability].
     pub fn is_exact(&self) -> bool {
-        T::is_representable_cap_exact(self)
+        self.representability().is_exact()
+    }
+    /// Returns whether the decoded bounds depend on the cursor (address) bits,
+    /// or if they can be derived purely from the exponent and B/T fields.
+    /// On Morello this calls the C function `cap_bounds_uses_value`, based on the exponent stored in [CcxCap] on decode.
+    ///
+    /// If this is true, the bounds must be recomputed whenever the address changes sign (bit 55),
+    /// because Morello sign-extends the address used for bounds calculations.
+    /// This is a Morello-only concept: for the CHERIv9/RISC-V Y formats it is always false,
+    /// see [CompressedCapability::bounds_uses_value].
+    ///
+    /// On Morello, asserts (in C) that the pesbt matches the decoded fields, i.e. the capability hasn't been modified
+    /// without going through the C functions.
+    pub fn bounds_uses_value(&self) -> bool {
+        T::bounds_uses_value(self)
     }
-    /// Helper function for easily calling FFI function [CompressedCapability::is_representable_new_addr]
-    /// on this capability.
+    /// Helper function for easily calling FFI function [CompressedCapability::is_representable_with_addr]
+    /// on this capability, using the precise check.
+    /// Like the C function, this doesn't take sealing into account.
     /// Assertions are present in the C code, but should never be triggered.
     pub fn is_representable_with_new_addr(&self, new_addr: T::Addr) -> bool {
-        T::is_representable_new_addr(
-            self.is_sealed(),
-            self.base(),
-            self.length(),
-            self.address(),
-            new_addr,
-        )
+        T::is_representable_with_addr(self, new_addr, true)
     }
-}
-/// Implements `operator==` from cheri_compressed_cap_common.h
-impl<T: CompressedCapability> PartialEq for CcxCap<T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.cr_tag == other.cr_tag
-            && self._cr_cursor == other._cr_cursor
-            && self.cr_pesbt == other.cr_pesbt
+
+    /// Returns the `(base, top)` of the representable region, i.e. the range of cursor values this capability
+    /// can be moved to without changing its decoded bounds.
+    ///
+    /// The region is `2^(exponent + MANTISSA_WIDTH)` bytes long and always contains the bounds.
+    /// It may extend past the top of the address space, in which case it wraps around to zero.
+    /// If the region is at least as large as the address space, the whole address space is returned.
+    /// Morello's sign-extension of the top address bits is not taken into account.
+    pub fn representable_limits(&self) -> (T::Addr, T::Length) {
+        let max_top = max_top::<T>();
+        let addr_width = (std::mem::size_of::<T::Addr>() * 8) as u32;
+        let region_width = self.extract_bounds_bits().e as u32 + T::MANTISSA_WIDTH;
+        if region_width >= addr_width {
+            return (T::Addr::zero(), max_top);
+        }
+
+        let two: T::Length = (T::Addr::one() + T::Addr::one()).into();
+        let region_size = num_traits::pow(two, region_width as usize);
+        let base: T::Length = self.base().into();
+        let below_base = if T::USES_V9_CORRECTION_FACTORS {
+            let step = region_size / two / two / two;
+            base % step + step
+        } else {
+            region_size / two / two
+        };
+        let region_base = (base + max_top - below_base) % max_top;
+        (region_base.as_(), region_base + region_size)
     }
-}
-impl<T: CompressedCapability> Eq for CcxCap<T> {}
-/// Equivalent to initialization pattern used in tests:
-/// ```c
-/// ccx_cap_t value;
-/// memset(&value, 0, sizeof(value));
-/// ```
-///
-/// cc64.rs doesn't pick it up when it was automatically #[derive]-d, so it's manually implemented here
-impl<T: CompressedCapability> Default for CcxCap<T> {
-    fn default() -> Self {
-        CcxCap {
-            // Use Default::default for the associated types (Addr, Length, Offset)
-            // Rust doesn't have enough information to know they are numbers
-            _cr_cursor: Default::default(),
-            cr_pesbt: Default::default(),
-            _cr_top: Default::default(),
-            cr_base: Default::default(),
-            cr_tag: 0,
-            cr_bounds_valid: 0,
-            cr_exp: 0,
-            cr_extra: 0,
+
+    /// Returns whether this capability's decoded bounds can be encoded exactly, and if not, why.
+    ///
+    /// Bounds decoded from a well-formed pesbt are always [Representability::Exact],
+    /// but arbitrary bits can decode to [Representability::Malformed] bounds.
+    /// This is for debugging bounds which were modified directly (e.g. with [Self::set_raw_cache_unchecked]),
+    /// and doesn't call into C, so can't trip the C library's consistency assertions.
+    /// To check bounds before setting them, use [Self::representability_of].
+    pub fn representability(&self) -> Representability<T> {
+        match self.checked_length() {
+            Some(len) if self.top() <= max_top::<T>() => self.representability_of(self.base(), len),
+            _ => Representability::Malformed,
+        }
+    }
+    /// Returns whether bounds `[base, base + len)` can be encoded exactly in this capability's format, and if not, why.
+    ///
+    /// Panics if `base + len` is outside the address space.
+    pub fn representability_of(&self, base: T::Addr, len: T::Length) -> Representability<T> {
+        let (representable_base, representable_len) = self.round_up_to_representable(base, len);
+        if (representable_base, representable_len) == (base, len) {
+            Representability::Exact
+        } else {
+            Representability::Inexact {
+                required_alignment: T::get_required_alignment(len),
+                representable_base,
+                representable_top: T::compute_top(representable_base, representable_len),
+            }
+        }
+    }
+
+    /// Calls [CompressedCapability::get_representable_length] for this capability's format.
+    pub fn representable_length(&self, len: T::Length) -> T::Length {
+        T::get_representable_length(len)
+    }
+    /// Calls [CompressedCapability::get_required_alignment] for this capability's format.
+    pub fn required_alignment(&self, len: T::Length) -> T::Length {
+        T::get_required_alignment(len)
+    }
+    /// Calls [CompressedCapability::get_alignment_mask] for this capability's format.
+    pub fn alignment_mask(&self, len: T::Length) -> T::Length {
+        T::get_alignment_mask(len)
+    }
+
+    /// Returns the smallest `(base, length)` containing `[base, base + len)` which is aligned to
+    /// [Self::alignment_mask] for its own length, i.e. which this capability's format can represent exactly.
+    /// Equivalent to [align_region] for this capability's format.
+    ///
+    /// Panics if `base + len` is outside the address space.
+    pub fn round_up_to_representable(&self, base: T::Addr, len: T::Length) -> (T::Addr, T::Length) {
+        align_region::<T>(base, len)
+    }
+
+    /// Returns the lowest address the cursor can be moved to without clearing the tag,
+    /// i.e. the start of [Self::representable_limits].
+    ///
+    /// This is usually below [Self::base], as the representable region extends past the bounds.
+    /// If the region wraps around the top of the address space, this is greater than [Self::max_valid_cursor].
+    pub fn min_valid_cursor(&self) -> T::Addr {
+        self.representable_limits().0
+    }
+
+    /// Returns the highest address (inclusive) the cursor can be moved to without clearing the tag,
+    /// i.e. the last address in [Self::representable_limits].
+    ///
+    /// This is usually above [Self::top], as the representable region extends past the bounds.
+    /// See [Self::min_valid_cursor] for the wraparound case.
+    pub fn max_valid_cursor(&self) -> T::Addr {
+        let repr_top: T::Addr = self.representable_limits().1.as_();
+        repr_top.wrapping_sub(&T::Addr::one())
+    }
+
+    /// Returns a copy of this capability with bounds widened to cover `[base - guard_bytes, top + guard_bytes)`,
+    /// rounded out to the nearest representable bounds, e.g. to reserve guard pages around an allocation.
+    ///
+    /// Returns None if the guarded region (before or after rounding) doesn't fit in the address space.
+    /// The cursor, permissions and otype are kept.
+    /// If the bounds grow the result is untagged, as it grants more than the original capability,
+    /// otherwise (e.g. if `guard_bytes` is zero) a copy of `self` is returned.
+    pub fn with_guard(&self, guard_bytes: T::Length) -> Option<CcxCap<T>> {
+        let base: T::Length = self.base().into();
+        let req_base = base.checked_sub(&guard_bytes)?;
+        let req_top = self.top().checked_add(&guard_bytes)?;
+        if req_top > max_top::<T>() {
+            return None;
+        }
+
+        // Decode untagged, as moving the cursor to the requested base may take it outside the representable region
+        let mut guarded = T::decompress_raw(self.cr_pesbt, req_base.as_(), false);
+        T::set_bounds(&mut guarded, req_top - req_base);
+        if guarded.top() > max_top::<T>() {
+            return None;
+        }
+        if guarded.bounds() == self.bounds() {
+            return Some(*self);
+        }
+        Some(T::decompress_raw(guarded.cr_pesbt, self.address(), false))
+    }
+
+    /// Returns the bits of [Self::base] below the exponent, i.e. `base & ((1 << e) - 1)`.
+    ///
+    /// The compressed encoding only stores bits of the base at and above the exponent,
+    /// so exactly representable capabilities always have these bits zero.
+    /// See also [Self::cursor_low_bits].
+    pub fn base_low_bits(&self) -> T::Addr {
+        self.base() & self.below_exponent_mask()
+    }
+
+    /// Returns the bits of [Self::address] below the exponent, i.e. `cursor & ((1 << e) - 1)`.
+    ///
+    /// These bits take no part in decoding the bounds, so changing only these bits never affects representability:
+    /// whether a cursor is representable depends only on the bits above the exponent.
+    /// See also [Self::base_low_bits].
+    pub fn cursor_low_bits(&self) -> T::Addr {
+        self.address() & self.below_exponent_mask()
+    }
+
+    /// Returns true if `self` and `other` have the same tag and pesbt, and cursors which only differ below the exponent
+    /// (see [Self::cursor_low_bits]).
+    ///
+    /// Such capabilities decode to the same bounds and can't be told apart by representability checks,
+    /// which is useful for grouping pointers.
+    /// This is *not* architectural equality: the cursors may still differ, so e.g. bounds checks on accesses
+    /// through them can give different results.
+    pub fn equal_ignoring_subgranule_cursor(&self, other: &CcxCap<T>) -> bool {
+        self.tag() == other.tag()
+            && self.cr_pesbt == other.cr_pesbt
+            && (self.address() ^ self.cursor_low_bits()) == (other.address() ^ other.cursor_low_bits())
+    }
+
+    /// Returns a mask of the address bits below the exponent, saturating at the address width for malformed exponents.
+    fn below_exponent_mask(&self) -> T::Addr {
+        let addr_width = (std::mem::size_of::<T::Addr>() * 8) as u32;
+        low_bits_mask::<T::Addr>(std::cmp::min(self.extract_bounds_bits().e as u32, addr_width))
+    }
+
+    /// Applies each increment in `incs` to the cursor in turn with `CIncOffset` semantics,
+    /// returning the final capability.
+    ///
+    /// The address wraps around on overflow.
+    /// The tag is cleared if this capability is sealed, or on the first step which leaves the representable region
+    /// (see [Self::is_representable_with_new_addr]), and stays cleared even if later steps move back into the region.
+    /// In the latter case the bounds are re-decoded relative to the final address, as they would be by hardware.
+    /// Representability is only checked until the tag has been cleared, so this is cheaper than repeated single steps.
+    pub fn apply_offset_sequence(&self, incs: &[T::Addr]) -> CcxCap<T> {
+        let (last, steps) = match incs.split_last() {
+            Some(split) => split,
+            None => return *self,
+        };
+        let mut addr = self.address();
+        let mut representable = true;
+        for inc in steps {
+            addr = addr.wrapping_add(inc);
+            representable = representable && self.is_representable_with_new_addr(addr);
+        }
+
+        let (_, mut cap) = self.moved_to(addr.wrapping_add(last));
+        if !representable {
+            cap.set_tag(false);
+        }
+        cap
+    }
+
+    /// Returns a copy of this capability with the cursor set to `base + offset`, modelling
+    /// `CGetPCCSetOffset`/`AUIPCC`-style derivation from PCC.
+    ///
+    /// The address wraps around on overflow.
+    /// Unlike [CheriRVFuncs::setCapOffset](wrappers::CheriRVFuncs::setCapOffset) this doesn't report failure:
+    /// as in the instructions, the tag is cleared if the new cursor is outside the representable region
+    /// (and the bounds are re-decoded relative to it, as they would be by hardware) or if this capability is sealed.
+    /// This is [Self::with_address] with `base + offset`.
+    pub fn with_offset(&self, offset: T::Offset) -> CcxCap<T> {
+        self.with_address(self.base().wrapping_add(&offset.as_())).1
+    }
+
+    /// Returns a copy of this capability with the cursor set to `addr`, and whether `addr` is representable
+    /// (see [Self::is_representable_with_new_addr]).
+    ///
+    /// As in hardware, if `addr` isn't representable the tag is cleared and the bounds are re-decoded relative to it.
+    /// The tag is also cleared if this capability is sealed.
+    pub fn with_address(&self, addr: T::Addr) -> (bool, CcxCap<T>) {
+        self.moved_to(addr)
+    }
+    /// Returns a copy of this capability with `delta` added to the cursor, wrapping around on overflow,
+    /// and whether the new cursor is representable. See [Self::with_address].
+    pub fn wrapping_add_offset(&self, delta: T::Addr) -> (bool, CcxCap<T>) {
+        self.with_address(self.address().wrapping_add(&delta))
+    }
+
+    /// Implements the cursor moves ([Self::with_address], [Self::with_offset], [Self::apply_offset_sequence]) with the C representability check,
+    /// which unlike [Self::representable_limits] handles Morello's sign-extension of the top address bits.
+    fn moved_to(&self, addr: T::Addr) -> (bool, CcxCap<T>) {
+        let representable = self.is_representable_with_new_addr(addr);
+        let cap = if representable {
+            let mut cap = *self;
+            cap.set_address_unchecked(addr);
+            if self.is_sealed() {
+                cap.set_tag(false);
+            }
+            cap
+        } else {
+            T::decompress_raw(self.cr_pesbt, addr, false)
+        };
+        (representable, cap)
+    }
+
+    /// Moves the cursor to the nearest in-bounds address if it's outside `[base, top)`:
+    /// to the base if it's below, or to `top - 1` if it's at or above the top (the base for zero-length bounds).
+    /// Returns true if the cursor was moved.
+    ///
+    /// This is a convenience for recovering a usable pointer, *not* an architectural operation.
+    /// Addresses within the bounds are always representable, so the tag is kept,
+    /// unless the capability is sealed and the cursor was moved.
+    pub fn clamp_cursor_to_bounds(&mut self) -> bool {
+        let addr = self.address();
+        let addr_len: T::Length = addr.into();
+        let clamped = if addr < self.base() || self.length_saturating() == T::Length::zero() {
+            self.base()
+        } else if addr_len >= self.top() {
+            (self.top() - T::Length::one()).as_()
+        } else {
+            addr
+        };
+
+        if clamped == addr {
+            return false;
+        }
+        self.set_address_unchecked(clamped);
+        if self.is_sealed() {
+            self.set_tag(false);
+        }
+        true
+    }
+
+    /// Check that this capability only uses the permissions and otypes implemented by `profile`,
+    /// i.e. it could have been produced by that platform.
+    ///
+    /// The format's reserved otypes (e.g. [CompressedCapability::OTYPE_UNSEALED]) are always accepted.
+    pub fn is_valid_for_platform(&self, profile: &platform::PlatformProfile) -> bool {
+        let otype = self.otype();
+        let otype_valid = otype <= profile.max_otype
+            || otype == T::OTYPE_UNSEALED
+            || otype == T::OTYPE_SENTRY
+            || otype == T::OTYPE_RESERVED2
+            || otype == T::OTYPE_RESERVED3;
+        (self.permissions() & !profile.permissions) == 0
+            && (self.software_permissions() & !profile.software_permissions) == 0
+            && otype_valid
+    }
+
+    /// Returns true if every bit in `perms` is set in [Self::permissions].
+    ///
+    /// Permissions which a profile doesn't model (i.e. `PERM_*` constants equal to zero) are treated as granted.
+    fn has_perms(&self, perms: u32) -> bool {
+        (self.permissions() & perms) == perms
+    }
+
+    /// Computes the capability which would end up in memory if this capability were stored (e.g. with `CSC`)
+    /// using `authority` as the authorizing capability.
+    ///
+    /// Returns an error if `authority` does not permit storing any data at all,
+    /// i.e. it is untagged, sealed, or lacks [CompressedCapability::PERM_STORE].
+    /// Otherwise, returns a copy of this capability with the tag cleared if
+    /// - `authority` lacks [CompressedCapability::PERM_STORE_CAP], or
+    /// - this capability is local (lacks [CompressedCapability::PERM_GLOBAL])
+    ///   and `authority` lacks [CompressedCapability::PERM_STORE_LOCAL].
+    ///
+    /// Some ISA versions trap instead of clearing the tag in the latter two cases.
+    /// Bounds and alignment checks on the store address are the responsibility of the caller.
+    pub fn store_through(&self, authority: &CcxCap<T>) -> Result<CcxCap<T>, StoreError> {
+        if !authority.tag() {
+            return Err(StoreError::TagViolation);
+        }
+        if authority.is_sealed() {
+            return Err(StoreError::SealViolation);
+        }
+        if !authority.has_perms(T::PERM_STORE) {
+            return Err(StoreError::PermitStoreViolation);
+        }
+
+        let mut stored = *self;
+        if !authority.has_perms(T::PERM_STORE_CAP) {
+            stored.set_tag(false);
+        }
+        if !self.has_perms(T::PERM_GLOBAL) && !authority.has_perms(T::PERM_STORE_LOCAL) {
+            stored.set_tag(false);
+        }
+        Ok(stored)
+    }
+
+    /*
+    Region predicates, from weakest to strongest:
+    - bounds_overlap: the two capabilities' bounds intersect at all
+    - aliases_region: the two capabilities have exactly the same bounds
+    - same_sealed_object: same bounds, and both are sealed with the same otype
+
+    None of these look at the cursor, permissions or tag.
+    */
+
+    /// Returns true if the bounds of `self` and `other` share at least one byte.
+    ///
+    /// Capabilities with zero length never overlap anything.
+    /// See also [Self::aliases_region], [Self::same_sealed_object].
+    pub fn bounds_overlap(&self, other: &CcxCap<T>) -> bool {
+        let self_base: T::Length = self.base().into();
+        let other_base: T::Length = other.base().into();
+        self_base < other.top() && other_base < self.top()
+    }
+
+    /// Returns true if `self` and `other` describe exactly the same memory region (identical base and top),
+    /// regardless of cursor, permissions, otype or tag.
+    ///
+    /// Unlike [Self::bounds_overlap], partially overlapping regions don't count.
+    /// See also [Self::same_sealed_object].
+    pub fn aliases_region(&self, other: &CcxCap<T>) -> bool {
+        self.bounds() == other.bounds()
+    }
+
+    /// Returns true if `self` and `other` are references to the same sealed object,
+    /// i.e. both are sealed with the same otype and have the same bounds.
+    ///
+    /// Deliberately ignores permissions, as two views of the same object may grant different rights,
+    /// and also ignores the cursor and tag.
+    /// See also [Self::aliases_region] for the same check on unsealed capabilities.
+    pub fn same_sealed_object(&self, other: &CcxCap<T>) -> bool {
+        self.is_sealed()
+            && other.is_sealed()
+            && self.otype() == other.otype()
+            && self.aliases_region(other)
+    }
+
+    /// Models the `CBuildCap` instruction: re-derives a tagged capability from the untagged `template`
+    /// (e.g. capability data read back from memory) using `authority`.
+    ///
+    /// Succeeds if `authority` is tagged and unsealed, and `template` is a subset of it,
+    /// i.e. its bounds are within the authority's bounds and its (software) permissions are a subset of the authority's.
+    /// Returns the template with its tag set, all other fields are copied as-is.
+    pub fn build_cap(authority: &CcxCap<T>, template: &CcxCap<T>) -> Result<CcxCap<T>, BuildCapError> {
+        if !authority.tag() {
+            return Err(BuildCapError::TagViolation);
+        }
+        if authority.is_sealed() {
+            return Err(BuildCapError::SealViolation);
+        }
+
+        let template_base: T::Length = template.base().into();
+        if template.base() < authority.base()
+            || template.top() > authority.top()
+            || template_base > template.top()
+        {
+            return Err(BuildCapError::LengthViolation);
+        }
+        if (template.permissions() & !authority.permissions()) != 0
+            || (template.software_permissions() & !authority.software_permissions()) != 0
+        {
+            return Err(BuildCapError::PermissionViolation);
+        }
+
+        Ok(template.with_tag(true))
+    }
+
+    /// Describes how the rights granted by `newer` differ from `self`, in CHERI terms, for audit trails.
+    ///
+    /// Events are reported in a fixed order: permissions, software permissions, bounds, then seal transitions.
+    /// Bounds are `BoundsWidened` if `newer` covers any byte `self` didn't, and `BoundsNarrowed` if it lost any byte,
+    /// so a shifted region reports both.
+    /// Changing from one otype to another is reported as `Unsealed` followed by `Sealed`.
+    ///
+    /// If both capabilities are tagged and `newer` gained any permission or bounds,
+    /// [RightsChange::MonotonicityViolation] is reported first:
+    /// valid derivations can't gain rights without the tag being cleared.
+    pub fn rights_delta(&self, newer: &CcxCap<T>) -> Vec<RightsChange> {
+        let mut changes = vec![];
+
+        let perms_gained = newer.permissions() & !self.permissions();
+        let perms_lost = self.permissions() & !newer.permissions();
+        let uperms_gained = newer.software_permissions() & !self.software_permissions();
+        let uperms_lost = self.software_permissions() & !newer.software_permissions();
+        if perms_gained != 0 {
+            changes.push(RightsChange::PermissionsGained(perms_gained));
+        }
+        if perms_lost != 0 {
+            changes.push(RightsChange::PermissionsLost(perms_lost));
+        }
+        if uperms_gained != 0 {
+            changes.push(RightsChange::SoftwarePermissionsGained(uperms_gained));
+        }
+        if uperms_lost != 0 {
+            changes.push(RightsChange::SoftwarePermissionsLost(uperms_lost));
+        }
+
+        let self_base: T::Length = self.base().into();
+        let newer_base: T::Length = newer.base().into();
+        let covers = |base: T::Length, top: T::Length, cap: &CcxCap<T>| {
+            // An empty region is covered by anything
+            base >= top || (base >= cap.base().into() && top <= cap.top())
+        };
+        let widened = !covers(newer_base, newer.top(), self);
+        let narrowed = !covers(self_base, self.top(), newer);
+        if widened {
+            changes.push(RightsChange::BoundsWidened);
+        }
+        if narrowed {
+            changes.push(RightsChange::BoundsNarrowed);
+        }
+
+        if self.otype() != newer.otype() {
+            if self.is_sealed() {
+                changes.push(RightsChange::Unsealed { otype: self.otype() });
+            }
+            if newer.is_sealed() {
+                changes.push(RightsChange::Sealed { otype: newer.otype() });
+            }
+        }
+
+        if self.tag() && newer.tag() && (perms_gained != 0 || uperms_gained != 0 || widened) {
+            changes.insert(0, RightsChange::MonotonicityViolation);
+        }
+        changes
+    }
+
+    /// Returns true if this capability grants no more authority than `parent`, i.e. it could have been derived from it:
+    /// its bounds are within `parent`'s, its permissions and software permissions are subsets of `parent`'s,
+    /// and it's only tagged if `parent` is.
+    ///
+    /// Intended for auditing derivations (e.g. checking a capability handed out by an allocator against its heap),
+    /// or finding capabilities to a revoked region.
+    /// Seal state is ignored, as sealing and unsealing don't change the rights granted.
+    pub fn is_subset_of(&self, parent: &CcxCap<T>) -> bool {
+        self.base() >= parent.base()
+            && self.top() <= parent.top()
+            && (self.permissions() & !parent.permissions()) == 0
+            && (self.software_permissions() & !parent.software_permissions()) == 0
+            && (!self.tag() || parent.tag())
+    }
+
+    /// Formats the bounds together with the representable region and exponent,
+    /// e.g. `b:0x1000 t:0x2000 [repr: 0xf00..0x2100 e:3]`.
+    ///
+    /// Useful for working out why moving the cursor cleared the tag.
+    /// See [Self::representable_limits], and [Self::detailed] for a [std::fmt::Display] adapter.
+    pub fn fmt_detailed(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (repr_base, repr_top) = self.representable_limits();
+        write!(
+            f,
+            "b:{:#x} t:{:#x} [repr: {:#x}..{:#x} e:{}]",
+            self.base(),
+            self.top(),
+            repr_base,
+            repr_top,
+            self.extract_bounds_bits().e
+        )
+    }
+
+    /// Returns the decoded fields as a flat `#[repr(C)]` struct, e.g. for passing to C callbacks
+    /// which shouldn't need to understand the compressed encoding.
+    pub fn to_cap_info(&self) -> CapInfo {
+        let top: u128 = num_traits::cast(self.top()).expect("top must fit in 128 bits");
+        CapInfo {
+            tag: self.tag() as u8,
+            base: num_traits::cast(self.base()).expect("addresses must fit in 64 bits"),
+            top_lo: top as u64,
+            top_hi: (top >> 64) as u64,
+            cursor: num_traits::cast(self.address()).expect("addresses must fit in 64 bits"),
+            perms: self.permissions(),
+            uperms: self.software_permissions(),
+            otype: self.otype(),
+            flags: self.flags(),
+            reserved: self.reserved_bits(),
+        }
+    }
+
+    /// Returns a value implementing [std::fmt::Display] using [Self::fmt_detailed].
+    pub fn detailed(&self) -> DetailedCcxCap<'_, T> {
+        DetailedCcxCap(self)
+    }
+
+    /*
+    Debugging access to the decoded-field cache.
+
+    _cr_top, cr_base and cr_exp are derived from cr_pesbt and the cursor when the capability is decoded.
+    These allow inspecting and corrupting that cache, e.g. to simulate a hardware bug.
+    */
+
+    /// Returns the cached `(top, base, exponent)` decoded from [Self::cr_pesbt], as stored in the structure.
+    ///
+    /// Counterpart: [Self::set_raw_cache_unchecked]
+    pub fn raw_cache(&self) -> (T::FfiLength, T::Addr, u8) {
+        (self._cr_top, self.cr_base, self.cr_exp)
+    }
+
+    /// Overwrites the cached `(top, base, exponent)` *without* touching [Self::cr_pesbt].
+    ///
+    /// **Danger**: this is only for testing and debugging.
+    /// If the new values don't match the encoding the capability is inconsistent:
+    /// the accessors will report bounds the encoding doesn't describe,
+    /// and many C functions (e.g. [CompressedCapability::compress_raw]) will abort the process on a debug assertion.
+    /// Use [Self::self_consistent] to check whether the cache matches.
+    pub fn set_raw_cache_unchecked(&mut self, top: T::FfiLength, base: T::Addr, exp: u8) {
+        self._cr_top = top;
+        self.cr_base = base;
+        self.cr_exp = exp;
+    }
+
+    /// Returns true if the cached top, base and exponent match what [Self::cr_pesbt] and the cursor decode to,
+    /// i.e. the capability hasn't been modified behind the C library's back (e.g. with [Self::set_raw_cache_unchecked]).
+    ///
+    /// Unlike the C `pesbt_is_correct`, this never asserts.
+    pub fn self_consistent(&self) -> bool {
+        let decoded = T::decompress_raw(self.cr_pesbt, self._cr_cursor, false);
+        decoded.top() == self.top() && decoded.cr_base == self.cr_base && decoded.cr_exp == self.cr_exp
+    }
+
+    /// Returns true if every field of the structure is equal, including the decoded-field cache
+    /// ([Self::raw_cache]), `cr_bounds_valid` and `cr_extra`.
+    ///
+    /// `==` follows the C `operator==` and only compares the tag, cursor and pesbt, because for a
+    /// [self-consistent](Self::self_consistent) capability the other fields are derived from those.
+    /// They can still diverge if the cache is modified directly (e.g. with [Self::set_raw_cache_unchecked]),
+    /// or if two decoders disagree, so this is useful for comparing implementations byte-for-byte.
+    pub fn eq_exact(&self, other: &Self) -> bool {
+        self == other
+            && self.top() == other.top()
+            && self.cr_base == other.cr_base
+            && self.cr_exp == other.cr_exp
+            && self.cr_bounds_valid == other.cr_bounds_valid
+            && self.cr_extra == other.cr_extra
+    }
+
+    /// Returns true if the encoding is valid for a tagged capability: the bounds decode validly,
+    /// with `base <= top <= 2^(address width)`, and the reserved bits are zero.
+    ///
+    /// The C library asserts this holds when decoding or encoding a tagged capability.
+    /// Morello hardware can tag some capabilities which fail the bounds checks (e.g. with `SCTAG`), but they are still rejected here.
+    pub fn is_well_formed(&self) -> bool {
+        let base: T::Length = self.base().into();
+        self.cr_bounds_valid != 0
+            && base <= self.top()
+            && self.top() <= max_top::<T>()
+            && self.reserved_typed().bits() == 0
+    }
+}
+
+/// Formats the capability on one line in the conventional CHERI tooling style, e.g.
+/// `v:1 s:0 p:00000fff b:0000000040000000 l:0000000000001000 o:0003ffff`:
+/// tag, sealed, [CcxCap::permissions], [CcxCap::base], [CcxCap::length], and [CcxCap::otype].
+///
+/// Addresses and lengths are zero-padded to the address width (8 hex digits for 64-bit capabilities,
+/// 16 for 128-bit). The length can take one more digit, for bounds covering the whole address space.
+/// Untagged capabilities are printed the same way, using the bounds decoded from their bits.
+/// Those can be reversed (base above top), which is printed as a zero length, see [CcxCap::length_saturating].
+///
+/// The alternate flag (`{:#}`) prints [CcxCap::fmt_detailed] instead.
+impl<T: CompressedCapability> std::fmt::Display for CcxCap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if f.alternate() {
+            return self.fmt_detailed(f);
         }
+        let width = std::mem::size_of::<T::Addr>() * 2;
+        write!(
+            f,
+            "v:{} s:{} p:{:08x} b:{:0width$x} l:{:0width$x} o:{:08x}",
+            self.tag() as u8,
+            self.is_sealed() as u8,
+            self.permissions(),
+            self.base(),
+            self.length_saturating(),
+            self.otype(),
+            width = width
+        )
     }
 }
-/// Debug printer for capabilities that decodes the PESBT field instead of printing it raw.
-impl<T: CompressedCapability> Debug for CcxCap<T> {
+
+/// Formats the register words from [CcxCap::reg_representation] as one hex number, the pesbt followed by the cursor,
+/// each zero-padded to the address width (e.g. 32 digits in total for 128-bit capabilities). The tag isn't included.
+///
+/// This is the usual form of capabilities in hardware traces. The alternate flag (`{:#x}`) adds a `0x` prefix.
+impl<T: CompressedCapability> std::fmt::LowerHex for CcxCap<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("CcxCap")
-            .field("cr_cursor", &self._cr_cursor)
-            .field("cr_base", &self.cr_base)
-            .field("cr_top", &self._cr_top)
-            .field("cr_tag", &self.cr_tag)
-            .field("cr_bounds_valid", &self.cr_bounds_valid)
-            .field("cr_exp", &self.cr_exp)
-            .field("cr_extra", &self.cr_extra)
-            .field("permissions", &self.permissions())
-            .field("software_permissions", &self.software_permissions())
-            .field("otype", &self.otype())
-            .field("reserved_bits", &self.reserved_bits())
-            .field("flags", &self.flags())
-            .finish()
+        let (_, [cursor, pesbt]) = self.reg_representation();
+        let width = std::mem::size_of::<T::Addr>() * 2;
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        write!(f, "{:0width$x}{:0width$x}", pesbt, cursor, width = width)
+    }
+}
+/// Uppercase version of the [std::fmt::LowerHex] format, e.g. for `{:X}`
+impl<T: CompressedCapability> std::fmt::UpperHex for CcxCap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, [cursor, pesbt]) = self.reg_representation();
+        let width = std::mem::size_of::<T::Addr>() * 2;
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        write!(f, "{:0width$X}{:0width$X}", pesbt, cursor, width = width)
     }
 }
 
-/// Structure matching the C type `_cc_N(bounds_bits)`.
-/// Represents a floating-point encoded capability bounds.
+/// [std::fmt::Display] adapter for [CcxCap::fmt_detailed], see [CcxCap::detailed]
+pub struct DetailedCcxCap<'a, T: CompressedCapability>(&'a CcxCap<T>);
+impl<'a, T: CompressedCapability> std::fmt::Display for DetailedCcxCap<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_detailed(f)
+    }
+}
+
+/// Decoded capability fields with a stable, flat C layout, see [CcxCap::to_cap_info].
+///
+/// Addresses are zero-extended to 64 bits for all formats.
+/// The top may be up to `2^64`, so it is split into two words: `top = (top_hi << 64) | top_lo`.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
-pub struct CcxBoundsBits {
-    pub b: u16,
-    pub t: u16,
-    pub e: u8,
-    pub ie: bool,
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CapInfo {
+    /// 1 if tagged, 0 otherwise
+    pub tag: u8,
+    pub base: u64,
+    pub top_lo: u64,
+    pub top_hi: u64,
+    pub cursor: u64,
+    pub perms: u32,
+    pub uperms: u32,
+    pub otype: u32,
+    pub flags: u8,
+    pub reserved: u8,
 }
 
-// Link the C functions
-mod c_funcs;
+/// One field which differs between two capabilities, see [diff].
+///
+/// Values are zero-extended to 128 bits so they can hold the top of any format, and the tag is 0 or 1.
+/// Displayed as e.g. `otype: 0x3ffff != 0x1234`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    /// The name of the field, e.g. `"otype"`
+    pub field: &'static str,
+    /// The value in the first capability
+    pub a: u128,
+    /// The value in the second capability
+    pub b: u128,
+}
+impl std::fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {:#x} != {:#x}", self.field, self.a, self.b)
+    }
+}
 
-pub mod caps;
+/// Reasons a store through an authorizing capability can fail, see [CcxCap::store_through]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StoreError {
+    /// The authorizing capability was untagged
+    TagViolation,
+    /// The authorizing capability was sealed
+    SealViolation,
+    /// The authorizing capability did not have [CompressedCapability::PERM_STORE]
+    PermitStoreViolation,
+}
 
-pub mod wrappers;
+/// Reasons sealing a capability can fail, see [CcxCap::seal_checked]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SealError {
+    /// The otype was [CompressedCapability::OTYPE_UNSEALED] or doesn't fit in the otype field
+    InvalidOType,
+    /// Sealing would change the decoded bounds
+    BoundsChanged,
+}
 
-#[cfg(test)]
-mod tests {
-    use crate::CompressedCapability;
-    // TODO port some tests from the C tests?
-    #[test]
-    fn test_printing() {
-        let cap = crate::caps::cheriv9::cc64::Cc64::decompress_raw(0, 0, false);
-        println!("{:?}", cap);
+/// Whether some bounds can be encoded exactly, see [CcxCap::representability]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Representability<T: CompressedCapability> {
+    /// The bounds can be encoded exactly
+    Exact,
+    /// The bounds can't be encoded exactly, because the base or top isn't aligned to `required_alignment`
+    /// (from [CompressedCapability::get_required_alignment] for the length),
+    /// or the length itself isn't representable.
+    /// `[representable_base, representable_top)` are the smallest exactly-representable bounds containing them,
+    /// see [CcxCap::round_up_to_representable].
+    Inexact {
+        required_alignment: T::Length,
+        representable_base: T::Addr,
+        representable_top: T::Length,
+    },
+    /// The bounds are reversed (base above top) or the top is above the address space,
+    /// so they can't be encoded at all, see [CcxCap::checked_length].
+    /// Only possible for bounds decoded from arbitrary bits, or modified directly.
+    Malformed,
+}
+impl<T: CompressedCapability> Representability<T> {
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Representability::Exact)
+    }
+}
+/// Reasons the offset of a capability can't be represented as an address, see [wrappers::CheriRVFuncs::getCapOffsetBits]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OffsetError {
+    /// The cursor is below the base
+    Negative,
+    /// The offset is too large for the address type
+    OutOfRange,
+}
 
-        let cap = crate::caps::cheriv9::cc128::Cc128::decompress_raw(0, 0, false);
-        println!("{:?}", cap);
-        dbg!(cap.is_exact());
+/// Reasons a (code, data) pair fails the `CInvoke` checks, see [wrappers::CheriRVFuncs::checkCInvoke].
+///
+/// The checks are made in the order of the variants, so the first failing one is reported.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CInvokeError {
+    /// The code capability is untagged
+    CodeUntagged,
+    /// The data capability is untagged
+    DataUntagged,
+    /// The code capability is unsealed
+    CodeUnsealed,
+    /// The data capability is unsealed
+    DataUnsealed,
+    /// The code and data capabilities are sealed with different otypes
+    OTypeMismatch,
+    /// The code capability lacks [CompressedCapability::PERM_CINVOKE]
+    CodeMissingCInvoke,
+    /// The data capability lacks [CompressedCapability::PERM_CINVOKE]
+    DataMissingCInvoke,
+    /// The code capability lacks [CompressedCapability::PERM_EXECUTE]
+    CodeMissingExecute,
+    /// The data capability has [CompressedCapability::PERM_EXECUTE]
+    DataHasExecute,
+    /// The otype is reserved (e.g. a sentry), so can't be invoked
+    ReservedOType,
+}
+
+/// Reasons a capability can't be converted to another format, see [caps::cheriv9::cc64::Cap::try_from_cc128]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The cursor, base or top don't fit in the target format's address space
+    AddressOutOfRange,
+    /// The bounds can't be represented exactly in the target format, or the cursor is outside its representable region
+    BoundsNotRepresentable,
+    /// The otype doesn't fit in the target format's otype field
+    OTypeNotRepresentable,
+}
+
+/// Reasons setting exact bounds can fail, see [CcxCap::set_bounds_exact]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SetBoundsError<A, L> {
+    /// The capability was untagged
+    RequiresTag,
+    /// The requested top was below the requested base, or above the top of the address space
+    InvalidRequest,
+    /// The requested bounds weren't representable, so were rounded out to these bounds
+    Rounded { actual_base: A, actual_top: L },
+}
+
+/// Reasons deriving a capability can fail, see [CcxCap::derive_subcap]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DeriveError {
+    /// The parent capability is untagged or sealed
+    InvalidParent,
+    /// The requested bounds aren't within the parent's bounds, or are inverted
+    NotMonotone,
+    /// The requested bounds can't be represented exactly
+    BoundsRounded,
+    /// The requested cursor isn't representable with the requested bounds
+    NotRepresentable,
+}
+
+/// The flags field of a format-`T` capability, as returned by [CcxCap::flags_typed].
+///
+/// The only architecturally meaningful flag is the execution mode (capability or integer pointer mode),
+/// whose encoding depends on the format, see [CompressedCapability::FLAGS_CAP_MODE].
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Flags<T: CompressedCapability> {
+    bits: u8,
+    format: PhantomData<T>,
+}
+impl<T: CompressedCapability> Flags<T> {
+    pub fn from_bits(bits: u8) -> Self {
+        Flags {
+            bits,
+            format: PhantomData,
+        }
+    }
+    /// The flags selecting capability mode (`cap_mode == true`) or integer mode.
+    ///
+    /// Formats without a mode bit always have zero flags.
+    pub fn from_cap_mode(cap_mode: bool) -> Self {
+        let bits = match T::FLAGS_CAP_MODE {
+            Some(mode_cap) if cap_mode => mode_cap,
+            Some(mode_cap) => mode_cap ^ 1,
+            None => 0,
+        };
+        Self::from_bits(bits)
+    }
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+    /// Returns true if the mode bit selects capability mode. Always false for formats without a mode bit.
+    pub fn cap_mode(&self) -> bool {
+        match T::FLAGS_CAP_MODE {
+            Some(mode_cap) => self.bits & 1 == mode_cap,
+            None => false,
+        }
+    }
+}
+impl<T: CompressedCapability> From<Flags<T>> for u8 {
+    fn from(flags: Flags<T>) -> u8 {
+        flags.bits
+    }
+}
+// Implemented manually to show the decoded mode
+impl<T: CompressedCapability> Debug for Flags<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Flags")
+            .field("bits", &self.bits)
+            .field("cap_mode", &self.cap_mode())
+            .finish()
     }
+}
 
-    #[test]
-    fn test_cc128_u128_conversion() {
-        // Generate a capability between 0, 0x1_0000_0000_0000_0000 with the current cursor/address at 0x100
-        // When we get the 128-bit top(), it should be the same as the one we specified.
-        // If it isn't, the memory representation of FfiU128 and the C u128 may be different
+/// An otype, classified using the constants of a format, see [CcxCap::otype_kind].
+///
+/// The raw values of [OType::Unsealed] and [OType::Sentry] depend on the format,
+/// so conversions to and from `u32` take the format as a type parameter ([Self::from_raw], [Self::to_raw])
+/// instead of implementing `TryFrom<u32>`/`Into<u32>`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OType {
+    /// [CompressedCapability::OTYPE_UNSEALED]
+    Unsealed,
+    /// [CompressedCapability::OTYPE_SENTRY]
+    Sentry,
+    /// Any other otype which can't be used for sealing with `CSeal`, i.e. [CompressedCapability::OTYPE_RESERVED2],
+    /// [CompressedCapability::OTYPE_RESERVED3] or above [CompressedCapability::MAX_UNRESERVED_OTYPE]
+    Reserved(u32),
+    /// An unreserved object type
+    Sealed(u32),
+}
+impl OType {
+    /// Classifies a raw format-`T` otype, returning None if it's above [CompressedCapability::MAX_REPRESENTABLE_OTYPE].
+    ///
+    /// Counterpart: [Self::to_raw]
+    pub fn from_raw<T: CompressedCapability>(otype: u32) -> Option<OType> {
+        if otype > T::MAX_REPRESENTABLE_OTYPE {
+            None
+        } else if otype == T::OTYPE_UNSEALED {
+            Some(OType::Unsealed)
+        } else if otype == T::OTYPE_SENTRY {
+            Some(OType::Sentry)
+        } else if otype == T::OTYPE_RESERVED2 || otype == T::OTYPE_RESERVED3 || otype > T::MAX_UNRESERVED_OTYPE {
+            Some(OType::Reserved(otype))
+        } else {
+            Some(OType::Sealed(otype))
+        }
+    }
 
-        let base: u64 = 0x1000_0000_0000;
-        let top: u128 = 0x2000_0000_0000;
-        let cap = crate::caps::cheriv9::cc128::Cc128::make_max_perms_cap(base, base, top);
-        assert_eq!(cap.top(), top);
-        assert_eq!(cap._cr_top, top);
-        // cr_base is stored directly after _cr_top, so if the sizes for FfiU128 and C u128 are different it will have been overwritten
-        assert_eq!(cap.cr_base, base);
+    /// Returns the raw format-`T` otype.
+    ///
+    /// The values in [OType::Reserved] and [OType::Sealed] are returned as-is, even if they don't classify as such for `T`.
+    pub fn to_raw<T: CompressedCapability>(self) -> u32 {
+        match self {
+            OType::Unsealed => T::OTYPE_UNSEALED,
+            OType::Sentry => T::OTYPE_SENTRY,
+            OType::Reserved(otype) | OType::Sealed(otype) => otype,
+        }
+    }
+}
+
+/// Reasons the `CSeal` instruction can fail, see [CcxCap::cseal]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CSealError {
+    /// The capability to seal was untagged
+    TagViolation,
+    /// The sealing authority was untagged
+    AuthorityTagViolation,
+    /// The capability to seal was already sealed
+    SealViolation,
+    /// The sealing authority was sealed
+    AuthoritySealViolation,
+    /// The sealing authority did not have [CompressedCapability::PERM_SEAL]
+    PermitSealViolation,
+    /// The sealing authority's cursor was outside of its bounds
+    LengthViolation,
+    /// The sealing authority's cursor was not a valid unreserved otype
+    InvalidOType,
+    /// Sealing would change the bounds of the capability
+    Unrepresentable,
+}
+
+/// Reasons a capability cannot be rebuilt from a template, see [CcxCap::build_cap]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BuildCapError {
+    /// The authorizing capability was untagged
+    TagViolation,
+    /// The authorizing capability was sealed
+    SealViolation,
+    /// The template's bounds are not within the authorizing capability's bounds, or are malformed (base > top)
+    LengthViolation,
+    /// The template has permissions or software permissions not present in the authorizing capability
+    PermissionViolation,
+}
+/// Mismatches between the Rust and C capability layouts, see [CompressedCapability::validate_field_widths]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The decoded base didn't match the requested base
+    Base,
+    /// The decoded top didn't match the requested top
+    Top,
+    /// The decoded cursor didn't match the requested cursor
+    Cursor,
+    /// Compressing and decompressing a capability gave a different capability
+    RoundTrip,
+}
+
+/// A semantic change in the rights granted by a capability, see [CcxCap::rights_delta]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RightsChange {
+    /// Both capabilities were tagged, but the newer one gained permissions or bounds
+    MonotonicityViolation,
+    /// The newer capability has these permissions, the older one didn't
+    PermissionsGained(u32),
+    /// The older capability had these permissions, the newer one doesn't
+    PermissionsLost(u32),
+    /// The newer capability has these software permissions, the older one didn't
+    SoftwarePermissionsGained(u32),
+    /// The older capability had these software permissions, the newer one doesn't
+    SoftwarePermissionsLost(u32),
+    /// The newer capability's bounds include memory outside the older one's bounds
+    BoundsWidened,
+    /// The older capability's bounds include memory outside the newer one's bounds
+    BoundsNarrowed,
+    /// The newer capability is sealed with `otype`, which the older one wasn't
+    Sealed { otype: u32 },
+    /// The older capability was sealed with `otype`, which the newer one isn't
+    Unsealed { otype: u32 },
+}
+
+/// Implements `operator==` from cheri_compressed_cap_common.h
+impl<T: CompressedCapability> PartialEq for CcxCap<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cr_tag == other.cr_tag
+            && self._cr_cursor == other._cr_cursor
+            && self.cr_pesbt == other.cr_pesbt
+    }
+}
+impl<T: CompressedCapability> Eq for CcxCap<T> {}
+/// Orders capabilities by where they point: by [CcxCap::address], then [CcxCap::base], [CcxCap::top], the tag,
+/// and finally [CcxCap::cr_pesbt] so that only equal capabilities compare as [Ordering::Equal](std::cmp::Ordering::Equal).
+///
+/// Conversely, equal capabilities always compare as `Equal`: the bounds are decoded from the cursor and pesbt,
+/// so two capabilities with the same tag, cursor and pesbt can't have different bounds
+/// unless their decoded fields were modified without going through the C functions
+/// (e.g. with [CcxCap::set_raw_cache_unchecked]).
+impl<T: CompressedCapability> Ord for CcxCap<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.address()
+            .cmp(&other.address())
+            .then_with(|| self.base().cmp(&other.base()))
+            .then_with(|| self.top().cmp(&other.top()))
+            .then_with(|| self.cr_tag.cmp(&other.cr_tag))
+            .then_with(|| self.cr_pesbt.cmp(&other.cr_pesbt))
+    }
+}
+impl<T: CompressedCapability> PartialOrd for CcxCap<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+/// Hashes the same fields compared by [PartialEq], so equal capabilities have equal hashes.
+/// The bounds fields are derived from [CcxCap::cr_pesbt] and the cursor, so aren't hashed separately.
+impl<T: CompressedCapability> Hash for CcxCap<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cr_tag.hash(state);
+        self._cr_cursor.hash(state);
+        self.cr_pesbt.hash(state);
+    }
+}
+/// Equivalent to initialization pattern used in tests:
+/// ```c
+/// ccx_cap_t value;
+/// memset(&value, 0, sizeof(value));
+/// ```
+///
+/// cc64.rs doesn't pick it up when it was automatically #[derive]-d, so it's manually implemented here
+impl<T: CompressedCapability> Default for CcxCap<T> {
+    fn default() -> Self {
+        CcxCap {
+            // Use Default::default for the associated types (Addr, Length, Offset)
+            // Rust doesn't have enough information to know they are numbers
+            _cr_cursor: Default::default(),
+            cr_pesbt: Default::default(),
+            _cr_top: Default::default(),
+            cr_base: Default::default(),
+            cr_tag: 0,
+            cr_bounds_valid: 0,
+            cr_exp: 0,
+            cr_extra: 0,
+        }
+    }
+}
+/// Debug printer for capabilities that decodes the PESBT field instead of printing it raw.
+impl<T: CompressedCapability> Debug for CcxCap<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CcxCap")
+            .field("cr_cursor", &self._cr_cursor)
+            .field("cr_base", &self.cr_base)
+            .field("cr_top", &self._cr_top)
+            .field("cr_tag", &self.cr_tag)
+            .field("cr_bounds_valid", &self.cr_bounds_valid)
+            .field("cr_exp", &self.cr_exp)
+            .field("cr_extra", &self.cr_extra)
+            .field("permissions", &self.permissions())
+            .field("software_permissions", &self.software_permissions())
+            .field("otype", &self.otype())
+            .field("reserved_bits", &self.reserved_bits())
+            .field("flags", &self.flags())
+            .finish()
+    }
+}
+
+/// Byte order for [CcxCap::to_mem_bytes]/[CcxCap::from_mem_bytes]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// Writes `addr` into `bytes`, which must be exactly `size_of::<A>()` long
+fn write_addr_bytes<A: NumType>(addr: A, bytes: &mut [u8], endian: Endian) {
+    let len = bytes.len();
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let shift = match endian {
+            Endian::Little => i,
+            Endian::Big => len - 1 - i,
+        } * 8;
+        *byte = num_traits::cast((addr >> shift) & low_bits_mask::<A>(8)).unwrap();
+    }
+}
+
+/// Reads an address of type `A` from `bytes`, which must be exactly `size_of::<A>()` long
+fn read_addr_bytes<A: NumType>(bytes: &[u8], endian: Endian) -> A {
+    let len = bytes.len();
+    bytes.iter().enumerate().fold(A::zero(), |addr, (i, &byte)| {
+        let shift = match endian {
+            Endian::Little => i,
+            Endian::Big => len - 1 - i,
+        } * 8;
+        addr | (num_traits::cast::<u8, A>(byte).unwrap() << shift)
+    })
+}
+
+/// Structure matching the C type `_cc_N(bounds_bits)`.
+/// Represents a floating-point encoded capability bounds.
+///
+/// The widths of each field depend on the format:
+///
+/// | Format          | `B`, `T` (`MANTISSA_WIDTH`) | `E` |
+/// |-----------------|-----------------------------|-----|
+/// | CHERIv9 64-bit  | 8                           | 6   |
+/// | CHERIv9 128-bit | 14                          | 6   |
+/// | Morello         | 16                          | 6   |
+/// | RISC-V Y 64-bit | 10                          | 5   |
+/// | RISC-V Y 128-bit| 14                          | 6   |
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CcxBoundsBits {
+    /// The bottom mantissa, `MANTISSA_WIDTH` bits wide. With an internal exponent the low bits (which stored the exponent) are zero.
+    pub b: u16,
+    /// The top mantissa, including the two top bits inferred from `b`. With an internal exponent the low bits are zero.
+    pub t: u16,
+    /// The exponent, zero without an internal exponent
+    pub e: u8,
+    /// Whether the exponent was stored in the low bits of `B` and `T` (the internal exponent flag)
+    pub ie: bool,
+}
+impl CcxBoundsBits {
+    /// The bottom mantissa `B`
+    pub fn bottom_bits(&self) -> u16 {
+        self.b
+    }
+    /// The top mantissa `T`, including the two inferred top bits
+    pub fn top_bits(&self) -> u16 {
+        self.t
+    }
+    /// The exponent `E`
+    pub fn exponent(&self) -> u8 {
+        self.e
+    }
+    /// The internal exponent flag `IE`
+    pub fn internal_exponent(&self) -> bool {
+        self.ie
+    }
+
+    /// Decodes the base and top these bounds bits describe for a capability with the given cursor,
+    /// without constructing a capability. See [CompressedCapability::compute_base_top].
+    pub fn decode<T: CompressedCapability>(&self, cursor: T::Addr) -> (T::Addr, T::Length) {
+        T::compute_base_top(*self, cursor)
+    }
+}
+
+// Link the C functions
+mod c_funcs;
+
+mod bounds;
+
+mod fields;
+
+#[cfg(any(test, feature = "rust-bounds"))]
+mod concentrate;
+
+pub mod caps;
+
+pub mod wrappers;
+
+pub mod cheri_instructions;
+
+pub mod platform;
+
+pub mod permissions;
+
+pub mod builder;
+
+pub mod prelude;
+
+pub mod reserved;
+
+pub mod register_file;
+
+pub mod memory;
+
+#[cfg(feature = "serde")]
+mod serialization;
+
+#[cfg(feature = "bytemuck")]
+mod pod;
+
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+
+#[cfg(feature = "rand")]
+pub mod random;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+#[cfg(test)]
+mod proptests;
+
+#[cfg(test)]
+mod tests {
+    use crate::CompressedCapability;
+    // Test vectors ported from the C tests are in tests/c_test_vectors.rs
+    #[test]
+    fn test_printing() {
+        let cap = crate::caps::cheriv9::cc64::Cc64::decompress_raw(0, 0, false);
+        println!("{:?}", cap);
+
+        let cap = crate::caps::cheriv9::cc128::Cc128::decompress_raw(0, 0, false);
+        println!("{:?}", cap);
+        dbg!(cap.is_exact());
+    }
+
+    #[test]
+    fn test_cc128_u128_conversion() {
+        // Generate a capability between 0, 0x1_0000_0000_0000_0000 with the current cursor/address at 0x100
+        // When we get the 128-bit top(), it should be the same as the one we specified.
+        // If it isn't, the memory representation of the Rust FfiLength (u128) and the C u128 may be different
+
+        let base: u64 = 0x1000_0000_0000;
+        let top: u128 = 0x2000_0000_0000;
+        let cap = crate::caps::cheriv9::cc128::Cc128::make_max_perms_cap(base, base, top);
+        assert_eq!(cap.top(), top);
+        assert_eq!(cap._cr_top, top);
+        // cr_base is stored directly after _cr_top, so if the sizes for FfiLength and C u128 are different it will have been overwritten
+        assert_eq!(cap.cr_base, base);
+    }
+
+    #[test]
+    fn test_cc128_symbols_link() {
+        // Call the C functions directly, so this checks the symbols resolve even with the rust-bounds feature
+        use crate::c_funcs::*;
+        use crate::caps::cheriv9::cc128::Cap;
+
+        let mut cap = Cap::default();
+        unsafe { cc128_decompress_mem(0, 0x1234, false, &mut cap) };
+        assert_eq!(cap.address(), 0x1234);
+        assert_eq!(unsafe { cc128_compress_mem(&cap) }, 0);
+        assert_eq!(unsafe { cc128_get_representable_length(0x1000) }, 0x1000);
+    }
+
+    #[test]
+    fn test_validate_field_widths() {
+        use crate::caps::{cheriv9, morello, rvy};
+
+        assert_eq!(cheriv9::cc64::Cc64::validate_field_widths(), Ok(()));
+        assert_eq!(cheriv9::cc128::Cc128::validate_field_widths(), Ok(()));
+        assert_eq!(morello::Cc128::validate_field_widths(), Ok(()));
+        assert_eq!(rvy::cc64::Cc64::validate_field_widths(), Ok(()));
+        assert_eq!(rvy::cc128::Cc128::validate_field_widths(), Ok(()));
+    }
+
+    #[test]
+    fn test_with_tag() {
+        let cap = crate::caps::cheriv9::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let tagged = cap.with_tag(true);
+        let untagged = cap.with_tag(false);
+        assert!(tagged.tag());
+        assert!(!untagged.tag());
+        assert_ne!(tagged, untagged);
+        // Only the tag should differ
+        assert_eq!(tagged.reg_representation().1, untagged.reg_representation().1);
+    }
+
+    #[test]
+    fn test_store_through() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::StoreError;
+
+        let authority = Cc128::make_max_perms_cap(0, 0x1000, 0x10000);
+        let global_cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let mut local_cap = global_cap;
+        local_cap.set_permissions(global_cap.permissions() & !Cc128::PERM_GLOBAL);
+
+        // A fully-permissioned authority preserves tags of both local and global caps
+        assert!(global_cap.store_through(&authority).unwrap().tag());
+        assert!(local_cap.store_through(&authority).unwrap().tag());
+
+        // Without PERM_STORE_LOCAL, global caps can still be stored but local caps are detagged
+        let mut no_store_local = authority;
+        no_store_local.set_permissions(authority.permissions() & !Cc128::PERM_STORE_LOCAL);
+        let stored_global = global_cap.store_through(&no_store_local).unwrap();
+        assert!(stored_global.tag());
+        assert_eq!(stored_global, global_cap);
+        let stored_local = local_cap.store_through(&no_store_local).unwrap();
+        assert!(!stored_local.tag());
+        assert_eq!(stored_local, local_cap.with_tag(false));
+
+        // Without PERM_STORE_CAP, everything is detagged
+        let mut no_store_cap = authority;
+        no_store_cap.set_permissions(authority.permissions() & !Cc128::PERM_STORE_CAP);
+        assert!(!global_cap.store_through(&no_store_cap).unwrap().tag());
+
+        // Without PERM_STORE or a valid authority, nothing can be stored
+        let mut no_store = authority;
+        no_store.set_permissions(authority.permissions() & !Cc128::PERM_STORE);
+        assert_eq!(global_cap.store_through(&no_store), Err(StoreError::PermitStoreViolation));
+        assert_eq!(global_cap.store_through(&authority.with_tag(false)), Err(StoreError::TagViolation));
+    }
+
+    #[test]
+    fn test_effective_address() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let mut cap = Cc128::make_max_perms_cap(0x1000, 0x1400, 0x2000);
+        assert_eq!(cap.effective_address(false), 0x1400);
+        assert_eq!(cap.effective_address(true), 0x1400);
+
+        // Negative offsets wrap back around to the cursor
+        cap.set_address_unchecked(0x800);
+        assert_eq!(cap.offset(), -0x800);
+        assert_eq!(cap.effective_address(false), 0x800);
+        assert_eq!(cap.effective_address(true), 0x800);
+
+        let mut cap = Cc128::make_max_perms_cap(0xFFFF_FFFF_FFFF_0000, 0xFFFF_FFFF_FFFF_0000, 0x1_0000_0000_0000_0000);
+        cap.set_address_unchecked(0xFFFF_FFFF_FFFF_FFFF);
+        assert_eq!(cap.effective_address(true), 0xFFFF_FFFF_FFFF_FFFF);
+    }
+
+    #[test]
+    fn test_compute_top() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::caps::cheriv9::cc64::Cc64;
+
+        assert_eq!(Cc128::compute_top(0x1000, 0x1000), 0x2000);
+        assert_eq!(Cc128::compute_top(u64::MAX - 1, 2), 1u128 << 64);
+        assert_eq!(Cc128::compute_top(u64::MAX, 1u128 << 64), (1u128 << 65) - 1);
+        assert_eq!(Cc64::compute_top(u32::MAX - 1, 2), 1u64 << 32);
+    }
+
+    #[test]
+    fn test_is_valid_for_platform() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::platform::PlatformProfile;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(cap.is_valid_for_platform(&PlatformProfile::cc128_reference()));
+
+        // A platform which doesn't implement PERM_SETCID or any otypes above 0xFF
+        let profile = PlatformProfile {
+            permissions: PlatformProfile::cc128_reference().permissions & !Cc128::PERM_SETCID,
+            max_otype: 0xFF,
+            ..PlatformProfile::cc128_reference()
+        };
+        assert!(!cap.is_valid_for_platform(&profile));
+
+        let mut cap = cap;
+        cap.set_permissions(cap.permissions() & !Cc128::PERM_SETCID);
+        // Unsealed otype is always fine, even though it's above max_otype
+        assert!(cap.is_valid_for_platform(&profile));
+        cap.set_otype(0x10);
+        assert!(cap.is_valid_for_platform(&profile));
+        cap.set_otype(0x100);
+        assert!(!cap.is_valid_for_platform(&profile));
+    }
+
+    #[test]
+    fn test_precision_loss() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::caps::cheriv9::cc64::Cc64;
+
+        assert_eq!(Cc128::precision_loss(0x1000, 0x2000), 0);
+        assert_eq!(Cc128::precision_loss(0, 1u128 << 64), 0);
+        assert_eq!(Cc64::precision_loss(0x1000, 0x2000), 0);
+
+        // A length of 0x12345 needs an exponent, and requires 128-byte alignment
+        // => base is rounded down to 0x1000, top 0x13346 is rounded up to 0x13380
+        assert_eq!(Cc128::alignment_overhang(0x1001, 0x1001 + 0x12345), (1, 0x3a));
+        assert_eq!(Cc128::precision_loss(0x1001, 0x1001 + 0x12345), 0x3b);
+    }
+
+    #[test]
+    fn test_same_sealed_object() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let mut object = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        object.set_otype(5);
+        let mut restricted = object;
+        restricted.set_permissions(Cc128::PERM_LOAD);
+        restricted.set_address_unchecked(0x1800);
+        assert!(object.same_sealed_object(&restricted));
+
+        let mut other_bounds = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x3000);
+        other_bounds.set_otype(5);
+        assert!(!object.same_sealed_object(&other_bounds));
+
+        let mut other_otype = object;
+        other_otype.set_otype(6);
+        assert!(!object.same_sealed_object(&other_otype));
+
+        let unsealed = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(!unsealed.same_sealed_object(&unsealed));
+    }
+
+    #[test]
+    fn test_offset_narrowing() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let mut cap = Cc128::make_max_perms_cap(0, 0, 0x1_0000_0000_0000_0000);
+        cap.set_address_unchecked(u64::MAX);
+        assert_eq!(cap.offset(), u64::MAX as i128);
+        assert_eq!(cap.offset_checked_addr(), Some(u64::MAX));
+        assert_eq!(cap.offset_saturating_addr(), u64::MAX);
+        assert_eq!(cap.offset_wrapping_addr(), u64::MAX);
+
+        // Offset exceeding the u32 range is fine for a 64-bit address
+        cap.set_address_unchecked(0x1_0000_0000);
+        assert_eq!(cap.offset_checked_addr(), Some(0x1_0000_0000));
+
+        // Cursor below base => negative offset
+        let mut cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        cap.set_address_unchecked(0xfff);
+        assert_eq!(cap.offset(), -1);
+        assert_eq!(cap.offset_checked_addr(), None);
+        assert_eq!(cap.offset_saturating_addr(), 0);
+        assert_eq!(cap.offset_wrapping_addr(), u64::MAX);
+    }
+
+    #[test]
+    fn test_length_narrowing() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let full = Cc128::make_max_perms_cap(0, 0, 0x1_0000_0000_0000_0000);
+        assert_eq!(full.length(), 0x1_0000_0000_0000_0000);
+        assert_eq!(full.length_checked_addr(), None);
+        assert_eq!(full.length_saturating_addr(), u64::MAX);
+        assert_eq!(full.length_wrapping_addr(), 0);
+
+        let small = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert_eq!(small.length_checked_addr(), Some(0x1000));
+        assert_eq!(small.length_saturating_addr(), 0x1000);
+        assert_eq!(small.length_wrapping_addr(), 0x1000);
+    }
+
+    #[test]
+    fn test_aliases_region() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let mut alias = cap.with_tag(false);
+        alias.set_permissions(Cc128::PERM_LOAD);
+        alias.set_address_unchecked(0x1800);
+        assert!(cap.aliases_region(&alias));
+        assert!(cap.bounds_overlap(&alias));
+
+        // Overlapping, but not the same region
+        let overlapping = Cc128::make_max_perms_cap(0x1800, 0x1800, 0x2800);
+        assert!(cap.bounds_overlap(&overlapping));
+        assert!(overlapping.bounds_overlap(&cap));
+        assert!(!cap.aliases_region(&overlapping));
+
+        // Adjacent regions don't overlap
+        let adjacent = Cc128::make_max_perms_cap(0x2000, 0x2000, 0x3000);
+        assert!(!cap.bounds_overlap(&adjacent));
+        assert!(!cap.aliases_region(&adjacent));
+    }
+
+    #[test]
+    fn test_build_cap() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::{BuildCapError, CcxCap};
+
+        let authority = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x10000);
+
+        let mut template = Cc128::make_max_perms_cap(0x2000, 0x2000, 0x3000).with_tag(false);
+        template.set_permissions(Cc128::PERM_LOAD | Cc128::PERM_STORE);
+        let built = CcxCap::build_cap(&authority, &template).unwrap();
+        assert!(built.tag());
+        assert_eq!(built, template.with_tag(true));
+
+        // Template exceeds the authority's bounds
+        let too_big = Cc128::make_max_perms_cap(0x8000, 0x8000, 0x20000).with_tag(false);
+        assert_eq!(CcxCap::build_cap(&authority, &too_big), Err(BuildCapError::LengthViolation));
+
+        // Template has more permissions than the authority
+        let mut weak_authority = authority;
+        weak_authority.set_permissions(Cc128::PERM_LOAD);
+        assert_eq!(
+            CcxCap::build_cap(&weak_authority, &template),
+            Err(BuildCapError::PermissionViolation)
+        );
+
+        assert_eq!(
+            CcxCap::build_cap(&authority.with_tag(false), &template),
+            Err(BuildCapError::TagViolation)
+        );
+    }
+
+    /// Checks [CcxCap::representable_limits] against decoding the same pesbt with the cursor at/past the limits
+    fn check_representable_limits<T: CompressedCapability>(cap: crate::CcxCap<T>) {
+        use num_traits::{AsPrimitive, One, WrappingAdd, WrappingSub};
+
+        let (repr_base, repr_top) = cap.representable_limits();
+        let pesbt = cap.reg_representation().1[1];
+        let same_bounds = |cursor: T::Addr| T::decompress_raw(pesbt, cursor, false).bounds() == cap.bounds();
+        let repr_last: T::Addr = repr_top.as_().wrapping_sub(&T::Addr::one());
+
+        assert!(same_bounds(repr_base), "{}", cap.detailed());
+        assert!(same_bounds(repr_last), "{}", cap.detailed());
+        assert!(!same_bounds(repr_base.wrapping_sub(&T::Addr::one())), "{}", cap.detailed());
+        assert!(!same_bounds(repr_last.wrapping_add(&T::Addr::one())), "{}", cap.detailed());
+    }
+
+    #[test]
+    fn test_representable_limits() {
+        use crate::caps::{cheriv9, rvy};
+
+        check_representable_limits(cheriv9::cc128::Cc128::make_max_perms_cap(0x10_0000, 0x10_0000, 0x20_0000));
+        check_representable_limits(cheriv9::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800));
+        check_representable_limits(cheriv9::cc64::Cc64::make_max_perms_cap(0x10_0000, 0x10_0000, 0x20_0000));
+        check_representable_limits(rvy::cc128::Cc128::make_max_perms_cap(0x10_0000, 0x10_0000, 0x20_0000));
+        check_representable_limits(rvy::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800));
+
+        // Full address space
+        let full = cheriv9::cc128::Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        assert_eq!(full.representable_limits(), (0, 1 << 64));
+    }
+
+    /// Checks [CcxCap::is_representable_with_new_addr] against decoding the same pesbt with the cursor at/past the
+    /// representable limits, and that the fast check never accepts an address the precise one rejects
+    fn check_representable_with_new_addr<T: CompressedCapability>(cap: crate::CcxCap<T>) {
+        use num_traits::{AsPrimitive, One, WrappingAdd, WrappingSub};
+
+        let (repr_base, repr_top) = cap.representable_limits();
+        let pesbt = cap.reg_representation().1[1];
+        let repr_last: T::Addr = repr_top.as_().wrapping_sub(&T::Addr::one());
+        let addrs = [
+            cap.address(),
+            cap.base(),
+            repr_base,
+            repr_last,
+            repr_base.wrapping_sub(&T::Addr::one()),
+            repr_last.wrapping_add(&T::Addr::one()),
+        ];
+        for &addr in addrs.iter() {
+            let same_bounds = T::decompress_raw(pesbt, addr, false).bounds() == cap.bounds();
+            let precise = cap.is_representable_with_new_addr(addr);
+            assert_eq!(precise, same_bounds, "{:#x} {}", addr, cap.detailed());
+            if T::is_representable_with_addr(&cap, addr, false) {
+                assert!(precise, "{:#x} {}", addr, cap.detailed());
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_representable_with_new_addr() {
+        use crate::caps::cheriv9;
+        use crate::wrappers::CheriRVFuncs;
+
+        crate::for_each_cap_format!(|C| {
+            check_representable_with_new_addr(C::make_max_perms_cap(0x10_0000, 0x10_0000, 0x20_0000));
+        });
+        check_representable_with_new_addr(cheriv9::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800));
+
+        // Sealing doesn't affect representability
+        let cap = cheriv9::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800);
+        let sealed = cap.seal_checked(10).unwrap();
+        check_representable_with_new_addr(sealed);
+        let (repr_base, repr_top) = cap.representable_limits();
+        for &addr in [0x1400, repr_base, repr_base - 1, repr_top as u64, 0xffff_0000].iter() {
+            assert_eq!(sealed.is_representable_with_new_addr(addr), cap.is_representable_with_new_addr(addr));
+        }
+        let mut unsealed = sealed;
+        unsealed.set_otype(cheriv9::cc128::Cc128::OTYPE_UNSEALED);
+        assert_eq!(unsealed, cap);
+
+        // The wrappers report failure when leaving the representable region
+        type Funcs = cheriv9::cc128::Cc128;
+        assert!(Funcs::setCapAddr(&cap, repr_base).0);
+        let (representable, moved) = Funcs::setCapAddr(&cap, repr_top as u64);
+        assert!(!representable);
+        assert_eq!(moved.address(), repr_top as u64);
+        assert!(!Funcs::incCapOffset(&cap, 0xffff_ffff_ffff_0000).0);
+    }
+
+    #[test]
+    fn test_fmt_detailed() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x10_0000, 0x10_0000, 0x20_0000);
+        let e = cap.extract_bounds_bits().e;
+        assert!(e > 0);
+        let (repr_base, repr_top) = cap.representable_limits();
+        let detailed = format!("{}", cap.detailed());
+        assert_eq!(
+            detailed,
+            format!("b:0x100000 t:0x200000 [repr: {:#x}..{:#x} e:{}]", repr_base, repr_top, e)
+        );
+    }
+
+    #[test]
+    fn test_apply_offset_sequence() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::caps::morello;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800);
+
+        // All steps stay within the representable region
+        let moved = cap.apply_offset_sequence(&[0x100, 0x200, 0u64.wrapping_sub(0x80)]);
+        assert!(moved.tag());
+        assert_eq!(moved.address(), 0x1280);
+        assert_eq!(moved.bounds(), cap.bounds());
+
+        // An early step leaves the representable region, and the tag stays cleared after coming back
+        let moved = cap.apply_offset_sequence(&[0x100, 0x10_0000, 0u64.wrapping_sub(0x10_0000)]);
+        assert!(!moved.tag());
+        assert_eq!(moved.address(), 0x1100);
+
+        assert_eq!(cap.apply_offset_sequence(&[]), cap);
+
+        // Morello doesn't use the top address byte for the bounds, so changing it keeps the tag,
+        // even though the new address is outside representable_limits
+        let cap = morello::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800);
+        let moved = cap.apply_offset_sequence(&[0x100, 0x1200_0000_0000_0000]);
+        assert!(moved.tag());
+        assert_eq!(moved.bounds(), cap.bounds());
+        assert_eq!(moved, cap.with_address(0x1200_0000_0000_1100).1);
+
+        // Flipping bit 55 changes the sign extension of the bounds, so the tag stays cleared after flipping it back
+        let moved = cap.apply_offset_sequence(&[0x80_0000_0000_0000, 0u64.wrapping_sub(0x80_0000_0000_0000)]);
+        assert!(!moved.tag());
+        assert_eq!(moved.address(), 0x1000);
+    }
+
+    #[test]
+    fn test_morello_bounds_uses_value() {
+        use crate::caps::morello::Cc128;
+
+        // Small exponent => high bits of the bounds come from the address
+        let small = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(small.bounds_uses_value());
+
+        // Covering the whole address space => bounds are fully described by E/B/T
+        let full = Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        assert!(!full.bounds_uses_value());
+    }
+
+    #[test]
+    fn test_bounds_uses_value_all_formats() {
+        use crate::caps::{cheriv9, morello, rvy};
+
+        // Morello: the high bits of small bounds come from the address
+        let small = morello::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(small.bounds_uses_value());
+        // With a high exponent the bounds are fully described by E/B/T
+        let large = morello::Cc128::make_max_perms_cap(0, 0x1234, 1 << 63);
+        assert!(!large.bounds_uses_value());
+        assert!(!morello::Cc128::make_max_perms_cap(0, 0, 1 << 64).bounds_uses_value());
+
+        // Other formats never need to recompute the bounds
+        assert!(!cheriv9::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000).bounds_uses_value());
+        assert!(!cheriv9::cc64::Cc64::make_max_perms_cap(0x1000, 0x1000, 0x2000).bounds_uses_value());
+        assert!(!rvy::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000).bounds_uses_value());
+        assert!(!rvy::cc64::Cc64::make_max_perms_cap(0x1000, 0x1000, 0x2000).bounds_uses_value());
+    }
+
+    #[test]
+    fn test_morello_bounds_valid_and_exp() {
+        use crate::caps::morello::{CcMorello, CcMorelloCap};
+
+        // Decoding fills in cr_exp and cr_bounds_valid, which stay 1/0 for other formats
+        let cap: CcMorelloCap = CcMorello::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let (tag, [cursor, pesbt]) = cap.reg_representation();
+        let decoded = CcMorello::decompress_raw(pesbt, cursor, tag);
+        assert_eq!(decoded, cap);
+        assert_eq!(decoded.cr_bounds_valid, 1);
+        assert_eq!(decoded.cr_exp, CcMorello::extract_bounds_bits(pesbt).e);
+        assert_eq!(decoded.bounds_uses_value(), cap.bounds_uses_value());
+
+        let mut narrowed = cap;
+        assert!(narrowed.set_bounds_unchecked(0x10));
+        assert_eq!(narrowed.cr_bounds_valid, 1);
+        assert_eq!(narrowed.cr_exp, 0);
+        assert!(narrowed.bounds_uses_value());
+
+        let full = CcMorello::make_max_perms_cap(0, 0, 1 << 64);
+        let (tag, [cursor, pesbt]) = full.reg_representation();
+        let decoded = CcMorello::decompress_raw(pesbt, cursor, tag);
+        assert_eq!(decoded.cr_bounds_valid, 1);
+        assert!(!decoded.bounds_uses_value());
+
+        // Exponents between the maximum and the maximum encodable value decode as invalid
+        let bad_pesbt = CcMorello::encode_bounds_bits(crate::CcxBoundsBits {
+            b: 0,
+            t: 0,
+            e: 51,
+            ie: true,
+        });
+        let bad = CcMorello::decompress_raw(bad_pesbt, 0, false);
+        assert_eq!(bad.cr_bounds_valid, 0);
+        assert_eq!(bad.bounds(), (0, 1 << 64));
+    }
+
+    #[test]
+    fn test_reserved_typed_roundtrip() {
+        use crate::caps::{cheriv9, rvy};
+        use crate::reserved::ReservedBits;
+
+        let mut cap = cheriv9::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000).with_tag(false);
+        assert_eq!(cap.reserved_typed().bits(), 0);
+        let reserved = ReservedBits::new(0b10).unwrap();
+        cap.set_reserved_typed(reserved);
+        assert_eq!(cap.reserved_typed(), reserved);
+
+        // Reserved bits must survive compression/decompression (only allowed for untagged caps)
+        let (_, [cursor, pesbt]) = cap.mem_representation();
+        let decompressed = cheriv9::cc128::Cc128::decompress_mem(pesbt, cursor, false);
+        assert_eq!(decompressed.reserved_typed(), reserved);
+        assert_eq!(decompressed.bounds(), cap.bounds());
+
+        // RISC-V Y has two separate reserved fields
+        let mut cap = rvy::cc128::Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000).with_tag(false);
+        let reserved = ReservedBits::new(0).unwrap().with_field(0, 0x4321).unwrap().with_field(1, 0x55).unwrap();
+        cap.set_reserved_typed(reserved);
+        assert_eq!(cap.reserved_typed().field(0), 0x4321);
+        assert_eq!(cap.reserved_typed().field(1), 0x55);
+        let (_, [cursor, pesbt]) = cap.reg_representation();
+        assert_eq!(rvy::cc128::Cc128::decompress_raw(pesbt, cursor, false).reserved_typed(), reserved);
+    }
+
+    #[test]
+    fn test_seal_checked() {
+        use crate::caps::{cheriv9, morello};
+        use crate::SealError;
+
+        let cap = cheriv9::cc128::Cc128::make_max_perms_cap(0, 0x1000, 0x1_0000_0000);
+        let sealed = cap.seal_checked(42).unwrap();
+        assert_eq!(sealed.otype(), 42);
+        assert!(sealed.is_sealed());
+        assert_eq!(sealed.bounds(), cap.bounds());
+        assert_eq!(cap.seal_checked(cheriv9::cc128::Cc128::OTYPE_UNSEALED), Err(SealError::InvalidOType));
+        assert_eq!(cap.seal_checked(0x4_0000), Err(SealError::InvalidOType));
+
+        // Morello otypes are separate from the bounds too, so large caps can be sealed
+        let cap = morello::Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        let sealed = cap.seal_checked(0x7fff).unwrap();
+        assert_eq!(sealed.otype(), 0x7fff);
+        assert_eq!(sealed.bounds(), cap.bounds());
+        assert_eq!(cap.seal_checked(0), Err(SealError::InvalidOType));
+        assert_eq!(cap.seal_checked(0x8000), Err(SealError::InvalidOType));
+    }
+
+    #[test]
+    fn test_encoding_family() {
+        use crate::caps::{cheriv9, morello, rvy};
+
+        let concentrate = [
+            cheriv9::cc64::Cc64::IS_CHERI_CONCENTRATE,
+            cheriv9::cc128::Cc128::IS_CHERI_CONCENTRATE,
+            morello::Cc128::IS_CHERI_CONCENTRATE,
+        ];
+        assert!(concentrate.iter().all(|&c| c));
+        assert_eq!(cheriv9::cc64::Cc64::encoding_family(), "CHERIv9");
+        assert_eq!(cheriv9::cc128::Cc128::encoding_family(), "CHERIv9");
+        assert_eq!(morello::Cc128::encoding_family(), "Morello");
+        assert_eq!(rvy::cc128::Cc128::encoding_family(), "RISC-V Y");
+    }
+
+    #[test]
+    fn test_address_space_fraction() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+
+        assert_eq!(Cc128::make_max_perms_cap(0, 0, 1 << 64).address_space_fraction(), 1.0);
+        assert_eq!(Cc128::make_max_perms_cap(0, 0, 1 << 63).address_space_fraction(), 0.5);
+        assert_eq!(Cc64::make_max_perms_cap(0, 0, 1 << 32).address_space_fraction(), 1.0);
+        assert_eq!(Cc64::make_max_perms_cap(0, 0, 1 << 31).address_space_fraction(), 0.5);
+
+        let small = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000).address_space_fraction();
+        assert!(small > 0.0 && small < 1e-15);
+    }
+
+    #[test]
+    fn test_cseal() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::CSealError;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        // Sealing authority for otypes 0..0x100, pointing at otype 0x42
+        let auth = Cc128::make_max_perms_cap(0, 0x42, 0x100);
+
+        let sealed = cap.cseal(&auth).unwrap();
+        assert!(sealed.tag());
+        assert_eq!(sealed.otype(), 0x42);
+        assert_eq!(sealed.bounds(), cap.bounds());
+        assert_eq!(sealed.address(), cap.address());
+
+        assert_eq!(cap.with_tag(false).cseal(&auth), Err(CSealError::TagViolation));
+        assert_eq!(cap.cseal(&auth.with_tag(false)), Err(CSealError::AuthorityTagViolation));
+        assert_eq!(sealed.cseal(&auth), Err(CSealError::SealViolation));
+        assert_eq!(cap.cseal(&auth.seal_checked(5).unwrap()), Err(CSealError::AuthoritySealViolation));
+
+        let mut no_seal_perm = auth;
+        no_seal_perm.set_permissions(auth.permissions() & !Cc128::PERM_SEAL);
+        assert_eq!(cap.cseal(&no_seal_perm), Err(CSealError::PermitSealViolation));
+
+        let mut out_of_bounds = auth;
+        out_of_bounds.set_address_unchecked(0x100);
+        assert_eq!(cap.cseal(&out_of_bounds), Err(CSealError::LengthViolation));
+
+        // Cursor in bounds, but not a valid otype
+        let big_auth = Cc128::make_max_perms_cap(0, Cc128::OTYPE_SENTRY as u64, 1 << 20);
+        assert_eq!(cap.cseal(&big_auth), Err(CSealError::InvalidOType));
+        let mut beyond_otypes = big_auth;
+        beyond_otypes.set_address_unchecked(Cc128::MAX_REPRESENTABLE_OTYPE as u64 + 1);
+        assert_eq!(cap.cseal(&beyond_otypes), Err(CSealError::InvalidOType));
+    }
+
+    /// Checks [CompressedCapability::next_representable_base] gives exact bounds for lengths with different alignments
+    fn check_next_representable_base<T: CompressedCapability>()
+    where
+        u64: num_traits::AsPrimitive<T::Addr>,
+    {
+        use num_traits::{AsPrimitive, One, WrappingAdd, Zero};
+
+        for &req_length in [0x10u64, 0x1000, 0x1234, 0x12_3456, 0xfed_cba9].iter() {
+            // Round the length up so that exact bounds are possible
+            let req_length: T::Addr = req_length.as_();
+            let mask: T::Addr = T::get_alignment_mask(req_length.into()).as_();
+            let length: T::Addr = req_length.wrapping_add(&!mask) & mask;
+            let alignment_mask: T::Addr = T::get_alignment_mask(length.into()).as_();
+            let alignment = !alignment_mask + T::Addr::one();
+
+            for &candidate in [0u64, 1, 0x1001, 0x10_0001, 0x3456_789a].iter() {
+                let candidate: T::Addr = candidate.as_();
+                let base = T::next_representable_base(candidate, length.into());
+                assert!(base >= candidate && base - candidate < alignment);
+                assert_eq!(T::next_representable_base(base, length.into()), base);
+
+                let mut cap = T::make_max_perms_cap(T::Addr::zero(), base, crate::max_top::<T>());
+                assert!(T::set_bounds(&mut cap, length.into()), "base {:#x} length {:#x}", base, length);
+                assert_eq!(cap.bounds(), (base, T::compute_top(base, length.into())));
+            }
+        }
+
+        // Any base is fine for zero-length bounds
+        let candidate: T::Addr = 0x1234_5677u64.as_();
+        assert_eq!(T::next_representable_base(candidate, T::Length::zero()), candidate);
+    }
+
+    #[test]
+    fn test_next_representable_base() {
+        crate::for_each_cap_format!(|C| {
+            check_next_representable_base::<C>();
+        });
+    }
+
+    #[test]
+    fn test_rights_delta() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::RightsChange;
+
+        let parent = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+
+        // Legal derivation: narrow the bounds
+        let mut child = parent;
+        child.set_address_unchecked(0x1100);
+        assert!(child.set_bounds_unchecked(0x100));
+        assert_eq!(parent.rights_delta(&child), vec![RightsChange::BoundsNarrowed]);
+        assert_eq!(child.rights_delta(&child), vec![]);
+
+        // Illegal derivation: a tagged capability regaining permissions
+        let mut reduced = parent;
+        reduced.set_permissions(Cc128::PERM_LOAD);
+        assert_eq!(
+            reduced.rights_delta(&parent),
+            vec![
+                RightsChange::MonotonicityViolation,
+                RightsChange::PermissionsGained(0xfff & !Cc128::PERM_LOAD)
+            ]
+        );
+        // Same thing, but the tag was cleared
+        assert_eq!(
+            reduced.rights_delta(&parent.with_tag(false)),
+            vec![RightsChange::PermissionsGained(0xfff & !Cc128::PERM_LOAD)]
+        );
+
+        // Sealing and unsealing
+        let mut sealed = parent;
+        sealed.set_otype(4);
+        assert_eq!(parent.rights_delta(&sealed), vec![RightsChange::Sealed { otype: 4 }]);
+        assert_eq!(sealed.rights_delta(&parent), vec![RightsChange::Unsealed { otype: 4 }]);
+    }
+
+    #[test]
+    fn test_unseal_sentry_for_jump() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let mut code = Cc128::make_max_perms_cap(0x1000, 0x1040, 0x2000);
+        code.set_otype(Cc128::OTYPE_SENTRY);
+        assert!(code.is_sentry() && code.is_invokable_sentry());
+        let pcc = code.unseal_sentry_for_jump().unwrap();
+        assert!(!pcc.is_sealed());
+        assert!(pcc.tag());
+        assert_eq!(pcc.address(), 0x1040);
+        assert_eq!(pcc.bounds(), code.bounds());
+        assert_eq!(pcc.permissions(), code.permissions());
+
+        // Data sentry without execute permission
+        let mut data = code;
+        data.set_permissions(Cc128::PERM_LOAD | Cc128::PERM_STORE);
+        assert!(data.is_sentry() && !data.is_invokable_sentry());
+        assert_eq!(data.unseal_sentry_for_jump(), None);
+
+        // Untagged, or sealed with a normal otype
+        assert_eq!(code.with_tag(false).unseal_sentry_for_jump(), None);
+        let mut sealed = code;
+        sealed.set_otype(4);
+        assert!(!sealed.is_sentry());
+        assert_eq!(sealed.unseal_sentry_for_jump(), None);
+    }
+
+    #[test]
+    fn test_low_bits() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x10_0000, 0x12_3456, 0x20_0000);
+        assert_eq!(cap.extract_bounds_bits().e, 8);
+        assert_eq!(cap.base_low_bits(), 0);
+        assert_eq!(cap.cursor_low_bits(), 0x56);
+
+        // Inexact bounds are rounded out to the exponent, so the base loses its low bits
+        let mut inexact = Cc128::make_max_perms_cap(0, 0x10_0123, 1 << 64);
+        assert!(!inexact.set_bounds_unchecked(0x10_0000));
+        assert_ne!(inexact.extract_bounds_bits().e, 0);
+        assert_eq!(inexact.base_low_bits(), 0);
+        assert!(inexact.base() <= 0x10_0123);
+
+        // Zero exponent: nothing is below it
+        let small = Cc128::make_max_perms_cap(0x1001, 0x1003, 0x1010);
+        assert_eq!(small.extract_bounds_bits().e, 0);
+        assert_eq!((small.base_low_bits(), small.cursor_low_bits()), (0, 0));
+    }
+
+    #[test]
+    fn test_with_guard() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let mut cap = Cc128::make_max_perms_cap(0x10_0000, 0x10_0080, 0x10_1000);
+        cap.set_permissions(Cc128::PERM_LOAD);
+        let guarded = cap.with_guard(0x1000).unwrap();
+        assert_eq!(guarded.bounds(), (0xf_f000, 0x10_2000));
+        assert_eq!(guarded.address(), 0x10_0080);
+        assert_eq!(guarded.permissions(), Cc128::PERM_LOAD);
+        assert!(!guarded.tag());
+
+        // Rounded out to representable bounds
+        let guarded = cap.with_guard(0x1234).unwrap();
+        let (base, top) = guarded.bounds();
+        assert!(base <= 0x10_0000 - 0x1234 && top >= 0x10_1000 + 0x1234);
+        assert!(guarded.is_exact());
+
+        // No guard leaves representable bounds unchanged
+        assert_eq!(cap.with_guard(0).unwrap(), cap);
+
+        // Low end of the address space
+        let low = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert_eq!(low.with_guard(0x1000).unwrap().bounds(), (0, 0x3000));
+        assert_eq!(low.with_guard(0x1001), None);
+
+        // High end of the address space
+        let high = Cc128::make_max_perms_cap(u64::MAX - 0x1fff, u64::MAX - 0x1fff, 1 << 64);
+        assert_eq!(high.with_guard(0x1000), None);
+        let high = Cc128::make_max_perms_cap(u64::MAX - 0x1fff, u64::MAX - 0x1fff, (1 << 64) - 0x1000);
+        assert_eq!(high.with_guard(0x1000).unwrap().bounds(), (u64::MAX - 0x2fff, 1 << 64));
+        assert_eq!(high.with_guard(0x1001), None);
+    }
+
+    #[test]
+    fn test_to_cap_info() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+
+        let mut cap = Cc128::make_max_perms_cap(0, 0x1234, 1 << 64);
+        cap.set_software_permissions(0x5);
+        cap.set_flags(1);
+        let info = cap.to_cap_info();
+        assert_eq!(info.tag, 1);
+        assert_eq!(info.base, cap.base());
+        assert_eq!((info.top_hi, info.top_lo), (1, 0));
+        assert_eq!(info.cursor, cap.address());
+        assert_eq!(info.perms, cap.permissions());
+        assert_eq!(info.uperms, 0x5);
+        assert_eq!(info.otype, cap.otype());
+        assert_eq!(info.flags, 1);
+        assert_eq!(info.reserved, cap.reserved_bits());
+
+        let cap = Cc64::make_max_perms_cap(0x1000, 0x1800, 0x2000).with_tag(false);
+        let info = cap.to_cap_info();
+        assert_eq!(info.tag, 0);
+        assert_eq!((info.base, info.top_hi, info.top_lo, info.cursor), (0x1000, 0, 0x2000, 0x1800));
+        assert_eq!(info.otype, Cc64::OTYPE_UNSEALED);
+    }
+
+    #[test]
+    fn test_length_saturating() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert_eq!(cap.length_saturating(), cap.length());
+
+        // Reversed bounds, as could be decoded from garbage
+        let mut reversed = cap.with_tag(false);
+        reversed.cr_base = 0x3000;
+        assert_eq!(reversed.length_saturating(), 0);
+    }
+
+    #[test]
+    fn test_raw_cache() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(cap.self_consistent());
+        let (top, base, exp) = cap.raw_cache();
+        assert_eq!((top, base), (0x2000, 0x1000));
+
+        let mut corrupted = cap;
+        corrupted.set_raw_cache_unchecked(0x3000, base, exp);
+        assert!(!corrupted.self_consistent());
+        assert_eq!(corrupted.top(), 0x3000);
+        assert_eq!(corrupted.cr_pesbt, cap.cr_pesbt);
+
+        corrupted.set_raw_cache_unchecked(top, base, exp);
+        assert!(corrupted.self_consistent());
+        assert_eq!(corrupted, cap);
+    }
+
+    #[test]
+    fn test_eq_exact() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(cap.eq_exact(&cap));
+        assert!(cap.eq_exact(&Cc128::decompress_raw(cap.cr_pesbt, cap.address(), true)));
+
+        let (top, _, exp) = cap.raw_cache();
+        let mut corrupted = cap;
+        corrupted.set_raw_cache_unchecked(top, 0x1800, exp);
+        assert_eq!(corrupted, cap);
+        assert!(!corrupted.eq_exact(&cap));
+        assert!(!cap.eq_exact(&corrupted));
+
+        // Differences in the canonical fields are still caught
+        assert!(!cap.eq_exact(&cap.with_tag(false)));
+    }
+
+    #[test]
+    fn test_equal_ignoring_subgranule_cursor() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        // Exponent 8, so the bottom 8 bits of the cursor are below the granule
+        let cap = Cc128::make_max_perms_cap(0x10_0000, 0x12_3456, 0x20_0000);
+        let same_granule = Cc128::make_max_perms_cap(0x10_0000, 0x12_34ff, 0x20_0000);
+        let next_granule = Cc128::make_max_perms_cap(0x10_0000, 0x12_3556, 0x20_0000);
+        assert!(cap.equal_ignoring_subgranule_cursor(&same_granule));
+        assert_ne!(cap, same_granule);
+        assert!(!cap.equal_ignoring_subgranule_cursor(&next_granule));
+        assert!(!cap.equal_ignoring_subgranule_cursor(&same_granule.with_tag(false)));
+
+        // Zero exponent: only equal if the cursors are
+        let small = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1010);
+        let small_moved = Cc128::make_max_perms_cap(0x1000, 0x1001, 0x1010);
+        assert!(!small.equal_ignoring_subgranule_cursor(&small_moved));
+    }
+
+    #[test]
+    fn test_valid_cursor_range() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x10_0000, 0x10_0000, 0x20_0000);
+        let (min, max) = (cap.min_valid_cursor(), cap.max_valid_cursor());
+        assert!(min < cap.base() && (max as u128) >= cap.top());
+        assert_eq!((min, max as u128 + 1), cap.representable_limits());
+
+        let pesbt = cap.reg_representation().1[1];
+        let same_bounds = |cursor: u64| Cc128::decompress_raw(pesbt, cursor, false).bounds() == cap.bounds();
+        assert!(same_bounds(min) && same_bounds(max));
+        assert!(!same_bounds(min - 1) && !same_bounds(max + 1));
+
+        // Whole address space
+        let full = Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        assert_eq!((full.min_valid_cursor(), full.max_valid_cursor()), (0, u64::MAX));
+    }
+
+    #[test]
+    fn test_with_offset() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::caps::morello;
+
+        let pcc = Cc128::make_max_perms_cap(0x10_0000, 0x10_0040, 0x10_1000);
+        let derived = pcc.with_offset(0x800);
+        assert_eq!(derived.address(), 0x10_0800);
+        assert!(derived.tag());
+        assert_eq!(derived.bounds(), pcc.bounds());
+        assert_eq!(derived.permissions(), pcc.permissions());
+
+        // Just outside the bounds, but still representable
+        let derived = pcc.with_offset(-0x10);
+        assert_eq!(derived.address(), 0xf_fff0);
+        assert!(derived.tag());
+
+        // Far outside the representable region
+        let derived = pcc.with_offset(0x1000_0000);
+        assert_eq!(derived.address(), 0x1010_0000);
+        assert!(!derived.tag());
+
+        // Sealed capabilities can't be modified
+        let mut sealed = pcc;
+        sealed.set_otype(4);
+        assert!(!sealed.with_offset(0x800).tag());
+
+        // Morello doesn't use the top address byte for the bounds, so an offset reaching it keeps the tag
+        let pcc = morello::Cc128::make_max_perms_cap(0x10_0000, 0x10_0040, 0x10_1000);
+        let derived = pcc.with_offset(0x1200_0000_0000_0800);
+        assert!(derived.tag());
+        assert_eq!(derived.bounds(), pcc.bounds());
+        assert_eq!(derived, pcc.with_address(0x1200_0000_0010_0800).1);
+    }
+
+    #[test]
+    fn test_retain_clear_permissions() {
+        use crate::caps::cheriv9::cc128::{Cc128, Cc128Cap};
+        use crate::caps::morello;
+
+        let mut cap = Cc128Cap::root();
+        assert!(cap.has_permission(Cc128::PERM_STORE));
+        cap.clear_permissions(Cc128::PERM_STORE);
+        assert!(!cap.has_permission(Cc128::PERM_STORE));
+        assert!(cap.has_permission(Cc128::PERM_LOAD));
+        assert!(!cap.has_permission(Cc128::PERM_LOAD | Cc128::PERM_STORE));
+        assert_eq!(cap.permissions(), Cc128Cap::root().permissions() & !Cc128::PERM_STORE);
+
+        cap.retain_permissions(Cc128::PERM_LOAD | Cc128::PERM_STORE | Cc128::PERM_GLOBAL);
+        assert_eq!(cap.permissions(), Cc128::PERM_LOAD | Cc128::PERM_GLOBAL);
+        assert!(cap.tag());
+        // Retaining can't grant anything back
+        cap.retain_permissions(u32::MAX);
+        assert_eq!(cap.permissions(), Cc128::PERM_LOAD | Cc128::PERM_GLOBAL);
+
+        // On Morello GLOBAL is bit 0, just below the user permissions, which CC128M_PERMS_ALL used to mask out
+        let mut cap = morello::Cap::root();
+        cap.clear_permissions(morello::Cc128::PERM_STORE);
+        assert_eq!(cap.permissions(), morello::Cap::root().permissions() & !morello::Cc128::PERM_STORE);
+        assert!(cap.has_permission(morello::Cc128::PERM_GLOBAL));
+        cap.retain_permissions(morello::Cc128::PERM_LOAD | morello::Cc128::PERM_GLOBAL);
+        assert_eq!(cap.permissions(), morello::Cc128::PERM_LOAD | morello::Cc128::PERM_GLOBAL);
+        assert!(cap.tag());
+    }
+
+    fn check_otype_signed<T: CompressedCapability>() {
+        use crate::CcxCap;
+
+        let with_otype = |otype: u32| {
+            let mut cap = CcxCap::<T>::root();
+            cap.set_otype(otype);
+            cap.otype_signed()
+        };
+        assert_eq!(with_otype(T::OTYPE_UNSEALED), -1);
+        assert_eq!(with_otype(T::OTYPE_SENTRY), -2);
+        assert_eq!(with_otype(T::OTYPE_RESERVED2), -3);
+        assert_eq!(with_otype(T::OTYPE_RESERVED3), -4);
+        assert_eq!(with_otype(5), 5);
+
+        for &otype in T::RESERVED_OTYPES {
+            assert!(T::is_reserved_otype(otype));
+        }
+        assert!(!T::is_reserved_otype(5));
+    }
+
+    #[test]
+    fn test_otype_signed() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+        use crate::caps::morello;
+        use crate::CcxCap;
+
+        check_otype_signed::<Cc64>();
+        check_otype_signed::<Cc128>();
+
+        // No signed convention for Morello
+        let mut cap = CcxCap::<morello::Cc128>::root();
+        assert_eq!(cap.otype_signed(), 0);
+        cap.set_otype(morello::Cc128::OTYPE_SENTRY);
+        assert_eq!(cap.otype_signed(), 1);
+        assert!(morello::Cc128::is_reserved_otype(morello::Cc128::OTYPE_RESERVED3));
+    }
+
+    /// Toggling the mode must only change the flags bit of the pesbt
+    fn check_cap_mode<T: CompressedCapability>()
+    where
+        T::Addr: num_traits::AsPrimitive<u64>,
+    {
+        use crate::{CcxCap, Flags};
+        use num_traits::AsPrimitive;
+
+        let cap = CcxCap::<T>::root();
+        let mut toggled = cap;
+        toggled.set_cap_mode(!cap.cap_mode());
+        assert_eq!(toggled.cap_mode(), !cap.cap_mode());
+        let changed: u64 = (cap.cr_pesbt ^ toggled.cr_pesbt).as_();
+        assert_eq!(changed.count_ones(), 1);
+        assert_eq!(toggled.flags() ^ cap.flags(), 1);
+        assert_eq!(toggled.address(), cap.address());
+        assert_eq!(toggled.bounds(), cap.bounds());
+        assert_eq!(toggled.permissions(), cap.permissions());
+
+        toggled.set_cap_mode(cap.cap_mode());
+        assert_eq!(toggled, cap);
+
+        assert!(Flags::<T>::from_cap_mode(true).cap_mode());
+        assert!(!Flags::<T>::from_cap_mode(false).cap_mode());
+    }
+
+    #[test]
+    fn test_cap_mode() {
+        use crate::caps::*;
+        use crate::{CcxCap, Flags};
+
+        check_cap_mode::<cheriv9::cc64::Cc64>();
+        check_cap_mode::<cheriv9::cc128::Cc128>();
+        check_cap_mode::<rvy::cc128::Cc128>();
+
+        // The polarity of the flag differs between CHERIv9 and RISC-V Y
+        assert_eq!(Flags::<cheriv9::cc128::Cc128>::from_cap_mode(true).bits(), 1);
+        assert_eq!(Flags::<rvy::cc128::Cc128>::from_cap_mode(true).bits(), 0);
+
+        // Morello doesn't have a mode bit
+        let mut cap = CcxCap::<morello::Cc128>::root();
+        cap.set_cap_mode(true);
+        assert_eq!(cap, CcxCap::root());
+        assert!(!cap.cap_mode());
+    }
+
+    const _: () = assert!(crate::caps::cheriv9::cc64::Cc64::CAP_SIZE_BYTES == 8);
+    const _: () = assert!(crate::caps::cheriv9::cc128::Cc128::CAP_SIZE_BYTES == 16);
+    const _: () = assert!(crate::caps::cheriv9::cc64::Cc64::ADDR_WIDTH_BITS == 32);
+    const _: () = assert!(crate::caps::cheriv9::cc128::Cc128::ADDR_WIDTH_BITS == 64);
+    const _: () = assert!(crate::caps::cheriv9::cc128::Cc128::CAP_MAX_LENGTH == 1 << 64);
+
+    fn check_size_consts<T: CompressedCapability>() {
+        use num_traits::{Bounded, One};
+
+        assert_eq!(T::CAP_SIZE_BYTES, std::mem::size_of::<T::MemBytes>());
+        assert_eq!(T::CAP_SIZE_BYTES, 2 * std::mem::size_of::<T::Addr>());
+        assert_eq!(T::ADDR_WIDTH_BITS as usize, 8 * std::mem::size_of::<T::Addr>());
+        let max_addr: T::Length = T::Addr::max_value().into();
+        assert_eq!(T::CAP_MAX_LENGTH, max_addr + T::Length::one());
+        assert_eq!(crate::CcxCap::<T>::root().top(), T::CAP_MAX_LENGTH);
+    }
+
+    #[test]
+    fn test_size_consts() {
+        crate::for_each_cap_format!(|C| {
+            check_size_consts::<C>();
+        });
+    }
+
+    #[test]
+    fn test_with_address() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x10_0000, 0x10_0000, 0x10_0100);
+        let (repr_base, repr_top) = cap.representable_limits();
+
+        let (representable, moved) = cap.with_address(0x10_0080);
+        assert!(representable && moved.tag());
+        assert_eq!(moved.address(), 0x10_0080);
+        assert_eq!(moved.bounds(), cap.bounds());
+
+        // Last representable address, outside the bounds
+        let last = (repr_top - 1) as u64;
+        let (representable, moved) = cap.with_address(last);
+        assert!(representable && moved.tag());
+        assert_eq!(moved.bounds(), cap.bounds());
+
+        // Just past the representable limit: the tag is cleared and the bounds re-decoded
+        let (representable, moved) = cap.wrapping_add_offset(last - cap.address() + 1);
+        assert!(!representable && !moved.tag());
+        assert_eq!(moved.address(), repr_top as u64);
+        assert_ne!(moved.bounds(), cap.bounds());
+        assert_eq!(moved, Cc128::decompress_raw(cap.cr_pesbt, repr_top as u64, false));
+
+        // Wrapping below the representable base
+        let (representable, moved) = cap.wrapping_add_offset(repr_base.wrapping_sub(cap.address()).wrapping_sub(1));
+        assert!(!representable && !moved.tag());
+        assert_eq!(moved.address(), repr_base - 1);
+
+        // Sealed capabilities can't be modified
+        let mut sealed = cap;
+        sealed.set_otype(4);
+        let (representable, moved) = sealed.wrapping_add_offset(0x10);
+        assert!(representable && !moved.tag());
+    }
+
+    /// Returns the number of `PERM_*` constants `T` defines as nonzero
+    fn count_perm_consts<T: CompressedCapability>() -> u32 {
+        [
+            T::PERM_GLOBAL,
+            T::PERM_EXECUTE,
+            T::PERM_LOAD,
+            T::PERM_STORE,
+            T::PERM_LOAD_CAP,
+            T::PERM_STORE_CAP,
+            T::PERM_STORE_LOCAL,
+            T::PERM_SEAL,
+            T::PERM_CINVOKE,
+            T::PERM_UNSEAL,
+            T::PERM_ACCESS_SYS_REGS,
+            T::PERM_SETCID,
+        ]
+        .iter()
+        .filter(|&&perm| perm != 0)
+        .count() as u32
+    }
+
+    #[test]
+    fn test_perm_counts() {
+        use crate::caps::{cheriv9, morello, rvy};
+
+        // Every CHERIv9 permission has a constant
+        assert_eq!(cheriv9::cc128::Cc128::arch_perm_count(), count_perm_consts::<cheriv9::cc128::Cc128>());
+        assert_eq!(cheriv9::cc64::Cc64::arch_perm_count(), count_perm_consts::<cheriv9::cc64::Cc64>());
+        // Other formats have permissions the trait doesn't name
+        assert!(morello::Cc128::arch_perm_count() > count_perm_consts::<morello::Cc128>());
+        assert!(rvy::cc128::Cc128::arch_perm_count() > count_perm_consts::<rvy::cc128::Cc128>());
+
+        // The software permission count is the width of the uperms field
+        let cap = cheriv9::cc128::Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        assert_eq!(cap.software_permissions(), (1 << cheriv9::cc128::Cc128::software_perm_count()) - 1);
+        let cap = morello::Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        assert_eq!(cap.software_permissions(), (1 << morello::Cc128::software_perm_count()) - 1);
+        assert_eq!(cheriv9::cc64::Cc64::software_perm_count(), 0);
+        assert_eq!(rvy::cc64::Cc64::software_perm_count(), 2);
+    }
+
+    #[test]
+    fn test_clamp_cursor_to_bounds() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let mut cap = Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000);
+        assert!(!cap.clamp_cursor_to_bounds());
+        assert_eq!(cap.address(), 0x1800);
+
+        // Below the base
+        let mut below = Cc128::make_max_perms_cap(0x1000, 0xff0, 0x2000);
+        assert!(below.clamp_cursor_to_bounds());
+        assert_eq!(below.address(), 0x1000);
+        assert!(below.tag());
+        assert_eq!(below.bounds(), (0x1000, 0x2000));
+
+        // At and above the top
+        let mut at_top = Cc128::make_max_perms_cap(0x1000, 0x2000, 0x2000);
+        assert!(at_top.clamp_cursor_to_bounds());
+        assert_eq!(at_top.address(), 0x1fff);
+        assert!(at_top.tag());
+        cap.set_address_unchecked(0x2010);
+        assert!(cap.clamp_cursor_to_bounds());
+        assert_eq!(cap.address(), 0x1fff);
+
+        // Zero-length bounds
+        let mut empty = Cc128::make_max_perms_cap(0x1000, 0x1010, 0x1000);
+        assert!(empty.clamp_cursor_to_bounds());
+        assert_eq!(empty.address(), 0x1000);
+
+        // Whole address space: everything is in bounds
+        let mut full = Cc128::make_max_perms_cap(0, u64::MAX, 1 << 64);
+        assert!(!full.clamp_cursor_to_bounds());
+    }
+
+    #[test]
+    fn test_mem_words() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc128::Cc128Cap, cc64::Cc64};
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000);
+        let (word0, word1, tag) = cap.to_mem_words();
+        // Same order as mem_representation
+        assert_eq!((tag, [word0, word1]), cap.mem_representation());
+        assert!(tag);
+        assert_eq!(word0, 0x1800);
+        // The in-memory pesbt is XOR-ed, unlike the register one
+        assert_ne!(word1, cap.reg_representation().1[1]);
+        assert_eq!(Cc128Cap::from_mem_words(word0, word1, tag), cap);
+        assert_eq!(Cc128Cap::from_mem_words(word0, word1, false), cap.with_tag(false));
+        assert_eq!(
+            Cc128Cap::from_mem_words(word0, word1, tag),
+            Cc128Cap::from_mem_representation(tag, [word0, word1])
+        );
+
+        // All-zero memory is the null capability
+        let null = Cc128Cap::from_mem_words(0, 0, false);
+        assert_eq!(null, crate::CcxCap::null(0));
+        assert_eq!(null.bounds(), (0, 1 << 64));
+        assert_eq!(null.to_mem_words(), (0, 0, false));
+
+        let cap = Cc64::make_max_perms_cap(0x100, 0x140, 0x200);
+        let (word0, word1, tag) = cap.to_mem_words();
+        assert_eq!(crate::CcxCap::<Cc64>::from_mem_words(word0, word1, tag), cap);
+    }
+
+    #[test]
+    fn test_representable_length_no_truncation() {
+        use crate::caps::{cheriv9, morello, rvy};
+
+        // Bounds covering the whole address space need the full length
+        assert_eq!(cheriv9::cc128::Cc128::get_representable_length(1 << 64), 1 << 64);
+        let mask = cheriv9::cc128::Cc128::get_alignment_mask(1 << 64);
+        assert_eq!(mask >> 64, 0);
+        assert_eq!(cheriv9::cc128::Cc128::get_required_alignment(1 << 64), (!mask & (u64::MAX as u128)) + 1);
+        assert_eq!(rvy::cc128::Cc128::get_representable_length(1 << 64), 1 << 64);
+        assert_eq!(morello::Cc128::get_representable_length(1 << 64), 1 << 64);
+        assert_eq!(cheriv9::cc64::Cc64::get_representable_length(1 << 32), 1 << 32);
+        assert_eq!(rvy::cc64::Cc64::get_representable_length(1 << 32), 1 << 32);
+
+        // Lengths just below, which C can represent, round up rather than wrapping to zero
+        assert_eq!(cheriv9::cc128::Cc128::get_representable_length(u64::MAX as u128), 1 << 64);
+        assert_eq!(cheriv9::cc128::Cc128::get_representable_length((1 << 63) + 1), (1 << 63) + (1 << 54));
+
+        // The mask only covers address bits
+        let mask = cheriv9::cc128::Cc128::get_alignment_mask(0x10_0001);
+        assert_eq!(mask >> 64, 0);
+        assert_eq!(cheriv9::cc128::Cc128::get_required_alignment(0x10_0001), (!mask & (u64::MAX as u128)) + 1);
+    }
+
+    /// Checks [CcxCap::round_up_to_representable] against the C library for lengths around each exponent change
+    fn check_round_up_to_representable<T: CompressedCapability>()
+    where
+        u64: num_traits::AsPrimitive<T::Addr>,
+    {
+        use crate::CcxCap;
+        use num_traits::{AsPrimitive, Zero};
+
+        let cap = CcxCap::<T>::root();
+        let mut rng = crate::testing::Lcg::new(0x1234_5678_9abc_def1);
+        // Lengths either side of every exponent change, including the start of the internal exponent
+        for width in 0..(T::ADDR_WIDTH_BITS - 1) {
+            for &delta in [-1i64, 0, 1].iter() {
+                let len = ((1u64 << width) as i64 + delta) as u64;
+                let base = rng.next_u64() >> (64 - width.max(1));
+                let (base, len): (T::Addr, T::Length) = (base.as_(), AsPrimitive::<T::Addr>::as_(len).into());
+
+                assert_eq!(cap.representable_length(len), T::get_representable_length(len));
+                assert_eq!(cap.required_alignment(len), T::get_required_alignment(len));
+                assert_eq!(cap.alignment_mask(len), T::get_alignment_mask(len));
+
+                let (new_base, new_len) = cap.round_up_to_representable(base, len);
+                let new_top = T::compute_top(new_base, new_len);
+                assert!(new_base <= base && new_top >= T::compute_top(base, len), "len = {:#x}", len);
+                // The C library agrees the result is exactly representable...
+                let mut exact = cap;
+                assert_eq!(exact.set_bounds_exact(new_base, new_top), Ok(()), "len = {:#x}", len);
+                // ...and it's no larger than the bounds set_bounds rounds the request to
+                let mut rounded = CcxCap::<T>::root();
+                rounded.set_address_unchecked(base);
+                T::set_bounds(&mut rounded, len);
+                assert!(new_len <= rounded.length(), "len = {:#x}", len);
+                let base_len: T::Length = base.into();
+                if T::get_representable_length(len) == len && (base_len % T::get_required_alignment(len)).is_zero() {
+                    assert_eq!((new_base, new_len), (base, len));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_up_to_representable() {
+        crate::for_each_cap_format!(|C| {
+            check_round_up_to_representable::<C>();
+        });
+    }
+
+    /// Checks [CompressedCapability::decompress_mem_slice] matches individual [CompressedCapability::decompress_mem] calls
+    fn check_decompress_mem_slice<T: CompressedCapability>()
+    where
+        u64: num_traits::AsPrimitive<T::Addr>,
+    {
+        use num_traits::AsPrimitive;
+
+        let mut rng = crate::testing::Lcg::new(0x0bad_cafe_f00d_d00d);
+        let mut words = vec![];
+        let mut tags = vec![];
+        for i in 0..256u64 {
+            let state = rng.next_u64();
+            if i % 2 == 0 {
+                // Arbitrary data, which must be untagged as it may not be a valid capability
+                words.push(((state >> 7).as_(), state.as_()));
+                tags.push(false);
+            } else {
+                let base: T::Addr = (i << 12).as_();
+                let cursor: T::Addr = ((i << 12) + (state & 0xfff)).as_();
+                let cap = T::make_max_perms_cap(base, cursor, T::compute_top(base, 0x1000u64.as_().into()));
+                words.push((T::compress_mem(&cap), cap.address()));
+                tags.push(i % 4 == 1);
+            }
+        }
+
+        let caps = T::decompress_mem_slice(&words, &tags);
+        assert_eq!(caps.len(), words.len());
+        for ((&(pesbt, cursor), &tag), cap) in words.iter().zip(&tags).zip(&caps) {
+            assert!(cap.eq_exact(&T::decompress_mem(pesbt, cursor, tag)));
+        }
+        assert!(T::decompress_mem_slice(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_decompress_mem_slice() {
+        crate::for_each_cap_format!(|C| {
+            check_decompress_mem_slice::<C>();
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_decompress_mem_slice_mismatched_tags() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        Cc128::decompress_mem_slice(&[(0, 0), (0, 0)], &[false]);
+    }
+
+    #[test]
+    fn test_representability() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::{CcxCap, Representability};
+
+        let cap = Cc128::make_max_perms_cap(0x10_0000, 0x10_0000, 0x20_0000);
+        assert_eq!(cap.representability(), Representability::Exact);
+        assert!(cap.representability().is_exact());
+        assert_eq!(CcxCap::<Cc128>::root().representability(), Representability::Exact);
+
+        // A large length which isn't aligned, as if the bounds had been set without rounding
+        let (base, len) = (0x10_0000, 0x10_0001);
+        let alignment = Cc128::get_required_alignment(len);
+        assert!(alignment > 1);
+        let mut misaligned = cap;
+        misaligned.set_raw_cache_unchecked(base as u128 + len, base, cap.exponent());
+        let expected = Representability::Inexact {
+            required_alignment: alignment,
+            representable_base: base,
+            representable_top: (base as u128 + len).div_ceil(alignment) * alignment,
+        };
+        assert_eq!(misaligned.representability(), expected);
+        assert_eq!(cap.representability_of(base, len), expected);
+        assert!(!expected.is_exact());
+
+        // Misaligned base with a representable length
+        let representability = cap.representability_of(0x10_0001, 0x10_0000);
+        match representability {
+            Representability::Inexact {
+                required_alignment,
+                representable_base,
+                representable_top,
+            } => {
+                assert_eq!(required_alignment, Cc128::get_required_alignment(0x10_0000));
+                assert!(representable_base < 0x10_0001 && representable_top > 0x20_0001);
+                let mut exact = cap;
+                assert!(exact.set_bounds_exact(representable_base, representable_top).is_ok());
+            }
+            _ => panic!("{:?}", representability),
+        }
+
+        // Bounds decoded from arbitrary bits can be reversed, or have a top above the address space
+        let reversed = crate::caps::cheriv9::cc64::Cc64::decompress_raw(0x6c35ce26, 0xf23f97fd, false);
+        assert_eq!(reversed.representability(), Representability::Malformed);
+        assert!(!reversed.is_exact());
+        let above_max_top = Cc128::decompress_raw(0xffff1ffffffe7ffb, 0, false);
+        assert!(above_max_top.top() > 1 << 64);
+        assert_eq!(above_max_top.representability(), Representability::Malformed);
+    }
+
+    /// Checks [crate::align_region] gives exactly-representable regions covering the request
+    fn check_align_region<T: CompressedCapability>()
+    where
+        u64: num_traits::AsPrimitive<T::Addr>,
+    {
+        use num_traits::AsPrimitive;
+
+        let lengths = [0u64, 1, 0xff, 0x1000, 0x1001, 0x12_3456, 0xfed_cba9, 0x1234_5678];
+        let bases = [0u64, 0x10, 0x1001, 0x3456_789a];
+        for &len in lengths.iter() {
+            for &base in bases.iter() {
+                let (base, len): (T::Addr, T::Addr) = (base.as_(), len.as_());
+                let (new_base, new_len) = crate::align_region::<T>(base, len.into());
+                let new_top = T::compute_top(new_base, new_len);
+                assert!(new_base <= base && new_top >= T::compute_top(base, len.into()), "{:?} {:?}", base, len);
+
+                let mut cap = crate::CcxCap::<T>::root();
+                assert_eq!(cap.set_bounds_exact(new_base, new_top), Ok(()), "{:?} {:?}", base, len);
+
+                // An aligned base only needs the length rounding up
+                let aligned = T::next_representable_base(base, T::get_representable_length(len.into()));
+                if aligned >= base {
+                    assert_eq!(
+                        crate::align_region::<T>(aligned, len.into()),
+                        (aligned, T::get_representable_length(len.into()))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_align_region() {
+        crate::for_each_cap_format!(|C| {
+            check_align_region::<C>();
+        });
+    }
+
+    #[test]
+    fn test_otype_kind() {
+        use crate::caps::{cheriv9, morello};
+        use crate::OType;
+
+        // CC64 has no otypes between the unreserved range and the reserved ones
+        type Cc64 = cheriv9::cc64::Cc64;
+        let cc64_cases = [
+            (0, Some(OType::Sealed(0))),
+            (11, Some(OType::Sealed(11))),
+            (12, Some(OType::Reserved(12))),
+            (13, Some(OType::Reserved(13))),
+            (14, Some(OType::Sentry)),
+            (15, Some(OType::Unsealed)),
+            (16, None),
+        ];
+        for &(raw, expected) in cc64_cases.iter() {
+            assert_eq!(OType::from_raw::<Cc64>(raw), expected, "{}", raw);
+            if let Some(otype) = expected {
+                assert_eq!(otype.to_raw::<Cc64>(), raw);
+            }
+        }
+
+        type Cc128 = cheriv9::cc128::Cc128;
+        let cc128_cases = [
+            (0, Some(OType::Sealed(0))),
+            (0x3_fffb, Some(OType::Sealed(0x3_fffb))),
+            (0x3_fffc, Some(OType::Reserved(0x3_fffc))),
+            (0x3_fffd, Some(OType::Reserved(0x3_fffd))),
+            (0x3_fffe, Some(OType::Sentry)),
+            (0x3_ffff, Some(OType::Unsealed)),
+            (0x4_0000, None),
+        ];
+        for &(raw, expected) in cc128_cases.iter() {
+            assert_eq!(OType::from_raw::<Cc128>(raw), expected, "{:#x}", raw);
+            if let Some(otype) = expected {
+                assert_eq!(otype.to_raw::<Cc128>(), raw);
+            }
+        }
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert_eq!(cap.otype_kind(), OType::Unsealed);
+        assert_eq!(cap.seal_checked(10).unwrap().otype_kind(), OType::Sealed(10));
+        assert_eq!(cap.seal_checked(Cc128::OTYPE_SENTRY).unwrap().otype_kind(), OType::Sentry);
+
+        // Morello puts the special otypes at the bottom
+        assert_eq!(OType::from_raw::<morello::Cc128>(0), Some(OType::Unsealed));
+        assert_eq!(OType::from_raw::<morello::Cc128>(2), Some(OType::Reserved(2)));
+        assert_eq!(OType::from_raw::<morello::Cc128>(4), Some(OType::Sealed(4)));
+        assert_eq!(OType::Unsealed.to_raw::<morello::Cc128>(), 0);
+    }
+
+    #[test]
+    fn test_hash_consistent_with_eq() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use std::collections::HashSet;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let (tag, [cursor, pesbt]) = cap.reg_representation();
+
+        let mut set = HashSet::new();
+        assert!(set.insert(cap));
+        // Decoding again gives an equal capability, which must collide
+        assert!(!set.insert(Cc128::decompress_raw(pesbt, cursor, tag)));
+        assert!(set.insert(Cc128::decompress_raw(pesbt, cursor + 0x10, tag)));
+        assert!(set.insert(cap.with_tag(false)));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&Cc128::decompress_raw(pesbt, cursor, tag)));
+    }
+
+    #[test]
+    fn test_ord() {
+        use crate::caps::cheriv9::cc128::{Cc128, Cc128Cap};
+        use std::cmp::Ordering;
+
+        let low = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let low_wide = Cc128::make_max_perms_cap(0x0, 0x1000, 0x4000);
+        let low_short = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800);
+        let low_untagged = low.with_tag(false);
+        let mut low_fewer_perms = low;
+        low_fewer_perms.set_permissions(Cc128::PERM_LOAD);
+        let high = Cc128::make_max_perms_cap(0x0, 0x1800, 0x4000);
+
+        let mut caps: Vec<Cc128Cap> = vec![high, low, low_fewer_perms, low_untagged, low_short, low_wide];
+        caps.sort();
+        assert_eq!(caps, vec![low_wide, low_short, low_untagged, low_fewer_perms, low, high]);
+
+        // Caps which differ only in fields other than the address/bounds/tag are ordered, not Equal
+        assert_ne!(low.cmp(&low_fewer_perms), Ordering::Equal);
+        let (tag, [cursor, pesbt]) = low.reg_representation();
+        assert_eq!(low.cmp(&Cc128::decompress_raw(pesbt, cursor, tag)), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_mem_bytes() {
+        use crate::caps::cheriv9;
+        use crate::Endian;
+
+        // Null capabilities are all-zero in memory
+        let null = cheriv9::cc128::Cc128::decompress_mem(0, 0, false);
+        assert_eq!(null.to_mem_bytes(Endian::Little), ([0; 16], false));
+        assert_eq!(null.to_mem_bytes(Endian::Big), ([0; 16], false));
+
+        // Stored the same way as the C library's compress_mem output, cursor first for little-endian
+        let cap = cheriv9::cc128::Cc128::make_max_perms_cap(0, 0x1122_3344_5566_7788, 1 << 64);
+        let (tag, [cursor, pesbt]) = cap.mem_representation();
+        let (le_bytes, le_tag) = cap.to_mem_bytes(Endian::Little);
+        assert_eq!(le_tag, tag);
+        assert_eq!(le_bytes[..8], cursor.to_le_bytes());
+        assert_eq!(le_bytes[8..], pesbt.to_le_bytes());
+        assert_eq!(
+            le_bytes,
+            [0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0, 0, 0, 0, 0, 0, 0xff, 0xff]
+        );
+        let (be_bytes, _) = cap.to_mem_bytes(Endian::Big);
+        assert_eq!(be_bytes[..8], pesbt.to_be_bytes());
+        assert_eq!(be_bytes[8..], [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]);
+
+        fn check_roundtrip<T: CompressedCapability>(cap: crate::CcxCap<T>) {
+            for &endian in [Endian::Little, Endian::Big].iter() {
+                let (bytes, tag) = cap.to_mem_bytes(endian);
+                assert_eq!(bytes.as_ref().len(), 2 * std::mem::size_of::<T::Addr>());
+                assert_eq!(crate::CcxCap::<T>::from_mem_bytes(&bytes, tag, endian), cap);
+            }
+        }
+        check_roundtrip(cap);
+        check_roundtrip(cap.with_tag(false));
+        crate::for_each_cap_format!(|C| {
+            check_roundtrip(C::make_max_perms_cap(0x1000, 0x1800, 0x2000));
+        });
+    }
+
+    #[test]
+    fn test_hex_format() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+
+        let cap = Cc128::decompress_raw(0x1f_0000_abcd_ef00, 0x1234, true);
+        assert_eq!(format!("{:x}", cap), "001f0000abcdef000000000000001234");
+        assert_eq!(format!("{:#x}", cap), "0x001f0000abcdef000000000000001234");
+        assert_eq!(format!("{:X}", cap), "001F0000ABCDEF000000000000001234");
+        assert_eq!(format!("{:#X}", cap), "0x001F0000ABCDEF000000000000001234");
+        // The tag isn't included
+        assert_eq!(format!("{:x}", cap.with_tag(false)), format!("{:x}", cap));
+
+        let cap = Cc64::decompress_raw(0xabc, 0x8000_0000, false);
+        assert_eq!(format!("{:x}", cap), "00000abc80000000");
+        let (_, [cursor, pesbt]) = cap.reg_representation();
+        assert_eq!(format!("{:x}", cap), format!("{:08x}{:08x}", pesbt, cursor));
+    }
+
+    #[test]
+    fn test_display() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+
+        let cap = Cc128::make_max_perms_cap(0x4000_0000, 0x4000_0000, 0x4000_1000);
+        assert_eq!(
+            format!("{}", cap),
+            "v:1 s:0 p:00000fff b:0000000040000000 l:0000000000001000 o:0003ffff"
+        );
+        assert_eq!(
+            format!("{}", cap.seal_checked(0x42).unwrap().with_tag(false)),
+            "v:0 s:1 p:00000fff b:0000000040000000 l:0000000000001000 o:00000042"
+        );
+
+        let full = Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        assert_eq!(
+            format!("{}", full),
+            "v:1 s:0 p:00000fff b:0000000000000000 l:10000000000000000 o:0003ffff"
+        );
+
+        // Untagged data uses the bounds decoded from the bits
+        let null = Cc128::decompress_mem(0, 0x1234, false);
+        assert_eq!(
+            format!("{}", null),
+            "v:0 s:0 p:00000000 b:0000000000000000 l:10000000000000000 o:0003ffff"
+        );
+
+        let cap64 = Cc64::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert_eq!(format!("{}", cap64), "v:1 s:0 p:00000fff b:00001000 l:00001000 o:0000000f");
+
+        // Arbitrary bits can decode to reversed bounds, which mustn't overflow
+        let reversed = Cc64::decompress_raw(0x6c35ce26, 0xf23f97fd, false);
+        assert_eq!(reversed.bounds(), (0x8000_0000, 0x2000_0000));
+        assert_eq!(format!("{}", reversed), "v:0 s:1 p:000006c3 b:80000000 l:00000000 o:0000000b");
+        assert!(format!("{:#}", reversed).starts_with("b:0x80000000 t:0x20000000 "));
+
+        // Alternate format shows the representable region
+        assert_eq!(format!("{:#}", cap64), format!("{}", cap64.detailed()));
+    }
+
+    #[test]
+    fn test_top64_length64() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+
+        let full = Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        assert_eq!(full.top(), 1 << 64);
+        assert_eq!(full.top64(), u64::MAX);
+        assert_eq!(full.length64(), u64::MAX);
+
+        let small = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1100);
+        assert_eq!(small.top64(), 0x1100);
+        assert_eq!(small.length64(), 0x100);
+
+        // 64-bit formats have a 33-bit top, which always fits
+        let full64 = Cc64::make_max_perms_cap(0, 0, 1 << 32);
+        assert_eq!(full64.top64(), 1 << 32);
+        assert_eq!(full64.length64(), 1 << 32);
+    }
+
+    #[test]
+    fn test_checked_offset_length() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let mut cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert_eq!(cap.checked_offset(), Some(0));
+        assert_eq!(cap.checked_length(), Some(0x1000));
+
+        // A cursor below base gives a negative offset, which fits in the wider signed Offset
+        cap.set_address_unchecked(0x800);
+        assert_eq!(cap.checked_offset(), Some(-0x800));
+        assert_eq!(cap.offset(), -0x800);
+        cap.set_address_unchecked(0);
+        cap.cr_base = u64::MAX;
+        assert_eq!(cap.checked_offset(), Some(-(u64::MAX as i128)));
+
+        // Reversed bounds, as decoded from arbitrary untagged bits
+        cap.cr_base = 0x3000;
+        assert_eq!(cap.checked_length(), None);
+        assert_eq!(cap.length_saturating(), 0);
+    }
+
+    #[test]
+    fn test_set_bounds_exact() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::SetBoundsError;
+
+        let root = Cc128::make_max_perms_cap(0, 0, 1 << 64);
+
+        let mut exact = root;
+        assert_eq!(exact.set_bounds_exact(0x1000, 0x1100), Ok(()));
+        assert_eq!((exact.bounds(), exact.address()), ((0x1000, 0x1100), 0x1000));
+        assert!(exact.tag());
+
+        let mut rounded = root;
+        let result = rounded.set_bounds_exact(0x10_0123, 0x20_0123);
+        let (base, top) = rounded.bounds();
+        assert_eq!(result, Err(SetBoundsError::Rounded { actual_base: base, actual_top: top }));
+        assert!(base < 0x10_0123 && top > 0x20_0123);
+        assert!(rounded.tag());
+
+        let mut untagged = root.with_tag(false);
+        assert_eq!(untagged.set_bounds_exact(0x1000, 0x1100), Err(SetBoundsError::RequiresTag));
+        assert_eq!(untagged, root.with_tag(false));
+
+        let mut inverted = root;
+        assert_eq!(inverted.set_bounds_exact(0x1100, 0x1000), Err(SetBoundsError::InvalidRequest));
+        assert_eq!(inverted.set_bounds_exact(0, (1 << 64) + 1), Err(SetBoundsError::InvalidRequest));
+        assert_eq!(inverted, root);
+    }
+
+    #[test]
+    fn test_null() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+        use crate::CcxCap;
+
+        let null = CcxCap::<Cc128>::null(0x1234);
+        assert_eq!(null.address(), 0x1234);
+        assert!(!null.tag());
+        assert!(null.is_null());
+        assert_eq!(null.permissions(), 0);
+        assert_eq!(null.otype(), Cc128::OTYPE_UNSEALED);
+        assert_eq!(null.mem_representation(), (false, [0x1234, 0]));
+
+        let null64 = CcxCap::<Cc64>::null(0x1234);
+        assert_eq!(null64.address(), 0x1234);
+        assert!(null64.is_null());
+
+        // A tagged capability with empty bounds isn't null
+        let empty = Cc128::make_max_perms_cap(0x1234, 0x1234, 0x1234);
+        assert_eq!(empty.length(), 0);
+        assert!(!empty.is_null());
+    }
+
+    fn check_root<T: CompressedCapability>() {
+        use crate::CcxCap;
+        use num_traits::Zero;
+
+        let name = std::any::type_name::<T>();
+        let root = CcxCap::<T>::root();
+        assert!(root.tag(), "{}", name);
+        assert!(!root.is_sealed(), "{}", name);
+        assert_eq!(root.address(), T::Addr::zero());
+        assert_eq!(root.base(), T::Addr::zero());
+        assert_eq!(root.top(), crate::max_top::<T>());
+        assert_eq!(root.length(), crate::max_top::<T>());
+        assert_eq!(root, T::make_max_perms_cap(T::Addr::zero(), T::Addr::zero(), crate::max_top::<T>()));
+
+        let all_perms = T::PERM_GLOBAL
+            | T::PERM_EXECUTE
+            | T::PERM_LOAD
+            | T::PERM_STORE
+            | T::PERM_LOAD_CAP
+            | T::PERM_STORE_CAP
+            | T::PERM_STORE_LOCAL
+            | T::PERM_SEAL
+            | T::PERM_CINVOKE
+            | T::PERM_UNSEAL
+            | T::PERM_ACCESS_SYS_REGS
+            | T::PERM_SETCID;
+        assert_eq!(root.permissions() & all_perms, all_perms, "{}", name);
+    }
+
+    #[test]
+    fn test_root() {
+        use crate::caps::*;
+        use crate::CcxCap;
+
+        crate::for_each_cap_format!(|C| {
+            check_root::<C>();
+        });
+
+        assert_eq!(CcxCap::<cheriv9::cc128::Cc128>::root().length(), 1 << 64);
+        assert_eq!(CcxCap::<cheriv9::cc64::Cc64>::root().length(), 1 << 32);
+    }
+
+    fn check_representation_roundtrip<T: CompressedCapability>(cap: crate::CcxCap<T>) {
+        use crate::CcxCap;
+
+        let (tag, data) = cap.reg_representation();
+        assert_eq!(CcxCap::<T>::from_reg_representation(tag, data), cap);
+        let (tag, data) = cap.mem_representation();
+        assert_eq!(CcxCap::<T>::from_mem_representation(tag, data), cap);
+
+        // The two representations differ, so mixing them up doesn't round-trip
+        assert_ne!(cap.reg_representation(), cap.mem_representation());
+    }
+
+    #[test]
+    fn test_from_representation() {
+        use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+
+        check_representation_roundtrip(Cc128::make_max_perms_cap(0x1000, 0x1234, 0x2000));
+        check_representation_roundtrip(Cc128::make_max_perms_cap(0, 0, 1 << 64).with_tag(false));
+        check_representation_roundtrip(Cc64::make_max_perms_cap(0x1000, 0x1234, 0x2000));
+        check_representation_roundtrip(Cc64::make_max_perms_cap(0, 0, 1 << 32).with_tag(false));
+    }
+
+    #[test]
+    fn test_exponent_and_bounds_valid() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::caps::morello::CcMorello;
+
+        let small = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1100);
+        assert_eq!(small.exponent(), 0);
+        assert!(small.bounds_valid());
+
+        let large = Cc128::make_max_perms_cap(0, 0, 1 << 40);
+        assert_ne!(large.exponent(), 0);
+        assert_eq!(large.exponent(), large.extract_bounds_bits().e);
+        assert!(large.bounds_valid());
+
+        let bad_pesbt = CcMorello::encode_bounds_bits(crate::CcxBoundsBits {
+            b: 0,
+            t: 0,
+            e: 51,
+            ie: true,
+        });
+        assert!(!CcMorello::decompress_raw(bad_pesbt, 0, false).bounds_valid());
+    }
+
+    #[test]
+    fn test_is_in_bounds() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(cap.is_in_bounds(0x1000, 0x1000));
+        assert!(cap.is_in_bounds(0x1ff8, 8));
+        // Straddling the top
+        assert!(!cap.is_in_bounds(0x1ffc, 8));
+        // Starting exactly at the top: only a zero-sized access is in bounds
+        assert!(cap.is_in_bounds(0x2000, 0));
+        assert!(!cap.is_in_bounds(0x2000, 1));
+        // Below the base
+        assert!(!cap.is_in_bounds(0xfff, 1));
+
+        let root = Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        assert!(root.is_in_bounds(u64::MAX, 1));
+        assert!(!root.is_in_bounds(u64::MAX, 2));
+        assert!(!root.is_in_bounds(1, u128::MAX));
+    }
+
+    #[test]
+    fn test_cursor_in_bounds() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let mut cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(cap.cursor_in_bounds());
+        cap.set_address_unchecked(0x1800);
+        assert!(cap.cursor_in_bounds());
+        cap.set_address_unchecked(0x1fff);
+        assert!(cap.cursor_in_bounds());
+        // One past the end, as left by a loop over the whole region
+        cap.set_address_unchecked(0x2000);
+        assert!(!cap.cursor_in_bounds());
+        cap.set_address_unchecked(0xfff);
+        assert!(!cap.cursor_in_bounds());
+
+        // The last address of the root doesn't overflow
+        let root = Cc128::make_max_perms_cap(0, u64::MAX, 1 << 64);
+        assert!(root.cursor_in_bounds());
+        // Zero-length capabilities have no cursor in bounds
+        assert!(!Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1000).cursor_in_bounds());
+    }
+
+    #[test]
+    fn test_diff() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::{diff, FieldDiff};
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(diff(&cap, &cap).is_empty());
+
+        let mut sealed = cap;
+        sealed.set_otype(0x1234);
+        assert_eq!(
+            diff(&cap, &sealed),
+            vec![FieldDiff {
+                field: "otype",
+                a: Cc128::OTYPE_UNSEALED.into(),
+                b: 0x1234
+            }]
+        );
+        assert_eq!(diff(&cap, &sealed)[0].to_string(), "otype: 0x3ffff != 0x1234");
+
+        // Several fields, in order, with a top that only fits in 128 bits
+        let root = Cc128::make_max_perms_cap(0, 0x1000, 1 << 64);
+        let fields: Vec<_> = diff(&cap.with_tag(false), &root).iter().map(|d| d.field).collect();
+        assert_eq!(fields, ["tag", "base", "top"]);
+        assert_eq!(diff(&cap, &root)[1].b, 1 << 64);
+
+        // The same bounds with a different encoding only differ in the pesbt.
+        // Bit 13 is in the bottom field, below the bits used at the root's exponent.
+        let root_pesbt = Cc128::compress_raw(&root);
+        let a = Cc128::decompress_raw(root_pesbt, 0, false);
+        let b = Cc128::decompress_raw(root_pesbt ^ (1 << 13), 0, false);
+        assert_eq!(
+            diff(&a, &b),
+            vec![FieldDiff {
+                field: "pesbt",
+                a: root_pesbt.into(),
+                b: (root_pesbt ^ (1 << 13)).into()
+            }]
+        );
+        // Even alongside a cursor change
+        let mut moved = b;
+        moved.set_address_unchecked(8);
+        let fields: Vec<_> = diff(&a, &moved).iter().map(|d| d.field).collect();
+        assert_eq!(fields, ["cursor", "pesbt"]);
+    }
+
+    #[test]
+    fn test_is_subset_of() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        let parent = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert!(parent.is_subset_of(&parent));
+
+        let mut narrowed = parent;
+        assert!(narrowed.set_bounds_unchecked(0x100));
+        assert!(narrowed.is_subset_of(&parent));
+        assert!(!parent.is_subset_of(&narrowed));
+
+        let mut restricted = narrowed;
+        restricted.set_permissions(restricted.permissions() & !Cc128::PERM_STORE);
+        restricted.set_software_permissions(0);
+        assert!(restricted.is_subset_of(&narrowed));
+        assert!(restricted.is_subset_of(&parent));
+        assert!(!narrowed.is_subset_of(&restricted));
+
+        // Tags can't be gained
+        assert!(parent.with_tag(false).is_subset_of(&parent));
+        assert!(!narrowed.is_subset_of(&parent.with_tag(false)));
+
+        let mut shifted = parent;
+        shifted.set_address_unchecked(0x1f00);
+        assert!(shifted.set_bounds_unchecked(0x200));
+        assert!(!shifted.is_subset_of(&parent));
+    }
+
+    #[test]
+    fn test_cc64_cc128_conversion() {
+        use crate::caps::cheriv9::{cc128, cc64};
+        use crate::ConversionError;
+
+        let mut cap = cc64::Cc64::make_max_perms_cap(0x1000, 0x1234, 0x2000);
+        cap.set_permissions(cc64::Cc64::PERM_LOAD | cc64::Cc64::PERM_STORE);
+        let sealed = cap.seal_checked(3).unwrap();
+        for &c in [cap, sealed, cap.with_tag(false), cc64::Cc64::make_max_perms_cap(0, 0, 1 << 32)].iter() {
+            let wide = cc128::Cap::from_cc64(&c);
+            assert_eq!(wide.tag(), c.tag());
+            assert_eq!(wide.address(), c.address() as u64);
+            assert_eq!(wide.bounds(), (c.base() as u64, c.top() as u128));
+            assert_eq!(wide.permissions(), c.permissions());
+            assert_eq!(wide.otype_kind(), c.otype_kind());
+            assert_eq!(cc64::Cap::try_from_cc128(&wide), Ok(c));
+        }
+
+        let sentry = cc64::Cap::try_from_cc128(&cc128::Cap::from_cc64(&cap).seal_checked(cc128::Cc128::OTYPE_SENTRY).unwrap());
+        assert!(sentry.unwrap().is_sentry());
+
+        let high = cc128::Cc128::make_max_perms_cap(1 << 32, 1 << 32, (1 << 32) + 0x100);
+        assert_eq!(cc64::Cap::try_from_cc128(&high), Err(ConversionError::AddressOutOfRange));
+        let imprecise = cc128::Cc128::make_max_perms_cap(0x1001, 0x1001, 0x1102);
+        assert_eq!(cc64::Cap::try_from_cc128(&imprecise), Err(ConversionError::BoundsNotRepresentable));
+        let sealed_high = cc128::Cap::from_cc64(&cap).seal_checked(0x100).unwrap();
+        assert_eq!(cc64::Cap::try_from_cc128(&sealed_high), Err(ConversionError::OTypeNotRepresentable));
+    }
+
+    #[test]
+    fn test_cc64_to_cc128_always_succeeds() {
+        use crate::caps::cheriv9::{cc128, cc64};
+
+        let mut rng = crate::testing::Lcg::new(0x2545_f491_4f6c_dd1d);
+        for _ in 0..10_000 {
+            let state = rng.next_u64();
+            let c = cc64::Cc64::decompress_raw((state >> 32) as u32, state as u32, false);
+            let wide = cc128::Cap::from_cc64(&c);
+            assert_eq!(wide.address(), c.address() as u64);
+            assert_eq!((wide.is_sealed(), wide.is_sentry()), (c.is_sealed(), c.is_sentry()));
+            assert_eq!(wide.permissions(), c.permissions());
+            if c.is_well_formed() {
+                // Only loses the tag if the cursor isn't representable, otherwise the bounds are the same
+                let wide = cc128::Cap::from_cc64(&c.with_tag(true));
+                if wide.tag() {
+                    assert_eq!(wide.bounds(), (c.base() as u64, c.top() as u128));
+                }
+            }
+        }
+
+        // The 64-bit representable region wraps around, so the cursor can be far from the bounds
+        let mut far = cc64::Cc64::make_max_perms_cap(0x8000_0000, 0x8000_0000, 0xc000_0000);
+        far.set_address_unchecked(0x100);
+        assert!(far.is_representable_with_new_addr(0x100));
+        let wide = cc128::Cap::from_cc64(&far);
+        assert_eq!(wide.address(), 0x100);
+        assert!(!wide.tag());
+    }
+
+    #[test]
+    fn test_bounds_bits_accessors() {
+        use crate::caps::cheriv9::cc128::Cc128;
+
+        // Small enough to be stored without an internal exponent
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800);
+        let bits = Cc128::extract_bounds_bits(cap.cr_pesbt);
+        assert_eq!(bits.bottom_bits(), 0x1000);
+        assert_eq!(bits.top_bits(), 0x1800);
+        assert_eq!(bits.exponent(), 0);
+        assert!(!bits.internal_exponent());
+
+        // The whole address space needs the maximum exponent, which zeroes the low bits of B and T
+        let cap = Cc128::make_max_perms_cap(0, 0, 1 << 64);
+        let bits = Cc128::extract_bounds_bits(cap.cr_pesbt);
+        assert_eq!(bits.bottom_bits(), 0);
+        assert_eq!(bits.top_bits(), 0x1000);
+        assert_eq!(bits.exponent(), 52);
+        assert!(bits.internal_exponent());
+    }
+
+    #[test]
+    fn test_derive_subcap() {
+        use crate::caps::cheriv9::cc128::Cc128;
+        use crate::DeriveError;
+
+        let parent = Cc128::make_max_perms_cap(0x1_0000, 0x1_0000, 0x2_0000);
+        let child = parent.derive_subcap(0x1_1000, 0x1_2000, 0x1_1800).unwrap();
+        assert!(child.tag());
+        assert_eq!(child.bounds(), (0x1_1000, 0x1_2000));
+        assert_eq!(child.address(), 0x1_1800);
+        assert_eq!(child.permissions(), parent.permissions());
+        assert!(child.is_subset_of(&parent));
+
+        assert_eq!(parent.derive_subcap(0xf000, 0x1_2000, 0x1_1000), Err(DeriveError::NotMonotone));
+        assert_eq!(parent.derive_subcap(0x1_1000, 0x2_0001, 0x1_1000), Err(DeriveError::NotMonotone));
+        assert_eq!(parent.derive_subcap(0x1_2000, 0x1_1000, 0x1_1000), Err(DeriveError::NotMonotone));
+        // Too long to be exact with these low bits
+        assert_eq!(parent.derive_subcap(0x1_0001, 0x1_8000, 0x1_0001), Err(DeriveError::BoundsRounded));
+        // Small bounds have a small representable region
+        assert_eq!(parent.derive_subcap(0x1_1000, 0x1_1010, 0x1_f000), Err(DeriveError::NotRepresentable));
+        assert_eq!(parent.with_tag(false).derive_subcap(0x1_1000, 0x1_2000, 0x1_1000), Err(DeriveError::InvalidParent));
+    }
+
+    #[test]
+    fn test_compress_roundtrip_all_formats() {
+        crate::for_each_cap_format!(|C| {
+            let cap = C::make_max_perms_cap(0x1000, 0x1800, 0x2000);
+            let pesbt = C::compress_raw(&cap);
+            assert_eq!(C::decompress_raw(pesbt, cap.address(), cap.tag()), cap);
+            let pesbt = C::compress_mem(&cap);
+            assert_eq!(C::decompress_mem(pesbt, cap.address(), cap.tag()), cap);
+        });
     }
 }
@@ -0,0 +1,184 @@
+//! Helpers for finding capabilities in snapshots of memory, e.g. in emulators and core dump tools.
+
+use crate::{CcxCap, CompressedCapability, Endian};
+use num_traits::{NumCast, WrappingAdd, Zero};
+use std::slice::ChunksExact;
+
+/// One tag bit per capability-sized granule of memory, e.g. the tag memory of an emulator.
+///
+/// Bit `i` is the tag for the `i`-th granule, stored least significant bit first:
+/// bit `i % 8` of byte `i / 8`.
+/// Granules past the end of the bitmap are untagged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagBitmap {
+    bits: Vec<u8>,
+}
+impl TagBitmap {
+    /// Creates a bitmap covering `granules` granules, all untagged
+    pub fn new(granules: usize) -> Self {
+        TagBitmap {
+            bits: vec![0; granules.div_ceil(8)],
+        }
+    }
+    /// Wraps existing tag bits, in the layout described on [TagBitmap]
+    pub fn from_bytes(bits: Vec<u8>) -> Self {
+        TagBitmap { bits }
+    }
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    /// Returns the tag of granule `index`, which is false if the index is past the end of the bitmap
+    pub fn get(&self, index: usize) -> bool {
+        match self.bits.get(index / 8) {
+            Some(byte) => (byte >> (index % 8)) & 1 != 0,
+            None => false,
+        }
+    }
+    /// Sets the tag of granule `index`, growing the bitmap if necessary
+    pub fn set(&mut self, index: usize, tag: bool) {
+        if index / 8 >= self.bits.len() {
+            if !tag {
+                return;
+            }
+            self.bits.resize(index / 8 + 1, 0);
+        }
+        let mask = 1 << (index % 8);
+        if tag {
+            self.bits[index / 8] |= mask;
+        } else {
+            self.bits[index / 8] &= !mask;
+        }
+    }
+}
+
+/// Iterator over the tagged capabilities in a region of memory, returned by [CompressedCapability::scan_memory].
+///
+/// Yields `(address, capability)` for each whole granule of the region whose tag is set,
+/// in increasing address order.
+/// A trailing partial granule (if the region isn't a multiple of [CompressedCapability::CAP_SIZE_BYTES] long) is ignored.
+///
+/// Tagged granules which don't hold a [well-formed](CcxCap::is_well_formed) capability are skipped,
+/// as decoding them as tagged would trip the C library's assertions.
+/// Real hardware can't create these, so they mean the snapshot or tag bitmap is inconsistent.
+pub struct CapScan<'a, T: CompressedCapability> {
+    granules: ChunksExact<'a, u8>,
+    tags: &'a TagBitmap,
+    index: usize,
+    /// The address of the next granule
+    addr: T::Addr,
+    granule_size: T::Addr,
+    endian: Endian,
+}
+impl<'a, T: CompressedCapability> CapScan<'a, T> {
+    pub(crate) fn new(mem: &'a [u8], tags: &'a TagBitmap, base_addr: T::Addr, endian: Endian) -> Self {
+        let granule_size: T::Addr = NumCast::from(T::CAP_SIZE_BYTES).unwrap();
+        assert!(
+            (base_addr % granule_size).is_zero(),
+            "memory snapshots must start at a capability-aligned address"
+        );
+        CapScan {
+            granules: mem.chunks_exact(T::CAP_SIZE_BYTES),
+            tags,
+            index: 0,
+            addr: base_addr,
+            granule_size,
+            endian,
+        }
+    }
+}
+impl<'a, T: CompressedCapability> Iterator for CapScan<'a, T> {
+    type Item = (T::Addr, CcxCap<T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for granule in &mut self.granules {
+            let (index, addr) = (self.index, self.addr);
+            self.index += 1;
+            self.addr = self.addr.wrapping_add(&self.granule_size);
+            if !self.tags.get(index) {
+                continue;
+            }
+
+            let mut bytes = T::MemBytes::default();
+            bytes.as_mut().copy_from_slice(granule);
+            let cap = CcxCap::<T>::from_mem_bytes(&bytes, false, self.endian);
+            if cap.is_well_formed() {
+                return Some((addr, cap.with_tag(true)));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caps::cheriv9::cc128::Cc128;
+    use crate::caps::cheriv9::cc64::Cc64;
+
+    #[test]
+    fn test_tag_bitmap() {
+        let mut tags = TagBitmap::new(4);
+        assert_eq!(tags.as_bytes(), &[0]);
+        tags.set(1, true);
+        tags.set(9, true);
+        assert_eq!(tags.as_bytes(), &[0b10, 0b10]);
+        assert!(tags.get(1) && tags.get(9) && !tags.get(0));
+        // Out of range indices are untagged, and clearing them doesn't grow the bitmap
+        assert!(!tags.get(1000));
+        tags.set(1000, false);
+        assert_eq!(tags.as_bytes().len(), 2);
+        tags.set(9, false);
+        assert_eq!(tags, TagBitmap::from_bytes(vec![0b10, 0]));
+    }
+
+    #[test]
+    fn test_scan_memory() {
+        let base_addr = 0x8000_0000;
+        let code = Cc128::make_max_perms_cap(0x8000_1000, 0x8000_1040, 0x8000_2000);
+        let data = Cc128::make_max_perms_cap(0x8000_4000, 0x8000_4000, 0x8000_4800);
+
+        for &endian in [Endian::Little, Endian::Big].iter() {
+            // Four granules plus a trailing partial one: code, untagged copy of data, nothing, data
+            let mut mem = vec![0u8; 4 * 16 + 8];
+            mem[0..16].copy_from_slice(code.to_mem_bytes(endian).0.as_ref());
+            mem[16..32].copy_from_slice(data.to_mem_bytes(endian).0.as_ref());
+            mem[48..64].copy_from_slice(data.to_mem_bytes(endian).0.as_ref());
+            let mut tags = TagBitmap::new(4);
+            tags.set(0, true);
+            tags.set(3, true);
+            // A tag past the end of the memory, and one for the partial granule, are ignored
+            tags.set(4, true);
+            tags.set(100, true);
+
+            let found: Vec<_> = Cc128::scan_memory(&mem, &tags, base_addr, endian).collect();
+            assert_eq!(found, vec![(base_addr, code), (base_addr + 48, data)]);
+        }
+
+        // Fewer tags than granules
+        let mem = [code.to_mem_bytes(Endian::Little).0, data.to_mem_bytes(Endian::Little).0].concat();
+        let found: Vec<_> = Cc128::scan_memory(&mem, &TagBitmap::from_bytes(vec![]), 0, Endian::Little).collect();
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_scan_memory_skips_malformed() {
+        // The all-ones pesbt has reserved bits set, so it isn't a valid capability even though it's tagged
+        let mem = [0xffu8; 8];
+        let tags = TagBitmap::from_bytes(vec![1]);
+        assert_eq!(Cc64::scan_memory(&mem, &tags, 0, Endian::Little).count(), 0);
+
+        let cap = Cc64::make_max_perms_cap(0x100, 0x100, 0x200);
+        let mem = cap.to_mem_bytes(Endian::Big).0;
+        assert_eq!(
+            Cc64::scan_memory(&mem, &tags, 0x100, Endian::Big).collect::<Vec<_>>(),
+            vec![(0x100, cap)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_scan_memory_misaligned_base() {
+        Cc128::scan_memory(&[], &TagBitmap::default(), 8, Endian::Little);
+    }
+}
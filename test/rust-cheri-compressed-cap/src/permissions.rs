@@ -0,0 +1,462 @@
+//! Compact human-readable rendering of architectural permissions, e.g. `Grwx--L-----`.
+//!
+//! Each architectural permission has a fixed position and letter:
+//! lowercase for data permissions, uppercase for capability permissions and the remaining system-level permissions.
+//! Absent permissions are rendered as `-`.
+//!
+//! | Letter | Permission |
+//! |---|---|
+//! | `G` | [CompressedCapability::PERM_GLOBAL] |
+//! | `r` | [CompressedCapability::PERM_LOAD] |
+//! | `w` | [CompressedCapability::PERM_STORE] |
+//! | `x` | [CompressedCapability::PERM_EXECUTE] |
+//! | `R` | [CompressedCapability::PERM_LOAD_CAP] |
+//! | `W` | [CompressedCapability::PERM_STORE_CAP] |
+//! | `L` | [CompressedCapability::PERM_STORE_LOCAL] |
+//! | `s` | [CompressedCapability::PERM_SEAL] |
+//! | `u` | [CompressedCapability::PERM_UNSEAL] |
+//! | `i` | [CompressedCapability::PERM_CINVOKE] |
+//! | `S` | [CompressedCapability::PERM_ACCESS_SYS_REGS] |
+//! | `C` | [CompressedCapability::PERM_SETCID] |
+//!
+//! The mapping is format-aware: the bit for each letter is taken from the format's `PERM_*` constants
+//! (which differ for Morello), and permissions a format doesn't model (`PERM_*` equal to zero) are left out of the string.
+//! Permission bits with no letter (e.g. the Morello user permissions) are not rendered.
+//!
+//! [Permissions::to_mnemonic] uses the same letters, but leaves out absent permissions instead of rendering `-`
+//! (e.g. `rwRW`), which is the form usually accepted on command lines.
+
+use crate::CompressedCapability;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/// Returns the `(letter, bit)` pairs for `T`, in rendering order, skipping unmodelled permissions.
+fn letters<T: CompressedCapability>() -> impl Iterator<Item = (char, u32)> {
+    // IntoIterator::into_iter iterates by value, unlike array.into_iter() in edition 2018
+    IntoIterator::into_iter([
+        ('G', T::PERM_GLOBAL),
+        ('r', T::PERM_LOAD),
+        ('w', T::PERM_STORE),
+        ('x', T::PERM_EXECUTE),
+        ('R', T::PERM_LOAD_CAP),
+        ('W', T::PERM_STORE_CAP),
+        ('L', T::PERM_STORE_LOCAL),
+        ('s', T::PERM_SEAL),
+        ('u', T::PERM_UNSEAL),
+        ('i', T::PERM_CINVOKE),
+        ('S', T::PERM_ACCESS_SYS_REGS),
+        ('C', T::PERM_SETCID),
+    ])
+    .filter(|&(_, bit)| bit != 0)
+}
+
+/// Renders the architectural permissions in `perms` as a fixed-order string, see the [module documentation](self).
+pub fn to_string<T: CompressedCapability>(perms: u32) -> String {
+    letters::<T>()
+        .map(|(letter, bit)| if (perms & bit) == bit { letter } else { '-' })
+        .collect()
+}
+
+/// Parses a string produced by [to_string] back into a permissions value.
+///
+/// The string must have exactly one character per permission modelled by `T`,
+/// each either that permission's letter or `-`.
+pub fn from_string<T: CompressedCapability>(s: &str) -> Result<u32, ParseError> {
+    let expected_len = letters::<T>().count();
+    let found_len = s.chars().count();
+    if found_len != expected_len {
+        return Err(ParseError::WrongLength {
+            expected: expected_len,
+            found: found_len,
+        });
+    }
+
+    let mut perms = 0;
+    for (index, (c, (letter, bit))) in s.chars().zip(letters::<T>()).enumerate() {
+        if c == letter {
+            perms |= bit;
+        } else if c != '-' {
+            return Err(ParseError::UnexpectedChar { index, found: c });
+        }
+    }
+    Ok(perms)
+}
+
+/// Reasons a permissions string can fail to parse, see [from_string] and [Permissions::from_mnemonic]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The string didn't have one character per permission
+    WrongLength { expected: usize, found: usize },
+    /// The character at `index` was neither the letter for that position nor `-`,
+    /// or (for [Permissions::from_mnemonic]) not the letter of any permission the format models.
+    /// `index` counts characters, not bytes.
+    UnexpectedChar { index: usize, found: char },
+}
+
+/// A named architectural permission, see [CcxCap::iter_permissions](crate::CcxCap::iter_permissions).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PermissionName {
+    /// [CompressedCapability::PERM_GLOBAL]
+    Global,
+    /// [CompressedCapability::PERM_EXECUTE]
+    Execute,
+    /// [CompressedCapability::PERM_LOAD]
+    Load,
+    /// [CompressedCapability::PERM_STORE]
+    Store,
+    /// [CompressedCapability::PERM_LOAD_CAP]
+    LoadCap,
+    /// [CompressedCapability::PERM_STORE_CAP]
+    StoreCap,
+    /// [CompressedCapability::PERM_STORE_LOCAL]
+    StoreLocal,
+    /// [CompressedCapability::PERM_SEAL]
+    Seal,
+    /// [CompressedCapability::PERM_CINVOKE]
+    CInvoke,
+    /// [CompressedCapability::PERM_UNSEAL]
+    Unseal,
+    /// [CompressedCapability::PERM_ACCESS_SYS_REGS]
+    AccessSysRegs,
+    /// [CompressedCapability::PERM_SETCID]
+    SetCid,
+}
+impl PermissionName {
+    /// All permissions, in the order of the `PERM_*` constants
+    pub const ALL: [PermissionName; 12] = [
+        PermissionName::Global,
+        PermissionName::Execute,
+        PermissionName::Load,
+        PermissionName::Store,
+        PermissionName::LoadCap,
+        PermissionName::StoreCap,
+        PermissionName::StoreLocal,
+        PermissionName::Seal,
+        PermissionName::CInvoke,
+        PermissionName::Unseal,
+        PermissionName::AccessSysRegs,
+        PermissionName::SetCid,
+    ];
+
+    /// Returns the format-`T` bit for this permission, i.e. the corresponding `PERM_*` constant.
+    /// This is zero if the format doesn't model the permission.
+    pub fn bit<T: CompressedCapability>(self) -> u32 {
+        match self {
+            PermissionName::Global => T::PERM_GLOBAL,
+            PermissionName::Execute => T::PERM_EXECUTE,
+            PermissionName::Load => T::PERM_LOAD,
+            PermissionName::Store => T::PERM_STORE,
+            PermissionName::LoadCap => T::PERM_LOAD_CAP,
+            PermissionName::StoreCap => T::PERM_STORE_CAP,
+            PermissionName::StoreLocal => T::PERM_STORE_LOCAL,
+            PermissionName::Seal => T::PERM_SEAL,
+            PermissionName::CInvoke => T::PERM_CINVOKE,
+            PermissionName::Unseal => T::PERM_UNSEAL,
+            PermissionName::AccessSysRegs => T::PERM_ACCESS_SYS_REGS,
+            PermissionName::SetCid => T::PERM_SETCID,
+        }
+    }
+}
+
+/// Returns the permissions modelled by `T` which are set in `perms`, in [PermissionName::ALL] order.
+pub fn iter_names<T: CompressedCapability>(perms: u32) -> impl Iterator<Item = PermissionName> {
+    IntoIterator::into_iter(PermissionName::ALL).filter(move |name| {
+        let bit = name.bit::<T>();
+        bit != 0 && (perms & bit) == bit
+    })
+}
+
+/// A set of format-`T` architectural permissions, as returned by [CcxCap::perms](crate::CcxCap::perms).
+///
+/// Typed by the format so permissions can't be mixed up between formats with different bit layouts (e.g. Morello),
+/// or with software permissions, which are still plain `u32`s.
+/// The named flags (e.g. [Self::LOAD]) are the `PERM_*` constants of `T`, so are [Self::EMPTY] for permissions the format doesn't model.
+///
+/// Each flag also has a builder method adding it, so a combination can be written as
+/// `Permissions::<Cc128>::EMPTY.global().load().store()`.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Permissions<T: CompressedCapability> {
+    bits: u32,
+    format: PhantomData<T>,
+}
+
+macro_rules! permission_flags {
+    ($($perm:ident => $name:ident, $method:ident),* $(,)?) => {
+        $(
+            #[doc = concat!("[CompressedCapability::", stringify!($perm), "]")]
+            pub const $name: Self = Self::from_bits(T::$perm);
+        )*
+        $(
+            #[doc = concat!("Adds [CompressedCapability::", stringify!($perm), "]")]
+            pub fn $method(self) -> Self {
+                self.union(Self::$name)
+            }
+        )*
+    };
+}
+
+impl<T: CompressedCapability> Permissions<T> {
+    /// No permissions
+    pub const EMPTY: Self = Self::from_bits(0);
+
+    permission_flags! {
+        PERM_GLOBAL => GLOBAL, global,
+        PERM_EXECUTE => EXECUTE, execute,
+        PERM_LOAD => LOAD, load,
+        PERM_STORE => STORE, store,
+        PERM_LOAD_CAP => LOAD_CAP, load_cap,
+        PERM_STORE_CAP => STORE_CAP, store_cap,
+        PERM_STORE_LOCAL => STORE_LOCAL, store_local,
+        PERM_SEAL => SEAL, seal,
+        PERM_UNSEAL => UNSEAL, unseal,
+        PERM_CINVOKE => CINVOKE, cinvoke,
+        PERM_ACCESS_SYS_REGS => ACCESS_SYS_REGS, access_sys_regs,
+        PERM_SETCID => SETCID, setcid,
+    }
+
+    /// Wraps a raw permissions word. Bits are not checked against the format.
+    pub const fn from_bits(bits: u32) -> Self {
+        Permissions {
+            bits,
+            format: PhantomData,
+        }
+    }
+
+    /// Returns the raw permissions word
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Returns true if no permissions are set
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns true if all permissions in `other` are also in `self`
+    pub fn contains(&self, other: Self) -> bool {
+        (self.bits & other.bits) == other.bits
+    }
+
+    /// Returns true if any permission in `other` is also in `self`
+    pub fn intersects(&self, other: Self) -> bool {
+        (self.bits & other.bits) != 0
+    }
+
+    /// Returns the permissions in either `self` or `other`
+    pub fn union(self, other: Self) -> Self {
+        Self::from_bits(self.bits | other.bits)
+    }
+
+    /// Returns the permissions in `self` but not in `other`
+    pub fn difference(self, other: Self) -> Self {
+        Self::from_bits(self.bits & !other.bits)
+    }
+
+    /// Renders the permissions as the letters of the set permissions only, in the fixed order of the
+    /// [module documentation](self), e.g. `rwx` for [Self::LOAD], [Self::STORE] and [Self::EXECUTE].
+    ///
+    /// Counterpart: [Self::from_mnemonic]
+    pub fn to_mnemonic(&self) -> String {
+        letters::<T>()
+            .filter(|&(_, bit)| (self.bits & bit) == bit)
+            .map(|(letter, _)| letter)
+            .collect()
+    }
+
+    /// Parses a string of permission letters, such as one produced by [Self::to_mnemonic].
+    ///
+    /// The letters may be in any order and may repeat. The empty string is [Self::EMPTY].
+    /// Letters for permissions the format doesn't model, and any other characters (including `-`), are rejected.
+    pub fn from_mnemonic(s: &str) -> Result<Self, ParseError> {
+        let mut bits = 0;
+        for (index, c) in s.chars().enumerate() {
+            match letters::<T>().find(|&(letter, _)| letter == c) {
+                Some((_, bit)) => bits |= bit,
+                None => return Err(ParseError::UnexpectedChar { index, found: c }),
+            }
+        }
+        Ok(Self::from_bits(bits))
+    }
+}
+
+impl<T: CompressedCapability> From<u32> for Permissions<T> {
+    fn from(bits: u32) -> Self {
+        Self::from_bits(bits)
+    }
+}
+impl<T: CompressedCapability> From<Permissions<T>> for u32 {
+    fn from(perms: Permissions<T>) -> u32 {
+        perms.bits
+    }
+}
+
+// Implemented manually, as #[derive] would require T: Default, and the formats are uninhabited
+impl<T: CompressedCapability> Default for Permissions<T> {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+// Implemented manually to show the mnemonic
+impl<T: CompressedCapability> Debug for Permissions<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Permissions").field(&to_string::<T>(self.bits)).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caps::cheriv9::cc128::Cc128;
+    use crate::caps::morello;
+    use crate::CcxCap;
+
+    #[test]
+    fn test_permissions_roundtrip() {
+        let combos = [
+            0,
+            Cc128::PERM_LOAD,
+            Cc128::PERM_LOAD | Cc128::PERM_STORE | Cc128::PERM_EXECUTE,
+            Cc128::PERM_GLOBAL | Cc128::PERM_LOAD_CAP | Cc128::PERM_STORE_CAP | Cc128::PERM_STORE_LOCAL,
+            Cc128::PERM_SEAL | Cc128::PERM_UNSEAL | Cc128::PERM_CINVOKE,
+            0xfff,
+        ];
+        for &perms in combos.iter() {
+            let s = to_string::<Cc128>(perms);
+            assert_eq!(from_string::<Cc128>(&s), Ok(perms), "{}", s);
+        }
+        assert_eq!(to_string::<Cc128>(0), "------------");
+        assert_eq!(to_string::<Cc128>(0xfff), "GrwxRWLsuiSC");
+        assert_eq!(
+            to_string::<Cc128>(Cc128::PERM_LOAD | Cc128::PERM_STORE | Cc128::PERM_EXECUTE),
+            "-rwx--------"
+        );
+    }
+
+    #[test]
+    fn test_permissions_morello() {
+        // Morello uses different bits for the same letters
+        let perms = morello::Cc128::PERM_LOAD | morello::Cc128::PERM_STORE;
+        assert_eq!(to_string::<morello::Cc128>(perms), "-rw---------");
+        assert_eq!(from_string::<morello::Cc128>("-rw---------"), Ok(perms));
+        assert_ne!(from_string::<Cc128>("-rw---------"), Ok(perms));
+    }
+
+    #[test]
+    fn test_permissions_parse_errors() {
+        assert_eq!(
+            from_string::<Cc128>("rwx"),
+            Err(ParseError::WrongLength { expected: 12, found: 3 })
+        );
+        assert_eq!(
+            from_string::<Cc128>("-wrx--------"),
+            Err(ParseError::UnexpectedChar { index: 1, found: 'w' })
+        );
+    }
+
+    #[test]
+    fn test_permissions_builder() {
+        type P = Permissions<Cc128>;
+
+        let built = P::EMPTY.global().execute().load();
+        assert_eq!(built.bits(), Cc128::PERM_GLOBAL | Cc128::PERM_EXECUTE | Cc128::PERM_LOAD);
+
+        let all = P::default()
+            .global()
+            .execute()
+            .load()
+            .store()
+            .load_cap()
+            .store_cap()
+            .store_local()
+            .seal()
+            .unseal()
+            .cinvoke()
+            .access_sys_regs()
+            .setcid();
+        assert_eq!(u32::from(all), 0xfff);
+
+        // Format-specific bits
+        let morello_perms = Permissions::<morello::Cc128>::EMPTY.load().store();
+        assert_eq!(morello_perms.bits(), morello::Cc128::PERM_LOAD | morello::Cc128::PERM_STORE);
+        assert_ne!(morello_perms.bits(), P::EMPTY.load().store().bits());
+    }
+
+    #[test]
+    fn test_permissions_type() {
+        type P = Permissions<Cc128>;
+
+        let rw = P::LOAD.union(P::STORE);
+        assert!(rw.contains(P::LOAD));
+        assert!(!rw.contains(P::LOAD.union(P::EXECUTE)));
+        assert!(rw.intersects(P::LOAD.union(P::EXECUTE)));
+        assert!(!rw.intersects(P::EXECUTE));
+        assert_eq!(rw.difference(P::STORE), P::LOAD);
+        assert!(rw.difference(rw).is_empty());
+        assert_eq!(u32::from(rw), Cc128::PERM_LOAD | Cc128::PERM_STORE);
+        assert_eq!(P::from(0xfff), P::from_bits(0xfff));
+        assert_eq!(format!("{:?}", rw), "Permissions(\"-rw---------\")");
+
+        // Flags follow the format's bit layout
+        assert_eq!(Permissions::<morello::Cc128>::LOAD.bits(), morello::Cc128::PERM_LOAD);
+        assert_ne!(Permissions::<morello::Cc128>::LOAD.bits(), P::LOAD.bits());
+
+        let mut cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        assert_eq!(cap.perms().bits(), cap.permissions());
+        cap.set_perms(cap.perms().difference(P::STORE));
+        assert!(cap.perms().contains(P::LOAD));
+        assert!(!cap.perms().contains(P::STORE));
+        assert_eq!(cap.permissions(), 0xfff & !Cc128::PERM_STORE);
+    }
+
+    #[test]
+    fn test_permissions_mnemonic() {
+        type P = Permissions<Cc128>;
+
+        assert_eq!(P::from_mnemonic("rwx"), Ok(P::LOAD.union(P::STORE).union(P::EXECUTE)));
+        assert_eq!(P::from_mnemonic("rwx").unwrap().bits(), Cc128::PERM_LOAD | Cc128::PERM_STORE | Cc128::PERM_EXECUTE);
+        assert_eq!(P::from_mnemonic(""), Ok(P::EMPTY));
+        // Order and repetition don't matter
+        assert_eq!(P::from_mnemonic("xwrr"), P::from_mnemonic("rwx"));
+
+        assert_eq!(P::EMPTY.to_mnemonic(), "");
+        assert_eq!(P::from_bits(0xfff).to_mnemonic(), "GrwxRWLsuiSC");
+        assert_eq!(P::EXECUTE.union(P::LOAD).union(P::LOAD_CAP).to_mnemonic(), "rxR");
+        for perms in 0..=0xfff {
+            let perms = P::from_bits(perms);
+            assert_eq!(P::from_mnemonic(&perms.to_mnemonic()), Ok(perms));
+        }
+
+        // Morello uses different bits for the same letters
+        let morello_rw = Permissions::<morello::Cc128>::from_mnemonic("rw").unwrap();
+        assert_eq!(morello_rw.bits(), morello::Cc128::PERM_LOAD | morello::Cc128::PERM_STORE);
+        assert_eq!(morello_rw.to_mnemonic(), "rw");
+
+        assert_eq!(P::from_mnemonic("rwz"), Err(ParseError::UnexpectedChar { index: 2, found: 'z' }));
+        assert_eq!(P::from_mnemonic("r-w"), Err(ParseError::UnexpectedChar { index: 1, found: '-' }));
+        assert_eq!(P::from_mnemonic("é r"), Err(ParseError::UnexpectedChar { index: 0, found: 'é' }));
+    }
+
+    #[test]
+    fn test_iter_permissions() {
+        let root = CcxCap::<Cc128>::root();
+        assert_eq!(root.iter_permissions().collect::<Vec<_>>(), PermissionName::ALL.to_vec());
+        assert_eq!(root.iter_software_permissions().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+        let mut cap = root;
+        cap.set_permissions(Cc128::PERM_LOAD | Cc128::PERM_STORE);
+        cap.set_software_permissions(0b10);
+        assert_eq!(
+            cap.iter_permissions().collect::<Vec<_>>(),
+            vec![PermissionName::Load, PermissionName::Store]
+        );
+        assert_eq!(cap.iter_software_permissions().collect::<Vec<_>>(), vec![1]);
+
+        // Morello uses different bits for the same names
+        let mut morello_cap = CcxCap::<morello::Cc128>::root();
+        morello_cap.set_permissions(morello::Cc128::PERM_LOAD | morello::Cc128::PERM_STORE);
+        assert_eq!(
+            morello_cap.iter_permissions().collect::<Vec<_>>(),
+            vec![PermissionName::Load, PermissionName::Store]
+        );
+    }
+}
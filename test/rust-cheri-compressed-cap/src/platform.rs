@@ -0,0 +1,40 @@
+//! Descriptions of which parts of a capability format a specific CHERI implementation supports.
+
+use crate::caps::{cheriv9, morello};
+use crate::CompressedCapability;
+
+/// Describes the subset of permissions and object types implemented by a particular platform.
+///
+/// Real implementations don't necessarily implement every architectural permission, or the full otype space.
+/// Use [crate::CcxCap::is_valid_for_platform] to check a capability could have been produced by such a platform.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlatformProfile {
+    /// Mask of the implemented hardware permissions, in the format of [crate::CcxCap::permissions]
+    pub permissions: u32,
+    /// Mask of the implemented software permissions, in the format of [crate::CcxCap::software_permissions]
+    pub software_permissions: u32,
+    /// The largest non-reserved otype the platform can seal with.
+    /// The format's reserved otypes (unsealed, sentry, etc.) are always considered implemented.
+    pub max_otype: u32,
+}
+impl PlatformProfile {
+    /// Profile for a platform implementing every permission and otype representable in the format `T`.
+    pub fn full<T: CompressedCapability>() -> Self {
+        let max_perms_cap = T::make_max_perms_cap(Default::default(), Default::default(), Default::default());
+        PlatformProfile {
+            permissions: max_perms_cap.permissions(),
+            software_permissions: max_perms_cap.software_permissions(),
+            max_otype: T::MAX_REPRESENTABLE_OTYPE,
+        }
+    }
+
+    /// Profile for the reference CHERIv9 128-bit implementation, which implements the whole format.
+    pub fn cc128_reference() -> Self {
+        Self::full::<cheriv9::cc128::Cc128>()
+    }
+
+    /// Profile for Arm Morello, which implements the whole format.
+    pub fn morello() -> Self {
+        Self::full::<morello::Cc128>()
+    }
+}
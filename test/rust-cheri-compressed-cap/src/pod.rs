@@ -0,0 +1,101 @@
+//! [bytemuck] support, enabled by the `bytemuck` feature.
+//!
+//! [CcxCap] matches the layout of the C `_cc_N(cap)` type, so a capability can be read directly out of
+//! a byte buffer holding a C capability struct (e.g. with [bytemuck::pod_read_unaligned]).
+//! Any bit pattern is a valid [CcxCap] as far as Rust is concerned, although the decoded fields may not be
+//! consistent with the pesbt (see [CcxCap::self_consistent]), so [AnyBitPattern] and [Zeroable] are implemented.
+//!
+//! [CcxCap] does *not* implement [bytemuck::Pod]/[NoUninit], so can't be viewed as bytes with [bytemuck::bytes_of]:
+//! like the C type it has trailing padding after `cr_extra` to reach its 16-byte alignment,
+//! and reading uninitialized padding bytes is undefined behaviour.
+//! Use [CcxCap::mem_representation] to get the bytes to store in memory.
+//!
+//! [CcxBoundsBits] contains a `bool`, so not every bit pattern is valid and it only implements [NoUninit] and [Zeroable].
+
+use crate::caps::{cheriv9, morello, rvy};
+use crate::{CcxBoundsBits, CcxCap, CompressedCapability};
+use bytemuck::{AnyBitPattern, NoUninit, Zeroable};
+use static_assertions::{assert_eq_align, assert_eq_size};
+
+// The sizes and alignments of the C types, see cheri_compressed_cap_common.h
+assert_eq_size!(CcxCap<cheriv9::cc64::Cc64>, [u8; 32]);
+assert_eq_size!(CcxCap<rvy::cc64::Cc64>, [u8; 32]);
+assert_eq_size!(CcxCap<cheriv9::cc128::Cc128>, [u8; 48]);
+assert_eq_size!(CcxCap<rvy::cc128::Cc128>, [u8; 48]);
+assert_eq_size!(CcxCap<morello::Cc128>, [u8; 48]);
+assert_eq_align!(CcxCap<cheriv9::cc64::Cc64>, u128);
+assert_eq_align!(CcxCap<cheriv9::cc128::Cc128>, u128);
+assert_eq_size!(CcxBoundsBits, [u8; 6]);
+
+// SAFETY: CcxCap is repr(C) and only contains integers, which are valid when zeroed
+unsafe impl<T: CompressedCapability> Zeroable for CcxCap<T>
+where
+    T::Addr: Zeroable,
+    T::FfiLength: Zeroable,
+{
+}
+// SAFETY: CcxCap is repr(C), Copy, and only contains integers, for which any bit pattern is valid.
+// Padding bytes are never read.
+unsafe impl<T: CompressedCapability + 'static> AnyBitPattern for CcxCap<T>
+where
+    T::Addr: AnyBitPattern,
+    T::FfiLength: AnyBitPattern,
+{
+}
+
+// SAFETY: CcxBoundsBits is repr(C), only contains integers and a bool, which are valid when zeroed
+unsafe impl Zeroable for CcxBoundsBits {}
+// SAFETY: CcxBoundsBits is repr(C) without padding (checked above), so every byte is initialized
+unsafe impl NoUninit for CcxBoundsBits {}
+
+#[cfg(test)]
+mod tests {
+    use crate::caps::cheriv9::cc128::{Cc128, Cc128Cap};
+    use crate::CompressedCapability;
+
+    /// Lays out `cap` as the C `cc128_cap_t`, with zeroed padding
+    fn to_c_bytes(cap: &Cc128Cap) -> [u8; 48] {
+        let (tag, [cursor, pesbt]) = cap.reg_representation();
+        let (top, base, exp) = cap.raw_cache();
+        let mut bytes = [0u8; 48];
+        bytes[0..8].copy_from_slice(&cursor.to_ne_bytes());
+        bytes[8..16].copy_from_slice(&pesbt.to_ne_bytes());
+        bytes[16..32].copy_from_slice(&top.to_ne_bytes());
+        bytes[32..40].copy_from_slice(&base.to_ne_bytes());
+        bytes[40] = tag as u8;
+        bytes[41] = 1; // cr_bounds_valid
+        bytes[42] = exp;
+        bytes
+    }
+
+    #[test]
+    fn test_read_from_bytes() {
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000);
+        let bytes = to_c_bytes(&cap);
+
+        let read: Cc128Cap = bytemuck::pod_read_unaligned(&bytes);
+        assert_eq!(read, cap);
+        assert_eq!(read.bounds(), cap.bounds());
+        assert!(read.self_consistent());
+
+        // Also via an aligned reference
+        let mut aligned = [0u128; 3];
+        bytemuck::cast_slice_mut::<u128, u8>(&mut aligned).copy_from_slice(&bytes);
+        let read: &Cc128Cap = bytemuck::from_bytes(bytemuck::cast_slice(&aligned));
+        assert_eq!(*read, cap);
+
+        let zeroed: Cc128Cap = bytemuck::Zeroable::zeroed();
+        assert_eq!(zeroed, Cc128Cap::default());
+    }
+
+    #[test]
+    fn test_bounds_bits_bytes() {
+        let bits = Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000).extract_bounds_bits();
+        let bytes = bytemuck::bytes_of(&bits);
+        assert_eq!(bytes.len(), 6);
+        assert_eq!(u16::from_ne_bytes([bytes[0], bytes[1]]), bits.b);
+        assert_eq!(u16::from_ne_bytes([bytes[2], bytes[3]]), bits.t);
+        assert_eq!(bytes[4], bits.e);
+        assert_eq!(bytes[5], bits.ie as u8);
+    }
+}
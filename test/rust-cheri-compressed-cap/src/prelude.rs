@@ -0,0 +1,25 @@
+//! Re-exports the traits and types most users need, for glob importing.
+//!
+//! The `Cc64`/`Cc128` formats are the CHERIv9 ones. Morello and RISC-V Y capabilities reuse the same names,
+//! so they have to be imported from [crate::caps] explicitly.
+//!
+//! ```
+//! use rust_cheri_compressed_cap::prelude::*;
+//!
+//! let cap: Cc128Cap = CapBuilder::<Cc128>::new()
+//!     .base(0x1000)
+//!     .top(0x2000)
+//!     .perms(Permissions::<Cc128>::EMPTY.load().store())
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(Cc128::getCapBounds(&cap), (0x1000, 0x2000));
+//! assert_eq!(cap.otype_kind(), OType::Unsealed);
+//! assert!(cap.perms().contains(Permissions::<Cc128>::LOAD));
+//! ```
+
+pub use crate::builder::CapBuilder;
+pub use crate::caps::cheriv9::cc128::{Cc128, Cc128Cap};
+pub use crate::caps::cheriv9::cc64::{Cc64, Cc64Cap};
+pub use crate::permissions::Permissions;
+pub use crate::wrappers::CheriRVFuncs;
+pub use crate::{CcxCap, CompressedCapability, Flags, OType};
@@ -0,0 +1,80 @@
+//! Property tests over randomly generated capabilities, for the CHERIv9 64- and 128-bit formats.
+//!
+//! Capabilities are derived like software would: from a root capability covering the whole address space,
+//! with random bounds set by [CcxCap::set_bounds_unchecked] and optionally a random otype.
+
+use crate::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+use crate::{max_top, CcxCap, CompressedCapability};
+use num_traits::Zero;
+use proptest::prelude::*;
+
+/// Returns the requested `(base, length)` for the raw inputs,
+/// with `raw_length >> shift` clamped so the top is at most [max_top].
+fn requested_bounds<T: CompressedCapability>(base: T::Addr, raw_length: T::Addr, shift: u32) -> (T::Addr, T::Length) {
+    let length: T::Length = (raw_length >> shift as usize).into();
+    let base_len: T::Length = base.into();
+    let max_length = max_top::<T>() - base_len;
+    (base, if length > max_length { max_length } else { length })
+}
+
+/// Derives a capability with the requested bounds from the root, returning it and whether setting the bounds was exact
+fn derive_cap<T: CompressedCapability>(base: T::Addr, length: T::Length) -> (CcxCap<T>, bool) {
+    let mut cap = T::make_max_perms_cap(T::Addr::zero(), base, max_top::<T>());
+    let exact = cap.set_bounds_unchecked(length);
+    (cap, exact)
+}
+
+fn check_roundtrip<T: CompressedCapability>(base: T::Addr, raw_length: T::Addr, shift: u32, otype: Option<u32>) {
+    let (base, length) = requested_bounds::<T>(base, raw_length, shift);
+    let (mut cap, _) = derive_cap::<T>(base, length);
+    if let Some(otype) = otype {
+        cap.set_otype(otype % (T::MAX_REPRESENTABLE_OTYPE + 1));
+    }
+
+    let pesbt = T::compress_raw(&cap);
+    let decoded = T::decompress_raw(pesbt, cap.address(), cap.tag());
+    assert_eq!(decoded, cap);
+    assert_eq!(decoded.bounds(), cap.bounds());
+    assert_eq!(decoded.otype(), cap.otype());
+}
+
+fn check_set_bounds_monotone<T: CompressedCapability>(base: T::Addr, raw_length: T::Addr, shift: u32) {
+    let (base, length) = requested_bounds::<T>(base, raw_length, shift);
+    let (cap, exact) = derive_cap::<T>(base, length);
+    let (new_base, new_top) = cap.bounds();
+    let req_top = Into::<T::Length>::into(base) + length;
+
+    assert!(cap.tag());
+    // set_bounds reports whether the requested bounds could be used as-is
+    if exact {
+        assert_eq!((new_base, new_top), (base, req_top));
+    } else {
+        assert!(new_base <= base, "base {:#x} > requested {:#x}", new_base, base);
+        assert!(new_top >= req_top, "top {:#x} < requested {:#x}", new_top, req_top);
+        assert!((new_base, new_top) != (base, req_top));
+    }
+    // The cursor is left at the requested base
+    assert_eq!(cap.address(), base);
+}
+
+proptest! {
+    #[test]
+    fn prop_roundtrip_cc64(base: u32, raw_length: u32, shift in 0u32..32, otype: Option<u32>) {
+        check_roundtrip::<Cc64>(base, raw_length, shift, otype);
+    }
+
+    #[test]
+    fn prop_roundtrip_cc128(base: u64, raw_length: u64, shift in 0u32..64, otype: Option<u32>) {
+        check_roundtrip::<Cc128>(base, raw_length, shift, otype);
+    }
+
+    #[test]
+    fn prop_set_bounds_monotone_cc64(base: u32, raw_length: u32, shift in 0u32..32) {
+        check_set_bounds_monotone::<Cc64>(base, raw_length, shift);
+    }
+
+    #[test]
+    fn prop_set_bounds_monotone_cc128(base: u64, raw_length: u64, shift in 0u32..64) {
+        check_set_bounds_monotone::<Cc128>(base, raw_length, shift);
+    }
+}
@@ -0,0 +1,106 @@
+//! Random valid capabilities for test harnesses and benchmarks, enabled by the `rand` feature.
+//!
+//! Unlike [fuzzing](crate::fuzzing), which decodes arbitrary bit patterns (so mostly produces untagged or huge capabilities),
+//! these are always tagged, exactly-representable capabilities with a spread of lengths, like those a program creates.
+
+use crate::{align_region, low_bits_mask, max_top, CcxCap, CompressedCapability};
+use num_traits::{AsPrimitive, WrappingAdd, Zero};
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+
+/// Returns a random tagged capability, see [CompressedCapability::random_cap].
+pub fn random_cap<T: CompressedCapability, R: Rng + ?Sized>(rng: &mut R) -> CcxCap<T>
+where
+    Standard: Distribution<T::Addr>,
+{
+    loop {
+        if let Some(cap) = try_random_cap(rng) {
+            return cap;
+        }
+    }
+}
+
+/// Draws random fields, or returns None if they can't be combined into a valid capability.
+/// This only happens on Morello, where some bounds can't be derived from the root capability,
+/// and some cursors the C library reports as representable decode to different bounds.
+fn try_random_cap<T: CompressedCapability, R: Rng + ?Sized>(rng: &mut R) -> Option<CcxCap<T>>
+where
+    Standard: Distribution<T::Addr>,
+{
+    // Pick the width of the length first, so small and large capabilities are equally likely
+    let width = rng.gen_range(0..=T::ADDR_WIDTH_BITS);
+    let len: T::Length = if width == T::ADDR_WIDTH_BITS && rng.gen_bool(0.5) {
+        max_top::<T>()
+    } else {
+        (rng.gen::<T::Addr>() & low_bits_mask(width)).into()
+    };
+    let mut base = rng.gen::<T::Addr>();
+    let base_len: T::Length = base.into();
+    if base_len + len > max_top::<T>() {
+        base = (max_top::<T>() - len).as_();
+    }
+    let (base, len) = align_region::<T>(base, len);
+
+    let mut cap = CcxCap::<T>::root();
+    cap.set_bounds_exact(base, T::compute_top(base, len))
+        .expect("align_region returned inexact bounds");
+    if !cap.tag() {
+        return None;
+    }
+
+    // Mostly within the bounds, but sometimes elsewhere in the representable region
+    let candidate = rng.gen::<T::Addr>();
+    let cursor = if rng.gen_bool(0.25) && cap.is_representable_with_new_addr(candidate) {
+        candidate
+    } else if len.is_zero() {
+        base
+    } else {
+        let offset: T::Length = T::Length::from(rng.gen::<T::Addr>()) % len;
+        base.wrapping_add(&offset.as_())
+    };
+    cap.set_address_unchecked(cursor);
+    if !cap.self_consistent() {
+        return None;
+    }
+
+    cap.set_permissions(rng.gen::<u32>() & cap.permissions());
+    cap.set_software_permissions(rng.gen::<u32>() & cap.software_permissions());
+    if rng.gen_bool(0.5) {
+        cap.set_otype(rng.gen_range(0..=T::MAX_REPRESENTABLE_OTYPE));
+    }
+    Some(cap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn check_random_caps<T: CompressedCapability>()
+    where
+        Standard: Distribution<T::Addr>,
+    {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let mut in_bounds = 0;
+        for _ in 0..1000 {
+            let cap = T::random_cap(&mut rng);
+            assert!(cap.tag() && cap.is_well_formed(), "{:?}", cap);
+            assert!(cap.is_exact() && cap.self_consistent(), "{:?}", cap);
+            assert!(cap.is_representable_with_new_addr(cap.address()), "{:?}", cap);
+            assert_eq!(cap.permissions() & !CcxCap::<T>::root().permissions(), 0);
+            assert!(cap.otype() <= T::MAX_REPRESENTABLE_OTYPE || cap.otype() == T::OTYPE_UNSEALED);
+            if cap.is_in_bounds(cap.address(), T::Length::from(T::Addr::zero())) {
+                in_bounds += 1;
+            }
+        }
+        assert!(in_bounds > 500, "only {} in bounds", in_bounds);
+    }
+
+    #[test]
+    fn test_random_caps() {
+        crate::for_each_cap_format!(|C| {
+            check_random_caps::<C>();
+        });
+    }
+}
@@ -0,0 +1,81 @@
+//! A simple model of a capability register file, for emulators operating on whole register sets.
+
+use crate::{CcxCap, CompressedCapability};
+use std::ops::{Index, IndexMut};
+
+/// The number of registers in a [CapRegisterFile], matching the RISC-V/MIPS integer register count.
+pub const NUM_CAP_REGS: usize = 32;
+
+/// A fixed-size set of [NUM_CAP_REGS] capability registers, indexed by register number.
+///
+/// All registers start out as the default (untagged, all-zero) capability.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CapRegisterFile<T: CompressedCapability> {
+    regs: [CcxCap<T>; NUM_CAP_REGS],
+}
+impl<T: CompressedCapability> CapRegisterFile<T> {
+    pub fn new() -> Self {
+        CapRegisterFile {
+            regs: [CcxCap::default(); NUM_CAP_REGS],
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CcxCap<T>> {
+        self.regs.iter()
+    }
+
+    /// Models the `CClearTags` instruction: clears the tag of every register `i` where bit `i` of `mask` is set.
+    ///
+    /// Only the tags are affected. The underlying register words (cursor and pesbt) are left untouched,
+    /// so the registers still hold the same data, they just no longer hold valid capabilities.
+    pub fn clear_tags(&mut self, mask: u32) {
+        for (i, reg) in self.regs.iter_mut().enumerate() {
+            if (mask >> i) & 1 == 1 {
+                reg.set_tag(false);
+            }
+        }
+    }
+}
+impl<T: CompressedCapability> Default for CapRegisterFile<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T: CompressedCapability> Index<usize> for CapRegisterFile<T> {
+    type Output = CcxCap<T>;
+
+    fn index(&self, index: usize) -> &CcxCap<T> {
+        &self.regs[index]
+    }
+}
+impl<T: CompressedCapability> IndexMut<usize> for CapRegisterFile<T> {
+    fn index_mut(&mut self, index: usize) -> &mut CcxCap<T> {
+        &mut self.regs[index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caps::cheriv9::cc128::Cc128;
+
+    #[test]
+    fn test_clear_tags() {
+        let mut regs = CapRegisterFile::<Cc128>::new();
+        for i in 0..NUM_CAP_REGS {
+            let base = 0x1000 * (i as u64 + 1);
+            regs[i] = Cc128::make_max_perms_cap(base, base, (base + 0x100) as u128);
+        }
+        let before = regs;
+
+        let mask = 0b1010_0000_0000_0000_0000_0000_0000_0110;
+        regs.clear_tags(mask);
+
+        for i in 0..NUM_CAP_REGS {
+            let cleared = (mask >> i) & 1 == 1;
+            assert_eq!(regs[i].tag(), !cleared, "register {}", i);
+            // Data words are untouched either way
+            assert_eq!(regs[i].reg_representation().1, before[i].reg_representation().1);
+        }
+    }
+}
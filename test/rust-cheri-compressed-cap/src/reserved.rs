@@ -0,0 +1,74 @@
+//! Typed access to the reserved bits of a capability's pesbt.
+//!
+//! The architecture doesn't assign these bits any meaning: tagged capabilities must have them all zero,
+//! but software is free to use them in untagged data (e.g. to carry device-memory attributes in a SoC model).
+//! Any meaning given to them is software-defined.
+//!
+//! The number and width of reserved fields differs by format
+//! (see [CompressedCapability::RESERVED_FIELDS]), so [ReservedBits] is parameterized by the format.
+
+use crate::CompressedCapability;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+/// All reserved bits of a format-`T` capability, packed together with the lowest field in the lowest bits.
+///
+/// Individual fields can be accessed by index with [Self::field] and [Self::with_field],
+/// e.g. for RISC-V Y index 0 is `RESERVED0` and index 1 is `RESERVED1`.
+///
+/// Get/set with [CcxCap::reserved_typed](crate::CcxCap::reserved_typed), [CcxCap::set_reserved_typed](crate::CcxCap::set_reserved_typed).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReservedBits<T: CompressedCapability> {
+    bits: u32,
+    format: PhantomData<T>,
+}
+impl<T: CompressedCapability> ReservedBits<T> {
+    /// The total number of reserved bits in format `T`
+    pub fn width() -> u32 {
+        T::RESERVED_FIELDS.iter().map(|&(_, width)| width).sum()
+    }
+
+    /// Wraps packed reserved bits. Returns None if `bits` doesn't fit in [Self::width] bits.
+    pub fn new(bits: u32) -> Option<Self> {
+        if (bits as u64) >> Self::width() != 0 {
+            None
+        } else {
+            Some(ReservedBits {
+                bits,
+                format: PhantomData,
+            })
+        }
+    }
+
+    /// Returns all fields packed together
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Returns the `(shift, width)` of field `index` within [Self::bits], or None if there is no such field.
+    fn field_position(index: usize) -> Option<(u32, u32)> {
+        let &(_, width) = T::RESERVED_FIELDS.get(index)?;
+        let shift = T::RESERVED_FIELDS[..index].iter().map(|&(_, width)| width).sum();
+        Some((shift, width))
+    }
+
+    /// Returns the value of reserved field `index`.
+    ///
+    /// Panics if the format doesn't have that many reserved fields.
+    pub fn field(&self, index: usize) -> u32 {
+        let (shift, width) = Self::field_position(index).expect("no such reserved field");
+        ((self.bits as u64 >> shift) & ((1u64 << width) - 1)) as u32
+    }
+
+    /// Returns a copy with reserved field `index` set to `value`,
+    /// or None if there is no such field or `value` doesn't fit in it.
+    pub fn with_field(self, index: usize, value: u32) -> Option<Self> {
+        let (shift, width) = Self::field_position(index)?;
+        let mask = (1u64 << width) - 1;
+        if value as u64 > mask {
+            return None;
+        }
+        let bits = (self.bits as u64 & !(mask << shift)) | ((value as u64) << shift);
+        Self::new(bits as u32)
+    }
+}
@@ -0,0 +1,102 @@
+//! [serde] support for [CcxCap], enabled by the `serde` feature.
+//!
+//! Capabilities are serialized as their canonical `(tag, cursor, pesbt)` in the register format,
+//! e.g. `{"tag":true,"cursor":"0x1000","pesbt":"0x..."}` in JSON.
+//! The cursor and pesbt are hex strings, so 64-bit values survive formats which store numbers as doubles.
+//! The decoded fields (bounds etc.) aren't serialized:
+//! deserializing decodes the capability again with [CompressedCapability::decompress_raw], so they're always consistent.
+
+use crate::{CcxCap, CompressedCapability};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The serialized form of a [CcxCap]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "CcxCap")]
+struct CapRepr {
+    tag: bool,
+    cursor: String,
+    pesbt: String,
+}
+
+fn parse_hex<T: CompressedCapability, E: Error>(field: &str, value: &str) -> Result<T::Addr, E> {
+    let digits = value
+        .strip_prefix("0x")
+        .ok_or_else(|| E::custom(format!("{} {:?} doesn't start with 0x", field, value)))?;
+    num_traits::Num::from_str_radix(digits, 16)
+        .map_err(|_| E::custom(format!("{} {:?} isn't a valid hex address", field, value)))
+}
+
+impl<T: CompressedCapability> Serialize for CcxCap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (tag, [cursor, pesbt]) = self.reg_representation();
+        CapRepr {
+            tag,
+            cursor: format!("{:#x}", cursor),
+            pesbt: format!("{:#x}", pesbt),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, T: CompressedCapability> Deserialize<'de> for CcxCap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = CapRepr::deserialize(deserializer)?;
+        let cursor = parse_hex::<T, D::Error>("cursor", &repr.cursor)?;
+        let pesbt = parse_hex::<T, D::Error>("pesbt", &repr.pesbt)?;
+        Ok(T::decompress_raw(pesbt, cursor, repr.tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::caps::{cheriv9, morello, rvy};
+    use crate::{CcxBoundsBits, CcxCap, CompressedCapability};
+
+    fn check_roundtrip<T: CompressedCapability>(cap: CcxCap<T>) {
+        let json = serde_json::to_string(&cap).unwrap();
+        let decoded: CcxCap<T> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, cap, "{}", json);
+        assert_eq!(decoded.bounds(), cap.bounds(), "{}", json);
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        check_roundtrip(cheriv9::cc64::Cc64::make_max_perms_cap(0x1000, 0x1800, 0x2000));
+        check_roundtrip(cheriv9::cc64::Cc64::make_max_perms_cap(0x1000, 0x1800, 0x2000).with_tag(false));
+        check_roundtrip(cheriv9::cc128::Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000));
+        check_roundtrip(cheriv9::cc128::Cc128::make_max_perms_cap(0, 0xffff_ffff_ffff_fff0, 1 << 64));
+        check_roundtrip(morello::Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000));
+        check_roundtrip(rvy::cc64::Cc64::make_max_perms_cap(0x1000, 0x1800, 0x2000));
+        check_roundtrip(rvy::cc128::Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000));
+
+        let bits = cheriv9::cc128::Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000).extract_bounds_bits();
+        let json = serde_json::to_string(&bits).unwrap();
+        assert_eq!(serde_json::from_str::<CcxBoundsBits>(&json).unwrap(), bits);
+    }
+
+    #[test]
+    fn test_serde_format() {
+        type Cc128 = cheriv9::cc128::Cc128;
+
+        let cap = Cc128::make_max_perms_cap(0, 0xffff_ffff_ffff_fff0, 1 << 64);
+        let (_, [_, pesbt]) = cap.reg_representation();
+        let json = serde_json::to_value(cap).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "tag": true,
+                "cursor": "0xfffffffffffffff0",
+                "pesbt": format!("{:#x}", pesbt),
+            })
+        );
+
+        // Decoded fields are recomputed from the pesbt
+        let untagged: CcxCap<Cc128> = serde_json::from_str(r#"{"tag":false,"cursor":"0x10","pesbt":"0x0"}"#).unwrap();
+        assert_eq!(untagged, Cc128::decompress_raw(0, 0x10, false));
+
+        assert!(serde_json::from_str::<CcxCap<Cc128>>(r#"{"tag":false,"cursor":"16","pesbt":"0x0"}"#).is_err());
+        assert!(serde_json::from_str::<CcxCap<Cc128>>(r#"{"tag":false,"cursor":"0xg","pesbt":"0x0"}"#).is_err());
+        assert!(serde_json::from_str::<CcxCap<Cc128>>(r#"{"tag":false,"cursor":"0x1","pesbt":"0x10000000000000000"}"#).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+//! Helpers for writing tests that are generic over capability formats.
+//!
+//! Only available with the `testing` feature enabled.
+
+/// Expands `body` once for each capability format, with `C` bound to a type implementing
+/// [CompressedCapability](crate::CompressedCapability).
+///
+/// ```
+/// use rust_cheri_compressed_cap::{for_each_cap_format, CompressedCapability};
+///
+/// for_each_cap_format!(|C| {
+///     let cap = C::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+///     assert!(cap.is_exact());
+/// });
+/// ```
+///
+/// Despite the closure-like syntax, `C` is a *type* and not a value.
+/// The body is pasted into a new block for each format, so it must type-check independently for every format
+/// (e.g. integer literals are inferred as `C::Addr` = `u32` for 64-bit capabilities and `u64` for 128-bit capabilities).
+/// The body can't `return` a value, and any values it produces are discarded.
+///
+/// Expands for every implemented format: CHERIv9 64- and 128-bit, Morello, and RISC-V Y 64- and 128-bit.
+#[macro_export]
+macro_rules! for_each_cap_format {
+    (|$cap_ty:ident| $body:block) => {{
+        {
+            #[allow(dead_code)]
+            type $cap_ty = $crate::caps::cheriv9::cc64::Cc64;
+            $body
+        }
+        {
+            #[allow(dead_code)]
+            type $cap_ty = $crate::caps::cheriv9::cc128::Cc128;
+            $body
+        }
+        {
+            #[allow(dead_code)]
+            type $cap_ty = $crate::caps::morello::Cc128;
+            $body
+        }
+        {
+            #[allow(dead_code)]
+            type $cap_ty = $crate::caps::rvy::cc64::Cc64;
+            $body
+        }
+        {
+            #[allow(dead_code)]
+            type $cap_ty = $crate::caps::rvy::cc128::Cc128;
+            $body
+        }
+    }};
+}
+
+/// Deterministic pseudo-random numbers, so randomised tests are reproducible without depending on a RNG crate.
+///
+/// This is a 64-bit linear congruential generator with Knuth's MMIX constants: it's fast, but the low bits are
+/// poor, so take the high bits (e.g. `next_u64() >> 56` for a byte) when fewer than 64 are needed.
+#[derive(Debug, Clone)]
+pub struct Lcg {
+    state: u64,
+}
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg { state: seed }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.state
+    }
+}
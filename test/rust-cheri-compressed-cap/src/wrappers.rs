@@ -1,9 +1,12 @@
 //! Defines easy-to-use wrapper traits for [CompressedCapability] implementations
 
 use std::convert::TryInto;
+use std::ops::{Deref, DerefMut};
+use crate::CInvokeError;
 use crate::CcxCap;
 use crate::CompressedCapability;
-use num_traits::WrappingAdd;
+use crate::OffsetError;
+use num_traits::{WrappingAdd, WrappingSub};
 
 /// Trait exposing the utility functions used to specify CHERI-RISC-V behaviour in Tech Report 951.
 /// Behaviour is derived from [the Sail specification](https://github.com/CTSRD-CHERI/sail-cheri-riscv)
@@ -25,10 +28,20 @@ pub trait CheriRVFuncs<T: CompressedCapability> {
     fn getCapLength(c: &Self::Cap) -> Self::CapLen;
     fn inCapBounds(c: &Self::Cap, addr: Self::CapAddrBits, size: Self::CapLen) -> bool;
     fn getCapCursor(c: &Self::Cap) -> Self::CapAddrInt;
-    fn getCapOffsetBits(c: &Self::Cap) -> Self::CapAddrBits;
+    /// The offset of the cursor from the base, or an error if it doesn't fit in [Self::CapAddrBits]
+    fn getCapOffsetBits(c: &Self::Cap) -> Result<Self::CapAddrBits, OffsetError>;
+    /// The offset of the cursor from the base truncated to [Self::CapAddrBits], as in the Sail
+    fn getCapOffsetBitsWrapping(c: &Self::Cap) -> Self::CapAddrBits;
+    /// The exponent `E` of the bounds encoding, decoded from the pesbt.
+    ///
+    /// Small capabilities which don't use the internal exponent (e.g. CC128 lengths below `0x1000`) report 0.
+    fn getCapExponent(c: &Self::Cap) -> u8;
 
     // These can return (false, cap); `cap` may not preserve bounds (???)
     fn setCapBounds(c: &Self::Cap, base: Self::CapAddrBits, top: Self::CapLen) -> (bool, Self::Cap);
+    /// [Self::setCapBounds], but only returns `Ok` if the bounds were set exactly.
+    /// Otherwise the capability with the rounded bounds is returned in `Err`, for callers which can accept it.
+    fn setCapBoundsExact(c: &Self::Cap, base: Self::CapAddrBits, top: Self::CapLen) -> Result<Self::Cap, Self::Cap>;
     fn setCapAddr(c: &Self::Cap, addr: Self::CapAddrBits) -> (bool, Self::Cap);
     fn setCapOffset(c: &Self::Cap, offset: Self::CapAddrBits) -> (bool, Self::Cap);
     fn incCapOffset(c: &Self::Cap, offset_inc: Self::CapAddrBits) -> (bool, Self::Cap);
@@ -38,9 +51,14 @@ pub trait CheriRVFuncs<T: CompressedCapability> {
 
     fn sealCap(c: &Self::Cap, otype: Self::OType) -> Self::Cap;
     fn unsealCap(c: &Self::Cap) -> Self::Cap;
+    /// Seals an unsealed capability as a sentry (otype [CompressedCapability::OTYPE_SENTRY]).
+    /// Already-sealed capabilities (including sentries) are returned unchanged.
+    fn sealCapAsSentry(c: &Self::Cap) -> Self::Cap;
     fn isCapSealed(c: &Self::Cap) -> bool;
     fn hasReservedOType(c: &Self::Cap) -> bool;
     fn invalidateCap(c: &Self::Cap) -> Self::Cap;
+    /// The checks made by `CInvoke` on a sealed (code, data) pair, returning the unsealed pair if they pass
+    fn checkCInvoke(code: &Self::Cap, data: &Self::Cap) -> Result<(Self::Cap, Self::Cap), CInvokeError>;
 
     fn getCapPerms(c: &Self::Cap) -> Self::Perms;
     fn setCapPerms(c: &Self::Cap, perms: Self::Perms) -> Self::Cap;
@@ -71,24 +89,38 @@ impl<T: CompressedCapability> CheriRVFuncs<T> for T where T::Offset: TryInto<T::
         c.length()
     }
     fn inCapBounds(c: &Self::Cap, addr: Self::CapAddrBits, size: Self::CapLen) -> bool {
-        addr >= c.base() && (size + addr.into()) <= c.top()
+        c.is_in_bounds(addr, size)
     }
     fn getCapCursor(c: &Self::Cap) -> Self::CapAddrInt {
         c.address()
     }
-    fn getCapOffsetBits(c: &Self::Cap) -> Self::CapAddrBits {
-        match c.offset().try_into() {
-            Ok(val) => val,
-            Err(_) => panic!("getCapOffsetBits can't convert to address")
+    fn getCapOffsetBits(c: &Self::Cap) -> Result<Self::CapAddrBits, OffsetError> {
+        if c.address() < c.base() {
+            return Err(OffsetError::Negative);
         }
+        c.offset().try_into().map_err(|_| OffsetError::OutOfRange)
+    }
+    fn getCapOffsetBitsWrapping(c: &Self::Cap) -> Self::CapAddrBits {
+        c.address().wrapping_sub(&c.base())
+    }
+    fn getCapExponent(c: &Self::Cap) -> u8 {
+        // Not CcxCap::exponent(), which is only filled in by the C library on decode
+        c.extract_bounds_bits().exponent()
     }
 
     // These can return (false, cap); `cap` may not preserve bounds (???)
     fn setCapBounds(c: &Self::Cap, base: Self::CapAddrBits, top: Self::CapLen) -> (bool, Self::Cap) {
         let mut c = *c;
         c.set_address_unchecked(base);
-        c.set_bounds_unchecked(top - (base.into()));
-        (c.is_exact(), c)
+        // The decoded bounds are always exactly representable, so is_exact() can't tell if they were rounded
+        let exact = c.set_bounds_unchecked(top - (base.into()));
+        (exact, c)
+    }
+    fn setCapBoundsExact(c: &Self::Cap, base: Self::CapAddrBits, top: Self::CapLen) -> Result<Self::Cap, Self::Cap> {
+        match Self::setCapBounds(c, base, top) {
+            (true, c) => Ok(c),
+            (false, c) => Err(c),
+        }
     }
     fn setCapAddr(c: &Self::Cap, addr: Self::CapAddrBits) -> (bool, Self::Cap) {
         // This deviates from the Sail - Sail checks validity by recomputing 
@@ -138,6 +170,12 @@ impl<T: CompressedCapability> CheriRVFuncs<T> for T where T::Offset: TryInto<T::
         c.set_otype(T::OTYPE_UNSEALED);
         c
     }
+    fn sealCapAsSentry(c: &Self::Cap) -> Self::Cap {
+        if c.is_sealed() {
+            return *c;
+        }
+        Self::sealCap(c, T::OTYPE_SENTRY)
+    }
     fn isCapSealed(c: &Self::Cap) -> bool {
         c.is_sealed()
     }
@@ -149,6 +187,32 @@ impl<T: CompressedCapability> CheriRVFuncs<T> for T where T::Offset: TryInto<T::
         c.set_tag(false);
         c
     }
+    fn checkCInvoke(code: &Self::Cap, data: &Self::Cap) -> Result<(Self::Cap, Self::Cap), CInvokeError> {
+        // Same order as the Sail
+        if !code.tag() {
+            Err(CInvokeError::CodeUntagged)
+        } else if !data.tag() {
+            Err(CInvokeError::DataUntagged)
+        } else if !Self::isCapSealed(code) {
+            Err(CInvokeError::CodeUnsealed)
+        } else if !Self::isCapSealed(data) {
+            Err(CInvokeError::DataUnsealed)
+        } else if code.otype() != data.otype() {
+            Err(CInvokeError::OTypeMismatch)
+        } else if !code.has_perms(T::PERM_CINVOKE) {
+            Err(CInvokeError::CodeMissingCInvoke)
+        } else if !data.has_perms(T::PERM_CINVOKE) {
+            Err(CInvokeError::DataMissingCInvoke)
+        } else if !code.has_perms(T::PERM_EXECUTE) {
+            Err(CInvokeError::CodeMissingExecute)
+        } else if data.has_perms(T::PERM_EXECUTE) {
+            Err(CInvokeError::DataHasExecute)
+        } else if Self::hasReservedOType(code) {
+            Err(CInvokeError::ReservedOType)
+        } else {
+            Ok((Self::unsealCap(code), Self::unsealCap(data)))
+        }
+    }
 
     fn getCapPerms(c: &Self::Cap) -> Self::Perms {
         c.permissions()
@@ -168,4 +232,308 @@ impl<T: CompressedCapability> CheriRVFuncs<T> for T where T::Offset: TryInto<T::
         c.set_flags(flags);
         c
     }
-}
\ No newline at end of file
+}
+
+/// Wraps a [CcxCap] with a dirty flag, for tracking whether e.g. a capability register has been modified.
+///
+/// Read-only accessors are forwarded transparently through [Deref].
+/// Any mutable access (through [DerefMut], [Self::set]) marks the capability as dirty,
+/// even if the new value happens to be the same as the old one.
+#[derive(Debug, Copy, Clone)]
+pub struct WatchedCap<T: CompressedCapability> {
+    cap: CcxCap<T>,
+    dirty: bool,
+}
+impl<T: CompressedCapability> WatchedCap<T> {
+    /// Wraps `cap`, initially clean.
+    pub fn new(cap: CcxCap<T>) -> Self {
+        WatchedCap { cap, dirty: false }
+    }
+
+    /// Replaces the wrapped capability, marking it as dirty.
+    pub fn set(&mut self, cap: CcxCap<T>) {
+        self.cap = cap;
+        self.dirty = true;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// If the capability has been modified since it was wrapped or last taken,
+    /// returns a copy of it and marks it clean again.
+    pub fn take_if_dirty(&mut self) -> Option<CcxCap<T>> {
+        if self.dirty {
+            self.dirty = false;
+            Some(self.cap)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner(self) -> CcxCap<T> {
+        self.cap
+    }
+}
+impl<T: CompressedCapability> Deref for WatchedCap<T> {
+    type Target = CcxCap<T>;
+
+    fn deref(&self) -> &CcxCap<T> {
+        &self.cap
+    }
+}
+impl<T: CompressedCapability> DerefMut for WatchedCap<T> {
+    fn deref_mut(&mut self) -> &mut CcxCap<T> {
+        self.dirty = true;
+        &mut self.cap
+    }
+}
+impl<T: CompressedCapability> From<CcxCap<T>> for WatchedCap<T> {
+    fn from(cap: CcxCap<T>) -> Self {
+        Self::new(cap)
+    }
+}
+
+/// Defines a typed wrapper around a `(tag, [cursor, pesbt])` tuple, see [MemRepr] and [RegRepr].
+macro_rules! cap_repr {
+    ($(#[$attr:meta])* $name:ident, $decompress:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub struct $name<T: CompressedCapability> {
+            tag: bool,
+            words: [T::Addr; 2],
+        }
+        impl<T: CompressedCapability> $name<T> {
+            /// Wraps a tag and `[cursor, pesbt]` words, e.g. read back from storage
+            pub fn new(tag: bool, words: [T::Addr; 2]) -> Self {
+                $name { tag, words }
+            }
+
+            pub fn tag(&self) -> bool {
+                self.tag
+            }
+
+            /// Returns the `[cursor, pesbt]` words
+            pub fn words(&self) -> [T::Addr; 2] {
+                self.words
+            }
+
+            #[doc = concat!("Decompresses the capability with [CompressedCapability::", stringify!($decompress), "]")]
+            pub fn decompress(&self) -> CcxCap<T> {
+                T::$decompress(self.words[1], self.words[0], self.tag)
+            }
+        }
+    };
+}
+
+cap_repr!(
+    /// The in-memory representation of a capability, as produced by [CcxCap::to_mem].
+    ///
+    /// Equivalent to [CcxCap::mem_representation], but the type ensures it can only be decompressed
+    /// with [CompressedCapability::decompress_mem] and not mixed up with a [RegRepr]:
+    ///
+    /// ```compile_fail
+    /// use rust_cheri_compressed_cap::caps::cheriv9::cc128::Cc128;
+    /// use rust_cheri_compressed_cap::wrappers::RegRepr;
+    /// use rust_cheri_compressed_cap::CompressedCapability;
+    ///
+    /// let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+    /// let reg: RegRepr<Cc128> = cap.to_mem();
+    /// ```
+    MemRepr,
+    decompress_mem
+);
+cap_repr!(
+    /// The in-register representation of a capability, as produced by [CcxCap::to_reg].
+    ///
+    /// Equivalent to [CcxCap::reg_representation], but the type ensures it can only be decompressed
+    /// with [CompressedCapability::decompress_raw] and not mixed up with a [MemRepr].
+    RegRepr,
+    decompress_raw
+);
+
+#[cfg(test)]
+mod tests {
+    use super::{MemRepr, RegRepr, WatchedCap};
+    use crate::caps::cheriv9::cc128::Cc128;
+    use crate::CompressedCapability;
+
+    #[test]
+    fn test_watched_cap_reads_dont_dirty() {
+        let mut watched = WatchedCap::new(Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000));
+        assert_eq!(watched.base(), 0x1000);
+        assert_eq!(watched.top(), 0x2000);
+        assert!(watched.tag());
+        let _ = watched.permissions();
+        assert!(!watched.is_dirty());
+        assert_eq!(watched.take_if_dirty(), None);
+    }
+
+    #[test]
+    fn test_watched_cap_writes_dirty() {
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let mut watched = WatchedCap::new(cap);
+        watched.set_address_unchecked(0x1800);
+        assert!(watched.is_dirty());
+
+        let taken = watched.take_if_dirty().unwrap();
+        assert_eq!(taken.address(), 0x1800);
+        // Taking resets the flag
+        assert!(!watched.is_dirty());
+        assert_eq!(watched.take_if_dirty(), None);
+
+        watched.set(cap);
+        assert_eq!(watched.take_if_dirty(), Some(cap));
+    }
+
+    #[test]
+    fn test_in_cap_bounds_no_overflow() {
+        use super::CheriRVFuncs;
+        use crate::caps::cheriv9::cc64::Cc64;
+
+        let cap = Cc64::make_max_perms_cap(0, 0, 1 << 32);
+        assert!(Cc64::inCapBounds(&cap, 0xffff_fff0, 0x10));
+        assert!(!Cc64::inCapBounds(&cap, 0xffff_fff0, 0x11));
+        // addr + size doesn't fit in the 64-bit CapLen
+        assert!(!Cc64::inCapBounds(&cap, 1, u64::MAX));
+    }
+
+    #[test]
+    fn test_get_cap_offset_bits() {
+        use super::CheriRVFuncs;
+        use crate::caps::cheriv9::cc64::Cc64;
+        use crate::OffsetError;
+
+        let cap = Cc64::make_max_perms_cap(0x1000, 0x1800, 0x2000);
+        assert_eq!(Cc64::getCapOffsetBits(&cap), Ok(0x800));
+        assert_eq!(Cc64::getCapOffsetBitsWrapping(&cap), 0x800);
+
+        // Cursor below the base: the i64 offset is negative, so doesn't fit in the u32 address
+        let mut below = cap;
+        below.set_address_unchecked(0xff0);
+        assert_eq!(below.offset(), -0x10);
+        assert_eq!(Cc64::getCapOffsetBits(&below), Err(OffsetError::Negative));
+        assert_eq!(Cc64::getCapOffsetBitsWrapping(&below), 0xffff_fff0);
+
+        let mut below = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        below.set_address_unchecked(0);
+        assert_eq!(Cc128::getCapOffsetBits(&below), Err(OffsetError::Negative));
+        assert_eq!(Cc128::getCapOffsetBitsWrapping(&below), 0u64.wrapping_sub(0x1000));
+    }
+
+    #[test]
+    fn test_get_cap_exponent() {
+        use super::CheriRVFuncs;
+        use crate::caps::cheriv9::cc64::Cc64;
+        use crate::CcxCap;
+
+        // Small caps don't use the internal exponent
+        let small = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x1800);
+        assert_eq!(Cc128::getCapExponent(&small), 0);
+        assert!(!small.extract_bounds_bits().internal_exponent());
+        assert_eq!(Cc128::getCapExponent(&CcxCap::default()), 0);
+
+        for shift in 0..52 {
+            let cap = Cc128::make_max_perms_cap(0, 0, 0x1000 << shift);
+            let expected = Cc128::extract_bounds_bits(Cc128::compress_raw(&cap)).exponent();
+            assert_eq!(Cc128::getCapExponent(&cap), expected);
+            assert_eq!(Cc128::getCapExponent(&cap), cap.exponent());
+            assert_eq!(Cc128::getCapExponent(&cap), shift);
+        }
+        for shift in 0..24 {
+            let cap = Cc64::make_max_perms_cap(0, 0, 0x100 << shift);
+            let expected = Cc64::extract_bounds_bits(Cc64::compress_raw(&cap)).exponent();
+            assert_eq!(Cc64::getCapExponent(&cap), expected);
+        }
+        assert_eq!(Cc128::getCapExponent(&CcxCap::root()), 52);
+    }
+
+    #[test]
+    fn test_set_cap_bounds_exact() {
+        use super::CheriRVFuncs;
+
+        let parent = Cc128::make_max_perms_cap(0x10_0000, 0x10_0000, 0x20_0000);
+
+        let exact = Cc128::setCapBoundsExact(&parent, 0x10_4000, 0x10_8000).unwrap();
+        assert_eq!(exact.bounds(), (0x10_4000, 0x10_8000));
+        assert!(exact.tag());
+        assert_eq!(Cc128::setCapBounds(&parent, 0x10_4000, 0x10_8000), (true, exact));
+
+        // Too long to be represented with byte granularity
+        let rounded = Cc128::setCapBoundsExact(&parent, 0x10_0001, 0x10_2002).unwrap_err();
+        let (base, top) = rounded.bounds();
+        assert!(base < 0x10_0001 && top > 0x10_2002);
+        assert_eq!(Cc128::setCapBounds(&parent, 0x10_0001, 0x10_2002), (false, rounded));
+    }
+
+    #[test]
+    fn test_check_cinvoke() {
+        use super::CheriRVFuncs;
+        use crate::CInvokeError;
+
+        let seal = |perms: u32, otype: u32| {
+            let mut cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+            cap.set_permissions(perms);
+            Cc128::sealCap(&cap, otype)
+        };
+        let code = seal(Cc128::PERM_EXECUTE | Cc128::PERM_CINVOKE | Cc128::PERM_LOAD, 10);
+        let data = seal(Cc128::PERM_CINVOKE | Cc128::PERM_LOAD | Cc128::PERM_STORE, 10);
+
+        let (unsealed_code, unsealed_data) = Cc128::checkCInvoke(&code, &data).unwrap();
+        assert!(!unsealed_code.is_sealed() && !unsealed_data.is_sealed());
+        assert!(unsealed_code.tag() && unsealed_data.tag());
+        assert_eq!(unsealed_code.bounds(), code.bounds());
+        assert_eq!(unsealed_data.permissions(), data.permissions());
+
+        let check = |code: &crate::CcxCap<Cc128>, data: &crate::CcxCap<Cc128>| Cc128::checkCInvoke(code, data).unwrap_err();
+        assert_eq!(check(&Cc128::invalidateCap(&code), &data), CInvokeError::CodeUntagged);
+        assert_eq!(check(&code, &Cc128::invalidateCap(&data)), CInvokeError::DataUntagged);
+        assert_eq!(check(&Cc128::unsealCap(&code), &data), CInvokeError::CodeUnsealed);
+        assert_eq!(check(&code, &Cc128::unsealCap(&data)), CInvokeError::DataUnsealed);
+        assert_eq!(check(&code, &Cc128::sealCap(&data, 11)), CInvokeError::OTypeMismatch);
+        assert_eq!(check(&seal(Cc128::PERM_EXECUTE, 10), &data), CInvokeError::CodeMissingCInvoke);
+        assert_eq!(check(&code, &seal(Cc128::PERM_LOAD, 10)), CInvokeError::DataMissingCInvoke);
+        assert_eq!(check(&seal(Cc128::PERM_CINVOKE, 10), &data), CInvokeError::CodeMissingExecute);
+        assert_eq!(check(&code, &code), CInvokeError::DataHasExecute);
+        let sentry = Cc128::OTYPE_SENTRY;
+        assert_eq!(
+            check(&Cc128::sealCap(&code, sentry), &Cc128::sealCap(&data, sentry)),
+            CInvokeError::ReservedOType
+        );
+    }
+
+    #[test]
+    fn test_seal_cap_as_sentry() {
+        use super::CheriRVFuncs;
+
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1000, 0x2000);
+        let sentry = Cc128::sealCapAsSentry(&cap);
+        assert!(sentry.is_sentry() && sentry.is_sealed());
+        assert_eq!(sentry.otype(), Cc128::OTYPE_SENTRY);
+        assert_eq!(Cc128::unsealCap(&sentry), cap);
+
+        // Already sealed: unchanged
+        let sealed = Cc128::sealCap(&cap, 10);
+        assert_eq!(Cc128::sealCapAsSentry(&sealed), sealed);
+        assert_eq!(Cc128::sealCapAsSentry(&sentry), sentry);
+    }
+
+    #[test]
+    fn test_mem_reg_repr_roundtrip() {
+        let cap = Cc128::make_max_perms_cap(0x1000, 0x1800, 0x2000);
+
+        let mem: MemRepr<Cc128> = cap.to_mem();
+        let reg: RegRepr<Cc128> = cap.to_reg();
+        assert_eq!(mem.decompress(), cap);
+        assert_eq!(reg.decompress(), cap);
+        assert_eq!((mem.tag(), mem.words()), cap.mem_representation());
+        assert_eq!((reg.tag(), reg.words()), cap.reg_representation());
+
+        // CHERIv9 XORs the pesbt in memory, so the two representations really are different
+        assert_ne!(mem.words(), reg.words());
+        assert_ne!(Cc128::decompress_raw(mem.words()[1], mem.words()[0], false), cap.with_tag(false));
+
+        // Rebuilding from stored words
+        assert_eq!(MemRepr::<Cc128>::new(false, mem.words()).decompress(), cap.with_tag(false));
+    }
+}
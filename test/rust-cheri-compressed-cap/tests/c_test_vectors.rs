@@ -0,0 +1,103 @@
+//! Decoding test vectors from the C library's tests (test/simple_test_*.cpp),
+//! checking the Rust view of the decompressed C struct matches what the C tests expect.
+//!
+//! Where the C tests only give a capability's bounds, the pesbt is the one the C library produces for them,
+//! with the hardware and user permission fields all ones and the otype field as given.
+
+use rust_cheri_compressed_cap::caps::cheriv9::{cc128::Cc128, cc64::Cc64};
+use rust_cheri_compressed_cap::CompressedCapability;
+
+/// `(pesbt, cursor, tag, expected_base, expected_top, expected_perms, expected_otype)`,
+/// where the pesbt is the raw (not in-memory) encoding passed to [CompressedCapability::decompress_raw].
+type TestVector<T> = (
+    <T as CompressedCapability>::Addr,
+    <T as CompressedCapability>::Addr,
+    bool,
+    <T as CompressedCapability>::Addr,
+    <T as CompressedCapability>::Length,
+    u32,
+    u32,
+);
+
+const CC128_VECTORS: &[TestVector<Cc128>] = &[
+    // NULL, i.e. CC128_NULL_PESBT: maximum length and the maximum exponent, with no permissions
+    (0x0000_1fff_fc01_8004, 0, false, 0, 1 << 64, 0, Cc128::OTYPE_UNSEALED),
+    // The reset capability, i.e. CC128_RESET_PESBT: maximum length with all permissions
+    (0xffff_1fff_fc01_8004, 0, true, 0, 1 << 64, 0xfff, Cc128::OTYPE_UNSEALED),
+    (0xffff_0091_a401_8004, 0, true, 0, 1 << 64, 0xfff, 0x1234),
+    // QEMU regression $c17: b:00000000401cf020 l:0000000000001800, with the internal exponent set
+    (0xffff_1fff_fe08_3020, 0x401d_0820, true, 0x401c_f020, 0x401d_0820, 0xfff, Cc128::OTYPE_UNSEALED),
+    (0xffff_0091_a608_3020, 0x401d_0820, true, 0x401c_f020, 0x401d_0820, 0xfff, 0x1234),
+    // QEMU regression $c18: b:00000000401ffff8 l:0000000000000008, with no internal exponent
+    (0xffff_1fff_f800_3ff8, 0x4020_0000, true, 0x401f_fff8, 0x4020_0000, 0xfff, Cc128::OTYPE_UNSEALED),
+    (0xffff_0091_a000_3ff8, 0x4020_0000, true, 0x401f_fff8, 0x4020_0000, 0xfff, 0x1234),
+    // QEMU regression from a broken cincoffset: b:00000000000b7fcc l:00000000000000e1 o:52
+    (0xffff_1fff_f82b_7fcc, 0x000b_8000, true, 0x000b_7fcc, 0x000b_80ad, 0xfff, Cc128::OTYPE_UNSEALED),
+    (0xffff_0091_a02b_7fcc, 0x000b_8000, true, 0x000b_7fcc, 0x000b_80ad, 0xfff, 0x1234),
+    // A larger power-of-two region, with the internal exponent set
+    (0xffff_1fff_fc00_4004, 0x1080_0000, true, 0x1000_0000, 0x1100_0000, 0xfff, Cc128::OTYPE_UNSEALED),
+    (0xffff_0091_a400_4004, 0x1080_0000, true, 0x1000_0000, 0x1100_0000, 0xfff, 0x1234),
+    // "Reprentability with TOP>MAX_TOP": valid at the original cursor...
+    (
+        0xffff_1fff_fffe_7ffb,
+        0xffff_002d_0201_3ff6,
+        true,
+        0xffff_002d_01ff_c000,
+        0xffff_002d_027f_c000,
+        0xfff,
+        Cc128::OTYPE_UNSEALED,
+    ),
+    // ...but decoding with a zero cursor gives a top above the maximum
+    (
+        0xffff_1fff_fffe_7ffb,
+        0,
+        false,
+        0xffff_ffff_ffff_c000,
+        (1 << 64) | 0x7f_c000,
+        0xfff,
+        Cc128::OTYPE_UNSEALED,
+    ),
+];
+
+const CC64_VECTORS: &[TestVector<Cc64>] = &[
+    // NULL, i.e. CC64_NULL_PESBT: maximum length and the maximum exponent, with no permissions
+    (0x0007_c302, 0, false, 0, 1 << 32, 0, Cc64::OTYPE_UNSEALED),
+    // The reset capability, i.e. CC64_RESET_PESBT: maximum length with all permissions
+    (0xfff7_c302, 0, true, 0, 1 << 32, 0xfff, Cc64::OTYPE_UNSEALED),
+    (0xfff2_c302, 0, true, 0, 1 << 32, 0xfff, 5),
+    // A small region, with the internal exponent set
+    (0xfff7_c002, 0x1010, true, 0x1000, 0x1100, 0xfff, Cc64::OTYPE_UNSEALED),
+    (0xfff2_c002, 0x1010, true, 0x1000, 0x1100, 0xfff, 5),
+    // A larger power-of-two region, with the internal exponent set
+    (0xfff7_c146, 0x18_0000, true, 0x10_0000, 0x20_0000, 0xfff, Cc64::OTYPE_UNSEALED),
+    (0xfff2_c146, 0x18_0000, true, 0x10_0000, 0x20_0000, 0xfff, 5),
+    // "Fast representability mismatch 1" and "2": zero-length, with no internal exponent.
+    // The otype field is zero, so the second is sealed with otype 0.
+    (0, 8, false, 0, 0, 0, 0),
+    (0x0a00_2020, 0xa, false, 0x20, 0x20, 0xa0, 0),
+];
+
+fn check_vectors<T: CompressedCapability>(vectors: &[TestVector<T>]) {
+    for &(pesbt, cursor, tag, base, top, perms, otype) in vectors {
+        let cap = T::decompress_raw(pesbt, cursor, tag);
+        let context = format!("pesbt={:#x} cursor={:#x}: {:?}", pesbt, cursor, cap);
+        assert_eq!(cap.address(), cursor, "{}", context);
+        assert_eq!(cap.tag(), tag, "{}", context);
+        assert_eq!(cap.base(), base, "{}", context);
+        assert_eq!(cap.top(), top, "{}", context);
+        assert_eq!(cap.permissions(), perms, "{}", context);
+        assert_eq!(cap.otype(), otype, "{}", context);
+        // Re-encoding must give back the same bits
+        assert_eq!(T::compress_raw(&cap), pesbt, "{}", context);
+    }
+}
+
+#[test]
+fn test_cc128_vectors() {
+    check_vectors::<Cc128>(CC128_VECTORS);
+}
+
+#[test]
+fn test_cc64_vectors() {
+    check_vectors::<Cc64>(CC64_VECTORS);
+}
@@ -107,3 +107,18 @@ TEST_CASE("Invalid exponent caps should never be representable 2", "[fuzz]") {
     CHECK(modified_cap.top() == (expected_top | 0xff00000000000000));
     CHECK(modified_cap.cr_exp == 40);
 }
+
+TEST_CASE("Old permissions API accepts PERM_GLOBAL", "[perms]") {
+    // CC128M_PERMS_ALL used to clear bits 0-3 (the unshifted UPERMS_ALL) instead of the user permissions in
+    // bits 2-5, so update_perms asserted on any value including PERM_GLOBAL.
+    CHECK((CC128M_PERMS_ALL & CC128M_PERM_SW_ALL) == 0);
+    CHECK((CC128M_PERMS_ALL & CC128M_PERM_GLOBAL) == CC128M_PERM_GLOBAL);
+    TestAPICC::cap_t max_cap = TestAPICC::make_max_perms_cap(0, 0, _CC_MAX_TOP);
+    _cc_addr_t perms = CC128M_PERM_GLOBAL | CC128M_PERM_LOAD;
+    _cc_N(update_perms)(&max_cap, perms);
+    CHECK(_cc_N(get_perms)(&max_cap) == perms);
+    CHECK(max_cap.software_permissions() == _CC_N(UPERMS_ALL));
+    // All architectural permissions can be restored
+    _cc_N(update_perms)(&max_cap, CC128M_PERMS_ALL);
+    CHECK(max_cap.all_permissions() == CC128M_PERMS_MASK);
+}